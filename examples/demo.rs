@@ -3,8 +3,10 @@ use std::{cell::RefCell, collections::HashMap};
 use eframe::App;
 use egui::{pos2, Color32, InnerResponse, Ui};
 use egui_snarl::{
-    ui::{Effects, Forbidden, InPin, OutPin, PinInfo, SnarlStyle, SnarlViewer},
-    InPinId, Snarl,
+    ui::{
+        Effects, GridStyle, InPin, NodeContext, OutPin, PinInfo, SnarlStyle, SnarlViewer, WireStyle,
+    },
+    InPinId, NodeId, Snarl, SnarlComment,
 };
 
 #[derive(Clone)]
@@ -31,17 +33,35 @@ enum DemoNode {
 struct DemoViewer;
 
 impl SnarlViewer<DemoNode> for DemoViewer {
-    fn node_picker(&mut self, _ui: &mut Ui) -> egui::InnerResponse<Option<DemoNode>> {
-        todo!()
+    fn node_picker(&mut self, ui: &mut Ui) -> egui::InnerResponse<Option<DemoNode>> {
+        ui.vertical(|ui| {
+            ui.label("Add node");
+            if ui.button("Integer").clicked() {
+                return Some(DemoNode::Integer(0));
+            }
+            if ui.button("String").clicked() {
+                return Some(DemoNode::String("".to_owned()));
+            }
+            if ui.button("Expression").clicked() {
+                return Some(DemoNode::ExprNode(ExprNode::new()));
+            }
+            if ui.button("Show").clicked() {
+                return Some(DemoNode::Show("".to_owned()));
+            }
+            if ui.button("Sink").clicked() {
+                return Some(DemoNode::Sink);
+            }
+            None
+        })
     }
 
-    #[inline]
-    fn connect(
-        &mut self,
-        from: &OutPin<DemoNode>,
-        to: &InPin<DemoNode>,
-        effects: &mut Effects<DemoNode>,
-    ) -> Result<(), Forbidden> {
+    fn graph_menu(&mut self, pos: egui::Pos2, ui: &mut Ui, effects: &mut Effects<DemoNode>) {
+        if let Some(node) = self.node_picker(ui).inner {
+            effects.add_node(node, pos);
+        }
+    }
+
+    fn can_connect(&self, from: &OutPin<DemoNode>, to: &InPin<DemoNode>) -> bool {
         // Validate connection
         match (&*from.node.borrow(), &*to.node.borrow()) {
             (DemoNode::Sink, _) => {
@@ -53,39 +73,65 @@ impl SnarlViewer<DemoNode> for DemoViewer {
             (_, DemoNode::String(_)) => {
                 unreachable!("String node has no inputs")
             }
-            (DemoNode::Integer(_), DemoNode::Show(_)) => {
-                return Err(Forbidden);
-            }
-            (DemoNode::Show(_), DemoNode::Show(_)) => {
-                return Err(Forbidden);
-            }
-            (_, DemoNode::Sink) => {}
-            (DemoNode::String(_), DemoNode::Show(_)) => {}
-            (DemoNode::ExprNode(_), DemoNode::ExprNode(_)) => {}
-            (DemoNode::Integer(_), DemoNode::ExprNode(_)) => {}
-            (DemoNode::String(_), DemoNode::ExprNode(_)) => {
-                return Err(Forbidden);
-            }
-            (DemoNode::Show(_), DemoNode::ExprNode(_)) => {
-                return Err(Forbidden);
-            }
-            (DemoNode::ExprNode(_), DemoNode::Show(_)) => {
-                return Err(Forbidden);
-            }
+            (DemoNode::Integer(_), DemoNode::Show(_)) => false,
+            (DemoNode::Show(_), DemoNode::Show(_)) => false,
+            (_, DemoNode::Sink) => true,
+            (DemoNode::String(_), DemoNode::Show(_)) => true,
+            (DemoNode::ExprNode(_), DemoNode::ExprNode(_)) => true,
+            (DemoNode::Integer(_), DemoNode::ExprNode(_)) => true,
+            (DemoNode::String(_), DemoNode::ExprNode(_)) => false,
+            (DemoNode::Show(_), DemoNode::ExprNode(_)) => false,
+            (DemoNode::ExprNode(_), DemoNode::Show(_)) => false,
         }
+    }
 
-        for remote in &to.remotes {
-            effects.disconnect(remote.id, to.id);
+    fn node_menu(&mut self, node: NodeId, ui: &mut Ui, effects: &mut Effects<DemoNode>) {
+        if ui.button("Disconnect").clicked() {
+            effects.drop_node_wires(node);
         }
+        if ui.button("Remove").clicked() {
+            effects.remove_node(node);
+        }
+    }
 
-        effects.connect(from.id, to.id);
-        Ok(())
+    fn wire_color(
+        &mut self,
+        from: &OutPin<DemoNode>,
+        _to: &InPin<DemoNode>,
+        blend: Color32,
+    ) -> Color32 {
+        match &*from.node.borrow() {
+            DemoNode::Integer(_) => Color32::RED,
+            DemoNode::String(_) => Color32::GREEN,
+            _ => blend,
+        }
     }
 
     fn size_hint(&self, _node: &DemoNode) -> egui::Vec2 {
         egui::vec2(130.0, 50.0)
     }
 
+    fn resizable(&self, node: &DemoNode) -> bool {
+        // Show node may display an image.
+        matches!(node, DemoNode::Show(_))
+    }
+
+    fn body_max_height(&self, node: &DemoNode) -> Option<f32> {
+        // Expressions with many bindings scroll instead of growing.
+        match node {
+            DemoNode::ExprNode(_) => Some(200.0),
+            _ => None,
+        }
+    }
+
+    fn node_frame(&mut self, node: &DemoNode, default: egui::Frame) -> egui::Frame {
+        match node {
+            // Sinks stand out as the ends of the graph.
+            DemoNode::Sink => default.fill(Color32::from_rgb(40, 50, 70)),
+            _ => default,
+        }
+    }
+
     fn title(&mut self, node: &DemoNode) -> &str {
         match node {
             DemoNode::Sink => "Sink",
@@ -96,9 +142,30 @@ impl SnarlViewer<DemoNode> for DemoViewer {
         }
     }
 
+    fn show_header(
+        &mut self,
+        _id: NodeId,
+        node: &RefCell<DemoNode>,
+        ui: &mut Ui,
+        _effects: &mut Effects<DemoNode>,
+    ) {
+        // Status dot tells if the expression is valid.
+        if let DemoNode::ExprNode(expr_node) = &*node.borrow() {
+            let color = match syn::parse_str::<Expr>(&expr_node.text) {
+                Ok(_) => Color32::GREEN,
+                Err(_) => Color32::RED,
+            };
+            let size = egui::Vec2::splat(ui.spacing().icon_width * 0.5);
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            ui.painter()
+                .circle_filled(rect.center(), size.x / 2.0, color);
+        }
+        ui.label(self.title(&node.borrow()));
+    }
+
     fn show_content(
         &mut self,
-        node_idx: usize,
+        ctx: &NodeContext,
         node: &RefCell<DemoNode>,
         inputs: &[InPin<DemoNode>],
         _outputs: &[OutPin<DemoNode>],
@@ -109,50 +176,46 @@ impl SnarlViewer<DemoNode> for DemoViewer {
             DemoNode::ExprNode(expr_node) => {
                 let r = ui.text_edit_singleline(&mut expr_node.text);
 
-                match syn::parse_str(&expr_node.text) {
-                    Ok(expr) => {
-                        expr_node.expr = expr;
+                if let Ok(expr) = syn::parse_str(&expr_node.text) {
+                    expr_node.expr = expr;
 
-                        let values = Iterator::zip(
-                            expr_node.bindings.iter().map(|s| &**s),
-                            expr_node.values.iter().copied(),
-                        )
-                        .collect::<HashMap<&str, f32>>();
+                    let values = Iterator::zip(
+                        expr_node.bindings.iter().map(|s| &**s),
+                        expr_node.values.iter().copied(),
+                    )
+                    .collect::<HashMap<&str, f32>>();
 
-                        let mut new_bindings = Vec::new();
-                        expr_node.expr.extend_bindings(&mut new_bindings);
+                    let mut new_bindings = Vec::new();
+                    expr_node.expr.extend_bindings(&mut new_bindings);
 
-                        for (idx, name) in expr_node.bindings.iter().enumerate() {
-                            let new_idx =
-                                new_bindings.iter().position(|new_name| *new_name == *name);
+                    for (idx, name) in expr_node.bindings.iter().enumerate() {
+                        let new_idx = new_bindings.iter().position(|new_name| *new_name == *name);
 
-                            match new_idx {
-                                None => {
-                                    effects.drop_inputs(inputs[idx].id);
-                                }
-                                Some(new_idx) if new_idx != idx => {
-                                    let new_in_pin = InPinId {
-                                        node: node_idx,
-                                        input: new_idx,
-                                    };
-                                    for remote in &inputs[idx].remotes {
-                                        effects.disconnect(remote.id, inputs[idx].id);
-                                        effects.connect(remote.id, new_in_pin);
-                                    }
+                        match new_idx {
+                            None => {
+                                effects.drop_inputs(inputs[idx].id);
+                            }
+                            Some(new_idx) if new_idx != idx => {
+                                let new_in_pin = InPinId {
+                                    node: ctx.id,
+                                    input: new_idx,
+                                };
+                                for remote in &inputs[idx].remotes {
+                                    effects.disconnect(remote.id, inputs[idx].id);
+                                    effects.connect(remote.id, new_in_pin);
                                 }
-                                _ => {}
                             }
+                            _ => {}
                         }
+                    }
 
-                        let new_values = new_bindings
-                            .iter()
-                            .map(|name| values.get(&**name).copied().unwrap_or(0.0))
-                            .collect::<Vec<_>>();
+                    let new_values = new_bindings
+                        .iter()
+                        .map(|name| values.get(&**name).copied().unwrap_or(0.0))
+                        .collect::<Vec<_>>();
 
-                        expr_node.bindings = new_bindings;
-                        expr_node.values = new_values;
-                    }
-                    Err(_) => {}
+                    expr_node.bindings = new_bindings;
+                    expr_node.values = new_values;
                 }
 
                 r
@@ -183,6 +246,33 @@ impl SnarlViewer<DemoNode> for DemoViewer {
         }
     }
 
+    fn wire_label(
+        &mut self,
+        from: &OutPin<DemoNode>,
+        _to: &InPin<DemoNode>,
+    ) -> Option<egui::WidgetText> {
+        match *from.node.borrow() {
+            DemoNode::Integer(value) => Some(value.to_string().into()),
+            _ => None,
+        }
+    }
+
+    fn input_tooltip(&mut self, pin: &InPin<DemoNode>) -> Option<egui::WidgetText> {
+        match *pin.node.borrow() {
+            DemoNode::Sink => Some("Displays a number, a string or an image".into()),
+            _ => None,
+        }
+    }
+
+    fn input_name(&mut self, pin: &InPin<DemoNode>) -> Option<egui::WidgetText> {
+        match &*pin.node.borrow() {
+            DemoNode::ExprNode(expr_node) => {
+                expr_node.bindings.get(pin.id.input).map(|name| name.into())
+            }
+            _ => None,
+        }
+    }
+
     fn show_input(
         &mut self,
         pin: &InPin<DemoNode>,
@@ -214,7 +304,7 @@ impl SnarlViewer<DemoNode> for DemoViewer {
                         DemoNode::ExprNode(ref expr) => {
                             assert_eq!(remote.id.output, 0, "Expr node has only one output");
                             let r = ui.label(format!("{}", expr.eval()));
-                            InnerResponse::new(PinInfo::square().with_fill(Color32::RED), r)
+                            InnerResponse::new(diamond_pin().with_fill(Color32::RED), r)
                         }
                         DemoNode::Show(ref uri) => {
                             assert_eq!(remote.id.output, 0, "Show node has only one output");
@@ -268,15 +358,12 @@ impl SnarlViewer<DemoNode> for DemoViewer {
                     match &*pin.remotes {
                         [] => match &mut *pin.node.borrow_mut() {
                             DemoNode::ExprNode(expr_node) => ui.horizontal(|ui| {
-                                ui.label(&expr_node.bindings[pin.id.input]);
                                 ui.add(egui::DragValue::new(&mut expr_node.values[pin.id.input]));
                                 PinInfo::square().with_fill(Color32::RED)
                             }),
                             _ => unreachable!(),
                         },
                         [remote] => ui.horizontal(|ui| {
-                            ui.label(&expr_node.bindings[pin.id.input]);
-
                             let remote_node = remote.node.borrow().clone();
                             match remote_node {
                                 DemoNode::Sink => unreachable!("Sink node has no outputs"),
@@ -352,7 +439,7 @@ impl SnarlViewer<DemoNode> for DemoViewer {
                 let value = expr_node.eval();
                 assert_eq!(pin.id.output, 0, "Add node has only one output");
                 let r = ui.label(format!("{:0.2}", value));
-                InnerResponse::new(PinInfo::square().with_fill(Color32::RED), r)
+                InnerResponse::new(diamond_pin().with_fill(Color32::RED), r)
             }
             DemoNode::Show(_) => {
                 let (_, r) = ui.allocate_exact_size(egui::Vec2::ZERO, egui::Sense::hover());
@@ -362,8 +449,33 @@ impl SnarlViewer<DemoNode> for DemoViewer {
     }
 }
 
+/// Pin of computed values.
+fn diamond_pin() -> PinInfo {
+    PinInfo::custom(|painter, rect, fill| {
+        let points = vec![
+            rect.center_top(),
+            rect.right_center(),
+            rect.center_bottom(),
+            rect.left_center(),
+        ];
+        painter.add(egui::epaint::PathShape::convex_polygon(
+            points,
+            fill,
+            egui::Stroke::new(1.0, Color32::BLACK),
+        ));
+    })
+}
+
 pub struct DemoApp {
     snarl: Snarl<DemoNode>,
+    style: SnarlStyle,
+    search: String,
+}
+
+impl Default for DemoApp {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DemoApp {
@@ -382,7 +494,30 @@ impl DemoApp {
 
         // snarl.add_node(DemoNode::Sink, pos2(190.0, 60.0));
 
-        DemoApp { snarl }
+        snarl.add_comment(SnarlComment {
+            pos: pos2(0.0, -20.0),
+            size: egui::vec2(100.0, 250.0),
+            color: egui::Color32::from_rgb(60, 90, 140),
+            text: "Sources".to_owned(),
+        });
+
+        let style = SnarlStyle {
+            upscale_wire: true,
+            downscale_wire: false,
+            highlight_connections: true,
+            enable_keyboard: true,
+            prune_invalid_wires: true,
+            pending_wire_dash: Some(vec![6.0, 4.0]),
+            pin_snap_radius: 20.0,
+            fan_out_wires: true,
+            ..Default::default()
+        };
+
+        DemoApp {
+            snarl,
+            style,
+            search: String::new(),
+        }
     }
 }
 
@@ -395,6 +530,10 @@ impl App for DemoApp {
 
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
+                    if ui.button("New").clicked() {
+                        self.snarl.clear();
+                        ui.close_menu();
+                    }
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close)
                     }
@@ -402,20 +541,46 @@ impl App for DemoApp {
                 ui.add_space(16.0);
 
                 egui::widgets::global_dark_light_mode_switch(ui);
+                ui.add_space(16.0);
+
+                ui.label("Wires");
+                ui.selectable_value(&mut self.style.wire_style, WireStyle::Bezier, "Bezier");
+                ui.selectable_value(&mut self.style.wire_style, WireStyle::Straight, "Straight");
+                ui.selectable_value(
+                    &mut self.style.wire_style,
+                    WireStyle::AxisAligned { corner_radius: 8.0 },
+                    "Axis aligned",
+                );
+                ui.checkbox(&mut self.style.wire_flow, "Flow");
+                ui.checkbox(&mut self.style.highlight_connections, "Highlight");
+                ui.checkbox(&mut self.style.show_minimap, "Minimap");
+
+                let mut grid = self.style.background_grid.is_some();
+                if ui.checkbox(&mut grid, "Grid").changed() {
+                    self.style.background_grid = grid.then(|| GridStyle {
+                        major_every: Some(5),
+                        ..Default::default()
+                    });
+                }
+                ui.add_space(16.0);
+
+                ui.label("Search");
+                if ui.text_edit_singleline(&mut self.search).changed() {
+                    let search = self.search.to_lowercase();
+                    let found = match search.is_empty() {
+                        true => Vec::new(),
+                        false => self.snarl.find_nodes(|_, node| {
+                            DemoViewer.title(node).to_lowercase().contains(&search)
+                        }),
+                    };
+                    self.snarl.set_highlighted(found);
+                }
             });
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            self.snarl.show(
-                &mut DemoViewer,
-                &SnarlStyle {
-                    upscale_wire: true,
-                    downscale_wire: false,
-                    ..Default::default()
-                },
-                egui::Id::new("snarl"),
-                ui,
-            );
+            self.snarl
+                .show(&mut DemoViewer, &self.style, egui::Id::new("snarl"), ui);
         });
     }
 }
@@ -431,7 +596,7 @@ struct ExprNode {
 impl ExprNode {
     fn new() -> Self {
         ExprNode {
-            text: format!("0"),
+            text: "0".to_owned(),
             bindings: Vec::new(),
             values: Vec::new(),
             expr: Expr::Val(0.0),
@@ -0,0 +1,209 @@
+use egui::{Pos2, Vec2};
+
+use crate::{InPinId, NodeId, OutPinId, Snarl};
+
+/// Nodes and wires among them copied from a Snarl.
+///
+/// Created with `Snarl::copy_nodes` and inserted back with `Snarl::paste`.
+/// Wire endpoints refer to positions of nodes in the clipboard, not to Snarl node identifiers.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnarlClipboard<T> {
+    nodes: Vec<(T, Pos2)>,
+    wires: Vec<CopiedWire>,
+}
+
+/// Wire between copied nodes, which are referred to by position in the clipboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct CopiedWire {
+    from: usize,
+    output: usize,
+    to: usize,
+    input: usize,
+}
+
+impl<T> SnarlClipboard<T> {
+    /// Returns number of copied nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if no nodes were copied.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Copies nodes and wires between them.
+    ///
+    /// Wires to nodes outside of `nodes` are not copied.
+    /// Nodes that do not exist and duplicates are skipped.
+    pub fn copy_nodes(&self, nodes: &[NodeId]) -> SnarlClipboard<T>
+    where
+        T: Clone,
+    {
+        let mut ids = Vec::with_capacity(nodes.len());
+        for &id in nodes {
+            if self.contains_node(id) && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        let position = |node: NodeId| ids.iter().position(|&id| id == node);
+
+        let mut wires = self
+            .wires
+            .iter()
+            .filter_map(|wire| {
+                Some(CopiedWire {
+                    from: position(wire.out_pin.node)?,
+                    output: wire.out_pin.output,
+                    to: position(wire.in_pin.node)?,
+                    input: wire.in_pin.input,
+                })
+            })
+            .collect::<Vec<_>>();
+        wires.sort_unstable();
+
+        let nodes = ids
+            .iter()
+            .map(|&id| {
+                let node = self.expect_node(id);
+                (node.value.borrow().clone(), node.pos)
+            })
+            .collect();
+
+        SnarlClipboard { nodes, wires }
+    }
+
+    /// Adds copies of the clipboard nodes moved by `offset` and reconnects wires between them.
+    /// Returns identifiers of the new nodes in the order they were copied.
+    ///
+    /// Wires referring to nodes outside of the clipboard, e.g. in a deserialized one, are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<i32>::new();
+    /// let a = snarl.add_node(1, egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node(2, egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node(3, egui::pos2(200.0, 0.0));
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 1 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    ///
+    /// let clipboard = snarl.copy_nodes(&[a, b]);
+    /// let pasted = snarl.paste(&clipboard, egui::vec2(0.0, 50.0));
+    /// let [a2, b2] = pasted[..] else { unreachable!() };
+    ///
+    /// assert_eq!(*snarl.get_node(b2).unwrap().borrow(), 2);
+    /// assert!(snarl.connected(OutPinId { node: a2, output: 0 }, InPinId { node: b2, input: 1 }));
+    /// assert!(!snarl.has_any_connection(egui_snarl::AnyPinId::Out(OutPinId { node: b2, output: 0 })));
+    /// assert_eq!(
+    ///     snarl.node_positions().filter(|(id, _)| pasted.contains(id)).collect::<Vec<_>>(),
+    ///     [(a2, egui::pos2(0.0, 50.0)), (b2, egui::pos2(100.0, 50.0))]
+    /// );
+    /// ```
+    pub fn paste(&mut self, clipboard: &SnarlClipboard<T>, offset: Vec2) -> Vec<NodeId>
+    where
+        T: Clone,
+    {
+        let ids = clipboard
+            .nodes
+            .iter()
+            .map(|(value, pos)| self.add_node(value.clone(), *pos + offset))
+            .collect::<Vec<_>>();
+
+        for wire in &clipboard.wires {
+            let (Some(&from), Some(&to)) = (ids.get(wire.from), ids.get(wire.to)) else {
+                continue;
+            };
+            self.connect(
+                OutPinId {
+                    node: from,
+                    output: wire.output,
+                },
+                InPinId {
+                    node: to,
+                    input: wire.input,
+                },
+            );
+        }
+
+        ids
+    }
+
+    /// Adds a copy of the node moved by `offset`, without its wires.
+    /// Returns identifier of the new node or `None` if the node does not exist.
+    ///
+    /// Collapsed state and size set by resizing are copied too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<i32>::new();
+    /// let a = snarl.add_node(1, egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node(2, egui::pos2(100.0, 0.0));
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let b2 = snarl.duplicate_node(b, egui::vec2(20.0, 20.0)).unwrap();
+    /// assert_eq!(*snarl.get_node(b2).unwrap().borrow(), 2);
+    /// assert_eq!(snarl.node_pos(b2), Some(egui::pos2(120.0, 20.0)));
+    /// assert!(snarl.in_pin_remotes(InPinId { node: b2, input: 0 }).is_empty());
+    /// assert_eq!(snarl.wires().count(), 1);
+    ///
+    /// snarl.remove_node(a);
+    /// assert_eq!(snarl.duplicate_node(a, egui::vec2(20.0, 20.0)), None);
+    /// ```
+    pub fn duplicate_node(&mut self, id: NodeId, offset: Vec2) -> Option<NodeId>
+    where
+        T: Clone,
+    {
+        let node = self.node(id)?;
+        let value = node.value.borrow().clone();
+        let (pos, collapsed, size) = (node.pos, node.collapsed, node.size);
+
+        let new_id = self.add_node(value, pos + offset);
+        let new_node = self.expect_node_mut(new_id);
+        new_node.collapsed = collapsed;
+        new_node.size = size;
+        Some(new_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{pos2, vec2};
+
+    use super::*;
+
+    #[test]
+    fn paste_skips_wires_outside_of_clipboard() {
+        let clipboard = SnarlClipboard {
+            nodes: vec![(1, pos2(0.0, 0.0)), (2, pos2(100.0, 0.0))],
+            wires: vec![
+                CopiedWire {
+                    from: 0,
+                    output: 0,
+                    to: 1,
+                    input: 0,
+                },
+                CopiedWire {
+                    from: 0,
+                    output: 0,
+                    to: 2,
+                    input: 0,
+                },
+            ],
+        };
+
+        let mut snarl = Snarl::new();
+        let pasted = snarl.paste(&clipboard, vec2(0.0, 0.0));
+
+        assert_eq!(pasted.len(), 2);
+        assert_eq!(snarl.wires().count(), 1);
+    }
+}
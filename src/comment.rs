@@ -0,0 +1,105 @@
+use egui::{Color32, Pos2, Rect, Vec2};
+
+use crate::{ui::SnarlViewer, NodeId, Snarl};
+
+/// Colored rectangle with a title drawn behind nodes.
+///
+/// Comments group nodes visually.
+/// They can be dragged by the title bar and resized by the bottom-right corner in `Snarl::show`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnarlComment {
+    /// Position of the top-left corner in graph space.
+    pub pos: Pos2,
+
+    /// Size in graph space.
+    pub size: Vec2,
+
+    /// Color of the title bar and the border.
+    /// Body is filled with its translucent version.
+    pub color: Color32,
+
+    /// Text shown in the title bar.
+    pub text: String,
+}
+
+impl SnarlComment {
+    /// Returns rect of the comment in graph space.
+    pub fn rect(&self) -> Rect {
+        Rect::from_min_size(self.pos, self.size)
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Adds a comment to the Snarl.
+    /// Returns the index of the comment.
+    pub fn add_comment(&mut self, comment: SnarlComment) -> usize {
+        self.comments.insert(comment)
+    }
+
+    /// Removes a comment from the Snarl.
+    /// Nodes in the comment are not affected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the comment does not exist.
+    pub fn remove_comment(&mut self, idx: usize) -> SnarlComment {
+        self.comments.remove(idx)
+    }
+
+    /// Returns reference to the comment.
+    pub fn get_comment(&self, idx: usize) -> Option<&SnarlComment> {
+        self.comments.get(idx)
+    }
+
+    /// Returns mutable reference to the comment.
+    pub fn get_comment_mut(&mut self, idx: usize) -> Option<&mut SnarlComment> {
+        self.comments.get_mut(idx)
+    }
+
+    /// Iterates over comments with their indices.
+    pub fn comments(&self) -> impl Iterator<Item = (usize, &SnarlComment)> + '_ {
+        self.comments.iter()
+    }
+
+    /// Returns nodes which rects intersect the comment, in ascending order.
+    ///
+    /// Node rects are in graph space, sized by `Snarl::node_size`
+    /// or by `SnarlViewer::size_hint` for nodes that were not resized.
+    /// Returns empty vector if the comment does not exist.
+    pub fn comment_nodes<V>(&self, idx: usize, viewer: &V) -> Vec<NodeId>
+    where
+        V: SnarlViewer<T>,
+    {
+        let Some(comment) = self.comments.get(idx) else {
+            return Vec::new();
+        };
+        let rect = comment.rect();
+
+        self.nodes
+            .iter()
+            .filter(|(_, node)| {
+                let size = node
+                    .size
+                    .unwrap_or_else(|| viewer.size_hint(&node.value.borrow()));
+                rect.intersects(Rect::from_min_size(node.pos, size))
+            })
+            .map(|(idx, _)| self.id_at(idx))
+            .collect()
+    }
+
+    /// Moves the comment by `delta` in graph space together with given nodes.
+    /// Nodes that do not exist are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the comment does not exist.
+    pub fn move_comment(&mut self, idx: usize, delta: Vec2, nodes: &[NodeId]) {
+        self.comments[idx].pos += delta;
+        for &node in nodes {
+            if self.contains_node(node) {
+                self.move_node(node, delta);
+            }
+        }
+    }
+}
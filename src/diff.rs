@@ -0,0 +1,218 @@
+use egui::Pos2;
+
+use crate::{InPinId, NodeId, OutPinId, Snarl};
+
+/// Changes that turn one Snarl into another.
+///
+/// Created with `Snarl::diff` and applied with `Snarl::apply`.
+/// Nodes are listed as `(identifier, value, position)` and sorted by identifier.
+/// Wires are sorted too, so the diff does not depend on order of insertion.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnarlDiff<T> {
+    /// Nodes that exist only in the new Snarl.
+    pub added_nodes: Vec<(NodeId, T, Pos2)>,
+
+    /// Nodes that exist only in the old Snarl.
+    pub removed_nodes: Vec<NodeId>,
+
+    /// Nodes that exist in both with different value or position.
+    /// Holds the new value and position.
+    pub changed_nodes: Vec<(NodeId, T, Pos2)>,
+
+    /// Wires that exist only in the new Snarl.
+    pub added_wires: Vec<(OutPinId, InPinId)>,
+
+    /// Wires that exist only in the old Snarl.
+    pub removed_wires: Vec<(OutPinId, InPinId)>,
+}
+
+impl<T> SnarlDiff<T> {
+    /// Returns true if there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_wires.is_empty()
+            && self.removed_wires.is_empty()
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Returns changes that turn this Snarl into `other`.
+    ///
+    /// Nodes are matched by identifier.
+    /// Node is changed if its value or position differs.
+    /// Collapsed state, size and comments are not compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut base = Snarl::<i32>::new();
+    /// let a = base.add_node(1, egui::pos2(0.0, 0.0));
+    /// let b = base.add_node(2, egui::pos2(100.0, 0.0));
+    /// let c = base.add_node(3, egui::pos2(200.0, 0.0));
+    /// let ab = (OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// let bc = (OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    /// base.connect(ab.0, ab.1);
+    /// base.connect(bc.0, bc.1);
+    ///
+    /// let mut target = base.clone();
+    /// let d = target.add_node(4, egui::pos2(300.0, 0.0));
+    /// target.remove_node(c);
+    /// *target.get_node_mut(a).unwrap() = 10;
+    /// let bd = (OutPinId { node: b, output: 0 }, InPinId { node: d, input: 0 });
+    /// target.connect(bd.0, bd.1);
+    ///
+    /// let diff = base.diff(&target);
+    /// assert_eq!(diff.added_nodes, [(d, 4, egui::pos2(300.0, 0.0))]);
+    /// assert_eq!(diff.removed_nodes, [c]);
+    /// assert_eq!(diff.changed_nodes, [(a, 10, egui::pos2(0.0, 0.0))]);
+    /// assert_eq!(diff.added_wires, [bd]);
+    /// assert_eq!(diff.removed_wires, [bc]);
+    ///
+    /// // Same graph built in another order has no differences.
+    /// let mut same = Snarl::<i32>::new();
+    /// same.insert_node(c.idx(), 3, egui::pos2(200.0, 0.0)).unwrap();
+    /// same.insert_node(b.idx(), 2, egui::pos2(100.0, 0.0)).unwrap();
+    /// same.insert_node(a.idx(), 1, egui::pos2(0.0, 0.0)).unwrap();
+    /// same.connect(bc.0, bc.1);
+    /// same.connect(ab.0, ab.1);
+    /// assert!(base.diff(&same).is_empty());
+    /// ```
+    pub fn diff(&self, other: &Snarl<T>) -> SnarlDiff<T>
+    where
+        T: PartialEq + Clone,
+    {
+        let mut added_nodes = Vec::new();
+        let mut changed_nodes = Vec::new();
+        for (idx, node) in other.nodes.iter() {
+            let id = other.id_at(idx);
+            match self.node(id) {
+                None => added_nodes.push((id, node.value.borrow().clone(), node.pos)),
+                Some(old) => {
+                    if *old.value.borrow() != *node.value.borrow() || old.pos != node.pos {
+                        changed_nodes.push((id, node.value.borrow().clone(), node.pos));
+                    }
+                }
+            }
+        }
+        added_nodes.sort_by_key(|(id, _, _)| *id);
+        changed_nodes.sort_by_key(|(id, _, _)| *id);
+
+        let mut removed_nodes = self
+            .node_ids()
+            .filter(|&id| !other.contains_node(id))
+            .collect::<Vec<_>>();
+        removed_nodes.sort_unstable();
+
+        let wires = |from: &Snarl<T>, to: &Snarl<T>| {
+            let mut wires = from
+                .wires
+                .iter()
+                .filter(|wire| !to.wires.contains(wire))
+                .map(|wire| (wire.out_pin, wire.in_pin))
+                .collect::<Vec<_>>();
+            wires.sort_unstable();
+            wires
+        };
+
+        SnarlDiff {
+            added_nodes,
+            removed_nodes,
+            changed_nodes,
+            added_wires: wires(other, self),
+            removed_wires: wires(self, other),
+        }
+    }
+
+    /// Applies changes from the diff to this Snarl.
+    ///
+    /// Applying diff from `a.diff(&b)` to `a` makes it match `b`.
+    /// When the Snarl was changed concurrently, changes that can't be applied are skipped:
+    /// changes of nodes that no longer exist, nodes added at occupied indices
+    /// and wires to missing nodes or to nodes that were not added.
+    ///
+    /// Changes are recorded for `SnarlHistory` like any other edit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut base = Snarl::<i32>::new();
+    /// let a = base.add_node(1, egui::pos2(0.0, 0.0));
+    /// let b = base.add_node(2, egui::pos2(100.0, 0.0));
+    /// base.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let mut target = base.clone();
+    /// let c = target.add_node(3, egui::pos2(200.0, 0.0));
+    /// target.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    /// target.disconnect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// target.set_node_pos(b, egui::pos2(100.0, 50.0));
+    /// target.remove_node(a);
+    ///
+    /// let diff = base.diff(&target);
+    /// let mut patched = base.clone();
+    /// patched.apply(&diff);
+    /// assert!(patched.diff(&target).is_empty());
+    ///
+    /// // Peer removed `b` meanwhile, changes referencing it are skipped.
+    /// let mut peer = base.clone();
+    /// peer.remove_node(b);
+    /// peer.apply(&diff);
+    /// assert!(peer.get_node(b).is_none());
+    /// assert!(peer.get_node(a).is_none());
+    /// assert_eq!(*peer.get_node(c).unwrap().borrow(), 3);
+    /// assert_eq!(peer.wires().count(), 0);
+    ///
+    /// // Peer added its own node at the index of `c`, wires added to `c` skip it.
+    /// let mut peer = base.clone();
+    /// let d = peer.add_node(9, egui::pos2(0.0, 0.0));
+    /// assert_eq!(d, c);
+    /// peer.apply(&diff);
+    /// assert_eq!(*peer.get_node(d).unwrap().borrow(), 9);
+    /// assert_eq!(peer.wires().count(), 0);
+    /// ```
+    pub fn apply(&mut self, diff: &SnarlDiff<T>)
+    where
+        T: Clone,
+    {
+        for &(from, to) in &diff.removed_wires {
+            self.disconnect(from, to);
+        }
+
+        for &id in &diff.removed_nodes {
+            if self.contains_node(id) {
+                self.remove_node(id);
+            }
+        }
+
+        // Nodes whose index was taken concurrently.
+        // Their identifier may refer to an unrelated node, so their wires are skipped.
+        let mut rejected = Vec::new();
+        for (id, value, pos) in &diff.added_nodes {
+            if self.insert_node_with_id(*id, value.clone(), *pos).is_err() {
+                rejected.push(*id);
+            }
+        }
+
+        for (id, value, pos) in &diff.changed_nodes {
+            let Some(node) = self.node_mut(*id) else {
+                continue;
+            };
+            let old = node.value.replace(value.clone());
+            if let Some(log) = &mut self.history_log {
+                log.record_replaced(*id, &old);
+            }
+            self.set_node_pos(*id, *pos);
+        }
+
+        for &(from, to) in &diff.added_wires {
+            if rejected.contains(&from.node) || rejected.contains(&to.node) {
+                continue;
+            }
+            self.connect(from, to);
+        }
+    }
+}
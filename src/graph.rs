@@ -0,0 +1,180 @@
+use std::{collections::VecDeque, fmt};
+
+use crate::{InPinId, NodeId, OutPinId, Snarl};
+
+/// Error returned from `Snarl::topological_order` when wires form a cycle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleError {
+    /// Nodes that could not be ordered, in ascending order.
+    /// These are nodes on cycles and nodes that depend on them.
+    pub nodes: Vec<NodeId>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "wires form a cycle through nodes {:?}", self.nodes)
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+impl<T> Snarl<T> {
+    /// Returns nodes connected to outputs of the node.
+    fn successors(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.wires
+            .iter()
+            .filter(move |wire| wire.out_pin.node == node)
+            .map(|wire| wire.in_pin.node)
+    }
+
+    /// Returns true if connecting two pins would make a cycle,
+    /// treating wires as directed edges from output to input.
+    ///
+    /// Connecting node to itself is a cycle too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node((), egui::pos2(200.0, 0.0));
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    /// assert!(!snarl.has_cycle());
+    ///
+    /// let (from, to) = (OutPinId { node: c, output: 0 }, InPinId { node: a, input: 0 });
+    /// assert!(snarl.would_create_cycle(from, to));
+    /// assert!(!snarl.would_create_cycle(OutPinId { node: a, output: 1 }, InPinId { node: c, input: 1 }));
+    ///
+    /// snarl.connect(from, to);
+    /// assert!(snarl.has_cycle());
+    /// ```
+    pub fn would_create_cycle(&self, from: OutPinId, to: InPinId) -> bool {
+        // New wire closes a cycle if its output is reachable from its input.
+        let mut visited = vec![to.node];
+        let mut stack = vec![to.node];
+
+        while let Some(node) = stack.pop() {
+            if node == from.node {
+                return true;
+            }
+            for next in self.successors(node) {
+                if !visited.contains(&next) {
+                    visited.push(next);
+                    stack.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns true if wires form a cycle,
+    /// treating them as directed edges from output to input.
+    pub fn has_cycle(&self) -> bool {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        let mut marks = vec![Mark::Unvisited; self.nodes.capacity()];
+
+        for start in self.node_ids() {
+            if marks[start.idx] != Mark::Unvisited {
+                continue;
+            }
+
+            // Depth-first search with explicit stack of nodes and their successors.
+            marks[start.idx] = Mark::InProgress;
+            let mut stack = vec![(start, self.successors(start).collect::<Vec<_>>())];
+
+            while let Some((node, successors)) = stack.last_mut() {
+                match successors.pop() {
+                    None => {
+                        marks[node.idx] = Mark::Done;
+                        stack.pop();
+                    }
+                    Some(next) => match marks[next.idx] {
+                        Mark::InProgress => return true,
+                        Mark::Done => {}
+                        Mark::Unvisited => {
+                            marks[next.idx] = Mark::InProgress;
+                            stack.push((next, self.successors(next).collect()));
+                        }
+                    },
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns nodes ordered so that each node goes after all nodes connected to its inputs.
+    ///
+    /// Uses Kahn's algorithm.
+    /// Nodes that are ready at the same time are ordered by index.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CycleError` with nodes that could not be ordered if wires form a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{CycleError, InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let [a, b, c, d] = [0, 1, 2, 3].map(|_| snarl.add_node((), egui::pos2(0.0, 0.0)));
+    /// let mut wire = |from, to| {
+    ///     snarl.connect(OutPinId { node: from, output: 0 }, InPinId { node: to, input: 0 })
+    /// };
+    ///
+    /// // Diamond: a feeds b and c, both feed d.
+    /// wire(a, b);
+    /// wire(a, c);
+    /// wire(b, d);
+    /// wire(c, d);
+    /// assert_eq!(snarl.topological_order(), Ok(vec![a, b, c, d]));
+    ///
+    /// // Wiring d back into a closes a cycle.
+    /// snarl.connect(OutPinId { node: d, output: 0 }, InPinId { node: a, input: 0 });
+    /// assert_eq!(
+    ///     snarl.topological_order(),
+    ///     Err(CycleError { nodes: vec![a, b, c, d] })
+    /// );
+    /// ```
+    pub fn topological_order(&self) -> Result<Vec<NodeId>, CycleError> {
+        let mut in_degree = vec![0usize; self.nodes.capacity()];
+        for wire in self.wires.iter() {
+            in_degree[wire.in_pin.node.idx] += 1;
+        }
+
+        let mut ready = self
+            .node_ids()
+            .filter(|id| in_degree[id.idx] == 0)
+            .collect::<VecDeque<_>>();
+
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node) = ready.pop_front() {
+            order.push(node);
+
+            let mut next_ready = Vec::new();
+            for next in self.successors(node) {
+                in_degree[next.idx] -= 1;
+                if in_degree[next.idx] == 0 {
+                    next_ready.push(next);
+                }
+            }
+            next_ready.sort_unstable();
+            ready.extend(next_ready);
+        }
+
+        if order.len() == self.nodes.len() {
+            Ok(order)
+        } else {
+            let nodes = self.node_ids().filter(|id| in_degree[id.idx] > 0).collect();
+            Err(CycleError { nodes })
+        }
+    }
+}
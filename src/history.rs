@@ -0,0 +1,372 @@
+use std::cell::RefCell;
+
+use egui::Pos2;
+
+use crate::{wire_pins, InPinId, Node, NodeId, OccupiedError, OutPinId, Snarl};
+
+/// Operation that reverts a recorded change.
+#[derive(Clone, Debug)]
+pub(crate) enum HistoryOp<T> {
+    InsertNode { node: NodeId, pos: Pos2, value: T },
+    RemoveNode { node: NodeId },
+    ReplaceNode { node: NodeId, value: T },
+    MoveNode { node: NodeId, pos: Pos2 },
+    Connect { from: OutPinId, to: InPinId },
+    Disconnect { from: OutPinId, to: InPinId },
+}
+
+impl<T> HistoryOp<T> {
+    /// Applies the operation to the Snarl without recording it.
+    /// Returns the operation that reverts it,
+    /// or `None` if the operation no longer applies.
+    ///
+    /// Changes made while history was disabled may remove recorded nodes
+    /// or take their indices, such operations are skipped.
+    fn apply(self, snarl: &mut Snarl<T>) -> Option<HistoryOp<T>> {
+        match self {
+            HistoryOp::InsertNode { node, pos, value } => {
+                snarl.insert_node_at(node, value, pos).ok()?;
+                Some(HistoryOp::RemoveNode { node })
+            }
+            HistoryOp::RemoveNode { node } => {
+                let pos = snarl.node(node)?.pos;
+                let value = snarl.remove_node_unrecorded(node);
+                Some(HistoryOp::InsertNode { node, pos, value })
+            }
+            HistoryOp::ReplaceNode { node, value } => {
+                let value = snarl.node_mut(node)?.value.replace(value);
+                Some(HistoryOp::ReplaceNode { node, value })
+            }
+            HistoryOp::MoveNode { node, pos } => {
+                let pos = std::mem::replace(&mut snarl.node_mut(node)?.pos, pos);
+                Some(HistoryOp::MoveNode { node, pos })
+            }
+            HistoryOp::Connect { from, to } => {
+                if !snarl.contains_node(from.node) || !snarl.contains_node(to.node) {
+                    return None;
+                }
+                snarl
+                    .wires
+                    .insert(wire_pins(from, to))
+                    .then_some(HistoryOp::Disconnect { from, to })
+            }
+            HistoryOp::Disconnect { from, to } => snarl
+                .wires
+                .remove(&wire_pins(from, to))
+                .then_some(HistoryOp::Connect { from, to }),
+        }
+    }
+}
+
+/// Changes recorded by the Snarl since the last `SnarlHistory::commit`.
+#[derive(Clone, Debug)]
+pub(crate) struct HistoryLog<T> {
+    /// Operations reverting recorded changes, in order of the changes.
+    ops: Vec<HistoryOp<T>>,
+
+    /// Clones node values that are removed or replaced.
+    clone_value: fn(&T) -> T,
+
+    /// Set while the pointer is held down in `Snarl::show`.
+    /// Commit is postponed so that a drag is undone in one step.
+    pub(crate) hold: bool,
+}
+
+impl<T> HistoryLog<T> {
+    /// Records removal of the node.
+    pub(crate) fn record_removed(&mut self, id: NodeId, node: &Node<T>) {
+        self.ops.push(HistoryOp::InsertNode {
+            node: id,
+            pos: node.pos,
+            value: (self.clone_value)(&node.value.borrow()),
+        });
+    }
+
+    /// Records replacement of the node value.
+    pub(crate) fn record_replaced(&mut self, id: NodeId, old: &T) {
+        self.ops.push(HistoryOp::ReplaceNode {
+            node: id,
+            value: (self.clone_value)(old),
+        });
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Starts recording changes for `SnarlHistory`.
+    ///
+    /// Adding, removing, replacing and moving nodes and connecting and disconnecting pins
+    /// is recorded both when done through `Snarl` methods and through the UI.
+    /// Recording is disabled by default and costs nothing until enabled.
+    pub fn enable_history(&mut self)
+    where
+        T: Clone,
+    {
+        if self.history_log.is_none() {
+            self.history_log = Some(HistoryLog {
+                ops: Vec::new(),
+                clone_value: T::clone,
+                hold: false,
+            });
+        }
+    }
+
+    /// Stops recording changes and drops changes not yet committed.
+    pub fn disable_history(&mut self) {
+        self.history_log = None;
+    }
+
+    /// Returns true if changes are recorded for `SnarlHistory`.
+    pub fn is_history_enabled(&self) -> bool {
+        self.history_log.is_some()
+    }
+
+    pub(crate) fn record(&mut self, op: HistoryOp<T>) {
+        let Some(log) = &mut self.history_log else {
+            return;
+        };
+
+        if let HistoryOp::MoveNode { node, .. } = op {
+            // Only the position before the first move needs to be restored.
+            let moved = log
+                .ops
+                .iter()
+                .any(|op| matches!(op, HistoryOp::MoveNode { node: n, .. } if *n == node));
+            if moved {
+                return;
+            }
+        }
+        log.ops.push(op);
+    }
+
+    /// Inserts node with specific identifier.
+    /// Fails if the index is occupied.
+    pub(crate) fn insert_node_at(
+        &mut self,
+        id: NodeId,
+        value: T,
+        pos: Pos2,
+    ) -> Result<(), OccupiedError> {
+        let idx = id.idx;
+        if self.nodes.contains(idx) {
+            return Err(OccupiedError { idx });
+        }
+
+        let node = Node {
+            value: RefCell::new(value),
+            pos,
+            collapsed: false,
+            size: None,
+        };
+
+        let entry = self.nodes.vacant_entry();
+        if entry.key() == idx {
+            entry.insert(node);
+        } else {
+            // `Slab` can't insert at a chosen key, rebuild it with the new entry.
+            let nodes = std::mem::take(&mut self.nodes);
+            self.nodes = nodes.into_iter().chain(Some((idx, node))).collect();
+        }
+        self.set_generation(id);
+        self.draw_order.push(idx);
+        Ok(())
+    }
+}
+
+/// Undo and redo stacks for changes recorded by the Snarl.
+///
+/// Enable recording with `Snarl::enable_history`
+/// and call `SnarlHistory::commit` once per frame after `Snarl::show`.
+/// All changes made since the previous commit become one undo step.
+///
+/// # Examples
+///
+/// ```
+/// # use egui_snarl::{InPinId, OutPinId, Snarl, SnarlHistory};
+/// let mut snarl = Snarl::<()>::new();
+/// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+/// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+///
+/// let mut history = SnarlHistory::new();
+/// snarl.enable_history();
+///
+/// let from = OutPinId { node: a, output: 0 };
+/// let to = InPinId { node: b, input: 0 };
+/// snarl.connect(from, to);
+/// history.commit(&mut snarl);
+///
+/// assert!(history.undo(&mut snarl));
+/// assert!(!snarl.connected(from, to));
+///
+/// assert!(history.redo(&mut snarl));
+/// assert!(snarl.connected(from, to));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SnarlHistory<T> {
+    undo: Vec<Vec<HistoryOp<T>>>,
+    redo: Vec<Vec<HistoryOp<T>>>,
+}
+
+impl<T> Default for SnarlHistory<T> {
+    fn default() -> Self {
+        SnarlHistory::new()
+    }
+}
+
+impl<T> SnarlHistory<T> {
+    /// Creates empty history.
+    pub fn new() -> Self {
+        SnarlHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Turns changes recorded by the Snarl since the last commit into one undo step.
+    /// Returns true if a step was added.
+    ///
+    /// New step clears redo stack.
+    /// Commit is postponed while a node is being dragged,
+    /// so that the whole drag is undone at once.
+    pub fn commit(&mut self, snarl: &mut Snarl<T>) -> bool {
+        let Some(log) = &mut snarl.history_log else {
+            return false;
+        };
+
+        if log.hold || log.ops.is_empty() {
+            return false;
+        }
+
+        self.undo.push(std::mem::take(&mut log.ops));
+        self.redo.clear();
+        true
+    }
+
+    /// Reverts the last step.
+    /// Uncommitted changes are committed first.
+    /// Returns false if there is nothing to undo.
+    pub fn undo(&mut self, snarl: &mut Snarl<T>) -> bool {
+        if let Some(log) = &mut snarl.history_log {
+            log.hold = false;
+        }
+        self.commit(snarl);
+
+        let Some(step) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(apply_step(snarl, step));
+        true
+    }
+
+    /// Reapplies the last undone step.
+    /// Returns false if there is nothing to redo.
+    pub fn redo(&mut self, snarl: &mut Snarl<T>) -> bool {
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(apply_step(snarl, step));
+        true
+    }
+
+    /// Returns true if there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns true if there is a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Drops all steps.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Applies step operations in reverse order, skipping ones that no longer apply.
+/// Returns the step that reverts it.
+fn apply_step<T>(snarl: &mut Snarl<T>, step: Vec<HistoryOp<T>>) -> Vec<HistoryOp<T>> {
+    step.into_iter()
+        .rev()
+        .filter_map(|op| op.apply(snarl))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::pos2;
+
+    use super::*;
+
+    #[test]
+    fn undo_skips_node_removed_without_history() {
+        let mut snarl = Snarl::<i32>::new();
+        let mut history = SnarlHistory::new();
+        snarl.enable_history();
+
+        let a = snarl.add_node(1, pos2(0.0, 0.0));
+        history.commit(&mut snarl);
+        snarl.set_node_pos(a, pos2(10.0, 0.0));
+        history.commit(&mut snarl);
+
+        snarl.disable_history();
+        snarl.remove_node(a);
+        snarl.enable_history();
+
+        assert!(history.undo(&mut snarl));
+        assert!(history.undo(&mut snarl));
+        assert_eq!(snarl.node_count(), 0);
+
+        // Skipped operations are not redone either.
+        assert!(history.redo(&mut snarl));
+        assert_eq!(snarl.node_count(), 0);
+    }
+
+    #[test]
+    fn undo_skips_insert_into_occupied_index() {
+        let mut snarl = Snarl::<i32>::new();
+        let mut history = SnarlHistory::new();
+        let a = snarl.add_node(1, pos2(0.0, 0.0));
+        snarl.enable_history();
+
+        snarl.remove_node(a);
+        history.commit(&mut snarl);
+
+        snarl.disable_history();
+        let b = snarl.add_node(2, pos2(0.0, 0.0));
+        assert_eq!(b.idx(), a.idx());
+        snarl.enable_history();
+
+        assert!(history.undo(&mut snarl));
+        assert!(!snarl.contains_node(a));
+        assert_eq!(*snarl.get_node(b).unwrap().borrow(), 2);
+    }
+
+    #[test]
+    fn undo_restores_nodes_and_wires_removed_by_clear() {
+        let mut snarl = Snarl::<i32>::new();
+        let mut history = SnarlHistory::new();
+        snarl.enable_history();
+
+        let a = snarl.add_node(1, pos2(0.0, 0.0));
+        let b = snarl.add_node(2, pos2(100.0, 0.0));
+        let (from, to) = (
+            OutPinId { node: a, output: 0 },
+            InPinId { node: b, input: 0 },
+        );
+        snarl.connect(from, to);
+        history.commit(&mut snarl);
+
+        snarl.freeze_layout();
+        snarl.clear();
+        history.commit(&mut snarl);
+        assert!(snarl.is_empty());
+        assert!(!snarl.is_layout_frozen());
+
+        assert!(history.undo(&mut snarl));
+        assert_eq!(*snarl.get_node(a).unwrap().borrow(), 1);
+        assert_eq!(*snarl.get_node(b).unwrap().borrow(), 2);
+        assert!(snarl.connected(from, to));
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::VecDeque;
+
+use egui::{pos2, vec2, Pos2, Vec2};
+
+use crate::{NodeId, Snarl};
+
+/// Number of barycenter sweeps used to reduce wire crossings.
+const ORDER_SWEEPS: usize = 4;
+
+/// Stiffness of springs along wires in `Snarl::force_layout`.
+const SPRING_STIFFNESS: f32 = 0.1;
+
+/// Maximum distance a node moves in one iteration of `Snarl::force_layout`.
+const MAX_STEP: f32 = 50.0;
+
+/// Parameters of `Snarl::auto_layout`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutParams {
+    /// Distance along x axis between positions of nodes in adjacent ranks.
+    pub horizontal_gap: f32,
+
+    /// Distance along y axis between positions of nodes in the same rank.
+    pub vertical_gap: f32,
+
+    /// Position of the first node of the first component.
+    pub origin: Pos2,
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        LayoutParams {
+            horizontal_gap: 200.0,
+            vertical_gap: 100.0,
+            origin: Pos2::ZERO,
+        }
+    }
+}
+
+/// Parameters of `Snarl::force_layout`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForceLayoutParams {
+    /// Strength of repulsion between every pair of nodes.
+    /// Repulsion force falls off with the squared distance.
+    pub repulsion: f32,
+
+    /// Rest length of springs along wires.
+    pub spring_length: f32,
+
+    /// Fraction of node velocity lost each iteration, in `[0, 1]`.
+    pub damping: f32,
+
+    /// Seed used to separate nodes at the same position.
+    pub seed: u64,
+}
+
+impl Default for ForceLayoutParams {
+    fn default() -> Self {
+        ForceLayoutParams {
+            repulsion: 10000.0,
+            spring_length: 200.0,
+            damping: 0.2,
+            seed: 0,
+        }
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Moves nodes to positions computed by `Snarl::layered_layout`.
+    ///
+    /// Moves are recorded for `SnarlHistory` as any other move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, LayoutParams, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let c = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let d = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let lonely = snarl.add_node((), egui::pos2(0.0, 0.0));
+    ///
+    /// // a -> b -> c, a -> c and d -> c.
+    /// let wire = |from, to| (OutPinId { node: from, output: 0 }, InPinId { node: to, input: 0 });
+    /// for (from, to) in [wire(a, b), wire(b, c), wire(a, c), wire(d, c)] {
+    ///     snarl.connect(from, to);
+    /// }
+    ///
+    /// snarl.auto_layout(LayoutParams::default());
+    ///
+    /// let x = |id| snarl.node_pos(id).unwrap().x;
+    /// assert!(x(a) < x(b));
+    /// assert!(x(b) < x(c));
+    /// assert!(x(d) < x(c));
+    ///
+    /// // Disconnected node is placed next to the graph.
+    /// assert!(x(lonely) > x(c));
+    /// ```
+    pub fn auto_layout(&mut self, params: LayoutParams) {
+        for (id, pos) in self.layered_layout(&params) {
+            self.set_node_pos(id, pos);
+        }
+    }
+
+    /// Computes layered positions of nodes, treating wires as directed edges
+    /// from output to input.
+    ///
+    /// Nodes are ranked by the longest path from their sources, so every node
+    /// is placed right of the nodes connected to its inputs.
+    /// Nodes within a rank are ordered to reduce wire crossings.
+    /// Cycles are broken at the node with the smallest index.
+    /// Disconnected components are laid out side by side, ordered by their smallest node index.
+    ///
+    /// Returns node identifiers with target positions in ascending order of identifiers,
+    /// suitable for `Snarl::animate_to_layout`.
+    pub fn layered_layout(&self, params: &LayoutParams) -> Vec<(NodeId, Pos2)> {
+        let capacity = self.nodes.capacity();
+        let mut preds = vec![Vec::new(); capacity];
+        let mut succs = vec![Vec::new(); capacity];
+        for wire in self.wires.iter() {
+            let (from, to) = (wire.out_pin.node.idx, wire.in_pin.node.idx);
+            if from != to {
+                succs[from].push(to);
+                preds[to].push(from);
+            }
+        }
+
+        // Longest-path ranks.
+        let mut in_degree = preds.iter().map(Vec::len).collect::<Vec<_>>();
+        let mut rank = vec![0; capacity];
+        let mut done = vec![false; capacity];
+        let mut ready = self
+            .nodes
+            .iter()
+            .map(|(idx, _)| idx)
+            .filter(|&idx| in_degree[idx] == 0)
+            .collect::<VecDeque<_>>();
+
+        loop {
+            while let Some(node) = ready.pop_front() {
+                if done[node] {
+                    continue;
+                }
+                done[node] = true;
+
+                for &next in &succs[node] {
+                    if done[next] {
+                        continue;
+                    }
+                    rank[next] = rank[next].max(rank[node] + 1);
+                    in_degree[next] -= 1;
+                    if in_degree[next] == 0 {
+                        ready.push_back(next);
+                    }
+                }
+            }
+
+            // Remaining nodes are on cycles or depend on them.
+            match self
+                .nodes
+                .iter()
+                .map(|(idx, _)| idx)
+                .find(|&idx| !done[idx])
+            {
+                Some(idx) => ready.push_back(idx),
+                None => break,
+            }
+        }
+
+        // Connected components, numbered by their smallest node index.
+        let mut parent = (0..capacity).collect::<Vec<_>>();
+        fn root(parent: &mut [usize], mut idx: usize) -> usize {
+            while parent[idx] != idx {
+                parent[idx] = parent[parent[idx]];
+                idx = parent[idx];
+            }
+            idx
+        }
+        for (from, next) in succs.iter().enumerate() {
+            for &to in next {
+                let (a, b) = (root(&mut parent, from), root(&mut parent, to));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut components: Vec<(usize, Vec<Vec<usize>>)> = Vec::new();
+        for (idx, _) in self.nodes.iter() {
+            let root = root(&mut parent, idx);
+            let layers = match components.iter_mut().find(|(r, _)| *r == root) {
+                Some((_, layers)) => layers,
+                None => {
+                    components.push((root, Vec::new()));
+                    &mut components.last_mut().unwrap().1
+                }
+            };
+            if layers.len() <= rank[idx] {
+                layers.resize_with(rank[idx] + 1, Vec::new);
+            }
+            layers[rank[idx]].push(idx);
+        }
+
+        // Barycenter ordering within ranks.
+        let mut order = vec![0.0f32; capacity];
+        let rank = &rank;
+        for (_, layers) in &mut components {
+            for layer in layers.iter() {
+                for (i, &idx) in layer.iter().enumerate() {
+                    order[idx] = i as f32;
+                }
+            }
+
+            for _ in 0..ORDER_SWEEPS {
+                for layer in layers.iter_mut().skip(1) {
+                    sort_layer(layer, &mut order, |idx| {
+                        preds[idx]
+                            .iter()
+                            .copied()
+                            .filter(move |&p| rank[p] < rank[idx])
+                    });
+                }
+                let last = layers.len().saturating_sub(1);
+                for layer in layers[..last].iter_mut().rev() {
+                    sort_layer(layer, &mut order, |idx| {
+                        succs[idx]
+                            .iter()
+                            .copied()
+                            .filter(move |&s| rank[s] > rank[idx])
+                    });
+                }
+            }
+        }
+
+        let mut positions = Vec::with_capacity(self.nodes.len());
+        let mut column = 0;
+        for (_, layers) in &components {
+            for (r, layer) in layers.iter().enumerate() {
+                for (i, &idx) in layer.iter().enumerate() {
+                    positions.push((
+                        self.id_at(idx),
+                        pos2(
+                            params.origin.x + (column + r) as f32 * params.horizontal_gap,
+                            params.origin.y + i as f32 * params.vertical_gap,
+                        ),
+                    ));
+                }
+            }
+            column += layers.len();
+        }
+
+        positions.sort_by_key(|&(id, _)| id);
+        positions
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Moves nodes to positions computed by `Snarl::force_directed_layout`.
+    ///
+    /// Moves are recorded for `SnarlHistory` as any other move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{ForceLayoutParams, InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let params = ForceLayoutParams {
+    ///     spring_length: 150.0,
+    ///     ..Default::default()
+    /// };
+    /// snarl.force_layout(100, params);
+    ///
+    /// let pos = snarl.node_positions().map(|(_, pos)| pos).collect::<Vec<_>>();
+    /// let distance = pos[0].distance(pos[1]);
+    /// assert!((distance - 150.0).abs() < 10.0, "{distance}");
+    /// ```
+    pub fn force_layout(&mut self, iterations: usize, params: ForceLayoutParams) {
+        for (id, pos) in self.force_directed_layout(iterations, &params) {
+            self.set_node_pos(id, pos);
+        }
+    }
+
+    /// Computes node positions with a spring-electrical model,
+    /// starting from current positions.
+    ///
+    /// All nodes repel each other and nodes connected with wires
+    /// are pulled together by springs.
+    /// Runs exactly `iterations` steps, so the result is deterministic
+    /// for given positions, wires and parameters.
+    ///
+    /// Returns node identifiers with target positions in ascending order of identifiers,
+    /// suitable for `Snarl::animate_to_layout`.
+    pub fn force_directed_layout(
+        &self,
+        iterations: usize,
+        params: &ForceLayoutParams,
+    ) -> Vec<(NodeId, Pos2)> {
+        let nodes = self.nodes.iter().map(|(idx, _)| idx).collect::<Vec<_>>();
+        let mut slot = vec![0; self.nodes.capacity()];
+        for (i, &idx) in nodes.iter().enumerate() {
+            slot[idx] = i;
+        }
+
+        let mut springs = self
+            .wires
+            .iter()
+            .filter(|wire| wire.out_pin.node != wire.in_pin.node)
+            .map(|wire| {
+                let (a, b) = (slot[wire.out_pin.node.idx], slot[wire.in_pin.node.idx]);
+                (a.min(b), a.max(b))
+            })
+            .collect::<Vec<_>>();
+        springs.sort_unstable();
+        springs.dedup();
+
+        let mut pos = nodes
+            .iter()
+            .map(|&idx| self.nodes[idx].pos)
+            .collect::<Vec<_>>();
+        let mut velocity = vec![Vec2::ZERO; nodes.len()];
+        let mut force = vec![Vec2::ZERO; nodes.len()];
+        let mut rng = params.seed;
+
+        for _ in 0..iterations {
+            force.fill(Vec2::ZERO);
+
+            for a in 0..pos.len() {
+                for b in a + 1..pos.len() {
+                    let mut delta = pos[b] - pos[a];
+                    if delta.length_sq() < f32::EPSILON {
+                        delta = random_direction(&mut rng);
+                    }
+                    let distance = delta.length().max(1.0);
+                    let push = delta.normalized() * params.repulsion / (distance * distance);
+                    force[a] -= push;
+                    force[b] += push;
+                }
+            }
+
+            for &(a, b) in &springs {
+                let mut delta = pos[b] - pos[a];
+                if delta.length_sq() < f32::EPSILON {
+                    delta = random_direction(&mut rng);
+                }
+                let pull =
+                    delta.normalized() * (delta.length() - params.spring_length) * SPRING_STIFFNESS;
+                force[a] += pull;
+                force[b] -= pull;
+            }
+
+            for i in 0..pos.len() {
+                velocity[i] = (velocity[i] + force[i]) * (1.0 - params.damping);
+                if velocity[i].length() > MAX_STEP {
+                    velocity[i] = velocity[i].normalized() * MAX_STEP;
+                }
+                pos[i] += velocity[i];
+            }
+        }
+
+        nodes
+            .into_iter()
+            .map(|idx| self.id_at(idx))
+            .zip(pos)
+            .collect()
+    }
+}
+
+/// Returns unit vector in pseudo-random direction and advances the `state`.
+fn random_direction(state: &mut u64) -> Vec2 {
+    // SplitMix64.
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    let angle = (z >> 40) as f32 / (1u64 << 24) as f32 * std::f32::consts::TAU;
+    vec2(angle.cos(), angle.sin())
+}
+
+/// Sorts nodes of the layer by the mean order of their neighbors.
+/// Nodes without neighbors keep their order.
+fn sort_layer<I>(layer: &mut [usize], order: &mut [f32], neighbors: impl Fn(usize) -> I)
+where
+    I: Iterator<Item = usize>,
+{
+    let mut keys = layer
+        .iter()
+        .map(|&idx| {
+            let (sum, count) =
+                neighbors(idx).fold((0.0, 0), |(sum, count), n| (sum + order[n], count + 1));
+            let key = if count > 0 {
+                sum / count as f32
+            } else {
+                order[idx]
+            };
+            (key, idx)
+        })
+        .collect::<Vec<_>>();
+
+    keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (i, (_, idx)) in keys.into_iter().enumerate() {
+        layer[i] = idx;
+        order[idx] = i as f32;
+    }
+}
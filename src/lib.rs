@@ -7,11 +7,29 @@
 
 pub mod ui;
 
+mod clipboard;
+mod comment;
+mod diff;
+mod graph;
+mod history;
+mod layout;
+
 use std::cell::RefCell;
 
-use egui::ahash::HashSet;
+use egui::ahash::{HashMap, HashSet};
 use slab::Slab;
 
+use self::history::HistoryOp;
+
+pub use self::{
+    clipboard::SnarlClipboard,
+    comment::SnarlComment,
+    diff::SnarlDiff,
+    graph::CycleError,
+    history::SnarlHistory,
+    layout::{ForceLayoutParams, LayoutParams},
+};
+
 impl<T> Default for Snarl<T> {
     fn default() -> Self {
         Snarl::new()
@@ -23,24 +41,95 @@ impl<T> Default for Snarl<T> {
 struct Node<T> {
     value: RefCell<T>,
     pos: egui::Pos2,
+
+    /// Only header of collapsed node is shown.
+    #[cfg_attr(feature = "serde", serde(default))]
+    collapsed: bool,
+
+    /// Size set by resizing the node, overrides `SnarlViewer::size_hint`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    size: Option<egui::Vec2>,
 }
 
-/// Output pin identifier. Cosists of node index and pin index.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// Stable identifier of a node.
+///
+/// Node indices are reused after nodes are removed,
+/// so an index kept outside of the Snarl may start to refer to another node.
+/// `NodeId` pairs the index with a generation of its slot,
+/// so identifier of a removed node never resolves to a node added later.
+///
+/// `Snarl::add_node` returns identifier of the new node,
+/// `Snarl::node_id` and `Snarl::node_idx` convert between identifiers and indices.
+/// `InPinId` and `OutPinId` refer to nodes by identifier,
+/// so pins of a removed node are not resolved either.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeId {
+    idx: usize,
+    generation: u32,
+}
+
+impl NodeId {
+    /// Returns index of the node, which may be reused after the node is removed.
+    pub fn idx(&self) -> usize {
+        self.idx
+    }
+
+    /// Returns generation of the node slot.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Error returned from `Snarl::insert_node` when the requested index is taken by another node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OccupiedError {
+    /// Index that is already occupied.
+    pub idx: usize,
+}
+
+impl std::fmt::Display for OccupiedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "node index {} is already occupied", self.idx)
+    }
+}
+
+impl std::error::Error for OccupiedError {}
+
+/// Output pin identifier. Cosists of node identifier and pin index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OutPinId {
-    pub node: usize,
+    pub node: NodeId,
     pub output: usize,
 }
 
-/// Input pin identifier. Cosists of node index and pin index.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// Input pin identifier. Cosists of node identifier and pin index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InPinId {
-    pub node: usize,
+    pub node: NodeId,
     pub input: usize,
 }
 
+/// Identifier of either input or output pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnyPinId {
+    Out(OutPinId),
+    In(InPinId),
+}
+
+impl AnyPinId {
+    /// Returns identifier of the node the pin belongs to.
+    pub fn node(&self) -> NodeId {
+        match self {
+            AnyPinId::Out(pin) => pin.node,
+            AnyPinId::In(pin) => pin.node,
+        }
+    }
+}
+
 /// Connection between two nodes.
 ///
 /// Nodes may support multiple connections to the same input or output.
@@ -49,10 +138,7 @@ pub struct InPinId {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Wire {
-    #[cfg_attr(feature = "serde", serde(flatten))]
     out_pin: OutPinId,
-
-    #[cfg_attr(feature = "serde", serde(flatten))]
     in_pin: InPinId,
 }
 
@@ -68,48 +154,105 @@ fn wire_pins(out_pin: OutPinId, in_pin: InPinId) -> Wire {
 )]
 struct Wires {
     wires: HashSet<Wire>,
+
+    /// Insertion counter of each wire, used to order wires from oldest to newest.
+    /// Not serialized, wires loaded from serialized data are the oldest.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ages: HashMap<Wire, u64>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    next_age: u64,
 }
 
 impl Wires {
     pub fn new() -> Self {
         Wires {
             wires: HashSet::with_hasher(egui::ahash::RandomState::new()),
+            ages: HashMap::with_hasher(egui::ahash::RandomState::new()),
+            next_age: 0,
         }
     }
 
     pub fn insert(&mut self, wire: Wire) -> bool {
-        self.wires.insert(wire)
+        if !self.wires.insert(wire) {
+            return false;
+        }
+        self.next_age += 1;
+        self.ages.insert(wire, self.next_age);
+        true
     }
 
     pub fn remove(&mut self, wire: &Wire) -> bool {
+        self.ages.remove(wire);
         self.wires.remove(wire)
     }
 
-    pub fn drop_node(&mut self, node: usize) {
-        self.wires
-            .retain(|wire| wire.out_pin.node != node && wire.in_pin.node != node);
+    /// Returns insertion counter of the wire.
+    pub fn age(&self, wire: &Wire) -> u64 {
+        self.ages.get(wire).copied().unwrap_or(0)
+    }
+
+    /// Drops wires matching the predicate.
+    /// Returns dropped wires.
+    fn drop_where(&mut self, f: impl Fn(&Wire) -> bool) -> Vec<Wire> {
+        let mut dropped = Vec::new();
+        self.wires.retain(|wire| {
+            if f(wire) {
+                dropped.push(*wire);
+                false
+            } else {
+                true
+            }
+        });
+        for wire in &dropped {
+            self.ages.remove(wire);
+        }
+        dropped
     }
 
-    pub fn drop_inputs(&mut self, pin: InPinId) {
-        self.wires.retain(|wire| wire.in_pin != pin);
+    pub fn drop_node(&mut self, node: NodeId) -> Vec<Wire> {
+        self.drop_where(|wire| wire.out_pin.node == node || wire.in_pin.node == node)
     }
 
-    pub fn drop_outputs(&mut self, pin: OutPinId) {
-        self.wires.retain(|wire| wire.out_pin != pin);
+    pub fn drop_inputs(&mut self, pin: InPinId) -> Vec<Wire> {
+        self.drop_where(|wire| wire.in_pin == pin)
+    }
+
+    pub fn drop_outputs(&mut self, pin: OutPinId) -> Vec<Wire> {
+        self.drop_where(|wire| wire.out_pin == pin)
+    }
+
+    /// Drops wires connected to pins of the node beyond given pin counts.
+    pub fn drop_excess_pins(&mut self, node: NodeId, inputs: usize, outputs: usize) -> Vec<Wire> {
+        self.drop_where(|wire| {
+            wire.in_pin.node == node && wire.in_pin.input >= inputs
+                || wire.out_pin.node == node && wire.out_pin.output >= outputs
+        })
     }
 
     pub fn wired_inputs(&self, out_pin: OutPinId) -> impl Iterator<Item = InPinId> + '_ {
         self.wires
             .iter()
             .filter(move |wire| wire.out_pin == out_pin)
-            .map(|wire| (wire.in_pin))
+            .map(|wire| wire.in_pin)
     }
 
     pub fn wired_outputs(&self, in_pin: InPinId) -> impl Iterator<Item = OutPinId> + '_ {
         self.wires
             .iter()
             .filter(move |wire| wire.in_pin == in_pin)
-            .map(|wire| (wire.out_pin))
+            .map(|wire| wire.out_pin)
+    }
+
+    pub fn contains(&self, wire: &Wire) -> bool {
+        self.wires.contains(wire)
+    }
+
+    pub fn has_pin(&self, pin: AnyPinId) -> bool {
+        match pin {
+            AnyPinId::Out(pin) => self.wires.iter().any(|wire| wire.out_pin == pin),
+            AnyPinId::In(pin) => self.wires.iter().any(|wire| wire.in_pin == pin),
+        }
     }
 
     pub fn iter(&self) -> impl Iterator<Item = Wire> + '_ {
@@ -118,12 +261,65 @@ impl Wires {
 }
 
 /// Snarl is node-graph container.
+///
+/// # Serialization
+///
+/// With `serde` feature enabled `Snarl<T>` implements `Serialize` and `Deserialize`
+/// when `T` does.
+/// Node values, positions, collapsed state, sizes, indices, draw order, wires and comments are persisted.
+/// Node indices are preserved on deserialization, so wires keep pointing at the same pins.
+///
+/// Transient UI state is not serialized:
+/// layout of the last frame, frozen layout, selection, running animations
+/// and pan and zoom of the viewports.
+/// Use `Snarl::transform` and `Snarl::set_transform` to save and restore pan and zoom.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Snarl<T> {
     nodes: Slab<Node<T>>,
     draw_order: Vec<usize>,
     wires: Wires,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    comments: Slab<SnarlComment>,
+
+    /// Geometry of the last rendered frame. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    layout: ui::LayoutSnapshot,
+
+    /// Layout captured by `Snarl::freeze_layout`. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    frozen_layout: Option<ui::LayoutSnapshot>,
+
+    /// Selected nodes in order of selection. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    selection: Vec<NodeId>,
+
+    /// Nodes outlined by `Snarl::show`, e.g. search results. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    highlighted: Vec<NodeId>,
+
+    /// Selection reported to `SnarlViewer::on_selection_changed` by the last `Snarl::show`.
+    /// Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    shown_selection: Vec<NodeId>,
+
+    /// Running node animation. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    animation: Option<ui::NodesAnimation>,
+
+    /// Changes recorded for `SnarlHistory`. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history_log: Option<history::HistoryLog<T>>,
+
+    /// Pan and zoom of the viewports the Snarl was shown in, keyed by id. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    viewports: HashMap<egui::Id, ui::SnarlTransform>,
+
+    /// Generations of node slots, bumped when node is removed.
+    /// Missing entries are zero.
+    #[cfg_attr(feature = "serde", serde(default))]
+    generations: Vec<u32>,
 }
 
 impl<T> Snarl<T> {
@@ -140,58 +336,1337 @@ impl<T> Snarl<T> {
             nodes: Slab::new(),
             draw_order: Vec::new(),
             wires: Wires::new(),
+            comments: Slab::new(),
+            layout: ui::LayoutSnapshot::default(),
+            frozen_layout: None,
+            selection: Vec::new(),
+            shown_selection: Vec::new(),
+            highlighted: Vec::new(),
+            animation: None,
+            history_log: None,
+            viewports: HashMap::default(),
+            generations: Vec::new(),
         }
     }
 
     /// Adds a node to the Snarl.
-    /// Returns the index of the node.
+    /// Returns identifier of the node.
     ///
     /// # Examples
     ///
     /// ```
     /// # use egui_snarl::Snarl;
     /// let mut snarl = Snarl::<()>::new();
-    /// snarl.add_node(());
+    /// snarl.add_node((), egui::pos2(0.0, 0.0));
     /// ```
-    pub fn add_node(&mut self, node: T, pos: egui::Pos2) -> usize {
+    pub fn add_node(&mut self, node: T, pos: egui::Pos2) -> NodeId {
         let idx = self.nodes.insert(Node {
             value: RefCell::new(node),
             pos,
+            collapsed: false,
+            size: None,
         });
+        let id = self.id_at(idx);
         self.draw_order.push(idx);
-        idx
+        self.record(HistoryOp::RemoveNode { node: id });
+        id
+    }
+
+    /// Adds a node to the Snarl at the given index.
+    /// Returns identifier of the new node.
+    ///
+    /// Unlike `Snarl::add_node` the index is chosen by the caller,
+    /// so nodes restored from a saved graph keep their indices
+    /// and saved wires refer to the same nodes without remapping.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OccupiedError` if a node with the same index already exists.
+    /// The Snarl is left unchanged in this case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OccupiedError, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<i32>::new();
+    /// let a = snarl.add_node(0, egui::pos2(0.0, 0.0));
+    /// let id = snarl.insert_node(5, 5, egui::pos2(100.0, 0.0)).unwrap();
+    ///
+    /// assert_eq!(id.idx(), 5);
+    /// assert_eq!(snarl.node_count(), 2);
+    /// assert_eq!(*snarl.get_node(id).unwrap().borrow(), 5);
+    /// assert_eq!(snarl.insert_node(5, 6, egui::pos2(0.0, 0.0)), Err(OccupiedError { idx: 5 }));
+    ///
+    /// // Saved wire resolves to the inserted node.
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let to = InPinId { node: id, input: 0 };
+    /// snarl.connect(from, to);
+    /// assert!(snarl.connected(from, to));
+    /// assert_eq!(*snarl.in_pin(to).unwrap().node.borrow(), 5);
+    /// assert_eq!(snarl.in_pin_remotes(to), [from]);
+    ///
+    /// // Gaps left below the index are still filled by `add_node`.
+    /// let b = snarl.add_node(1, egui::pos2(0.0, 0.0));
+    /// assert!(b != a && b != id);
+    /// ```
+    pub fn insert_node(
+        &mut self,
+        idx: usize,
+        node: T,
+        pos: egui::Pos2,
+    ) -> Result<NodeId, OccupiedError> {
+        let id = self.id_at(idx);
+        self.insert_node_with_id(id, node, pos)?;
+        Ok(id)
+    }
+
+    /// Inserts node with specific identifier and records it for `SnarlHistory`.
+    fn insert_node_with_id(
+        &mut self,
+        id: NodeId,
+        node: T,
+        pos: egui::Pos2,
+    ) -> Result<(), OccupiedError> {
+        self.insert_node_at(id, node, pos)?;
+        self.record(HistoryOp::RemoveNode { node: id });
+        Ok(())
     }
 
     /// Removes a node from the Snarl.
     /// Returns the node if it was removed.
     ///
+    /// All wires connected to the node's pins are removed as well.
+    /// Identifiers of other nodes are not affected,
+    /// so `InPinId` and `OutPinId` of remaining nodes stay valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    ///
     /// # Examples
     ///
     /// ```
-    /// # use egui_snarl::Snarl;
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<i32>::new();
+    /// let a = snarl.add_node(0, egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node(1, egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node(2, egui::pos2(200.0, 0.0));
+    ///
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let to = InPinId { node: c, input: 0 };
+    /// snarl.connect(from, to);
+    ///
+    /// assert_eq!(snarl.remove_node(b), 1);
+    /// assert!(snarl.connected(from, to));
+    /// ```
+    pub fn remove_node(&mut self, id: NodeId) -> T {
+        assert!(self.contains_node(id), "node does not exist");
+
+        let dropped = self.wires.drop_node(id);
+        self.record_dropped_wires(dropped);
+        if let Some(log) = &mut self.history_log {
+            log.record_removed(id, &self.nodes[id.idx]);
+        }
+        self.remove_node_unrecorded(id)
+    }
+
+    /// Removes all nodes, wires and comments.
+    ///
+    /// Selection is cleared and indices of new nodes start from zero again.
+    /// Identifiers of removed nodes are not reused.
+    /// Removal of nodes and wires is recorded for `SnarlHistory`, so undo restores them.
+    /// Comments are not recorded and are not restored.
+    /// Layout of the last frame and frozen layout are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
     /// let mut snarl = Snarl::<()>::new();
-    /// let node = snarl.add_node(());
-    /// snarl.remove_node(node);
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.select_node(b);
+    /// assert_eq!(snarl.node_count(), 2);
+    ///
+    /// snarl.clear();
+    ///
+    /// assert!(snarl.is_empty());
+    /// assert_eq!(snarl.node_count(), 0);
+    /// assert_eq!(snarl.wires().count(), 0);
+    /// assert_eq!(snarl.selected_nodes().count(), 0);
+    ///
+    /// let c = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// assert_eq!(c.idx(), 0);
+    /// assert_ne!(c, a);
     /// ```
-    pub fn remove_node(&mut self, idx: usize) -> T {
+    pub fn clear(&mut self) {
+        let nodes = self.node_ids().collect::<Vec<_>>();
+        for id in nodes {
+            self.remove_node(id);
+        }
+
+        // Resets allocation of indices.
+        self.nodes.clear();
+        self.comments.clear();
+        self.animation = None;
+        self.layout = ui::LayoutSnapshot::default();
+        self.frozen_layout = None;
+    }
+
+    /// Returns true if the Snarl has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn remove_node_unrecorded(&mut self, id: NodeId) -> T {
+        let idx = id.idx;
         let value = self.nodes.remove(idx).value.into_inner();
-        self.wires.drop_node(idx);
+        self.set_generation(NodeId {
+            idx,
+            generation: id.generation.wrapping_add(1),
+        });
+        self.wires.drop_node(id);
         let order = self.draw_order.iter().position(|&i| i == idx).unwrap();
         self.draw_order.remove(order);
+        self.deselect_node(id);
+        self.highlighted.retain(|&i| i != id);
         value
     }
 
+    /// Returns identifier of the node in the slot, whether the slot is occupied or not.
+    fn id_at(&self, idx: usize) -> NodeId {
+        NodeId {
+            idx,
+            generation: self.generations.get(idx).copied().unwrap_or(0),
+        }
+    }
+
+    /// Sets generation of the slot to the generation of the identifier.
+    fn set_generation(&mut self, id: NodeId) {
+        if self.generations.len() <= id.idx {
+            self.generations.resize(id.idx + 1, 0);
+        }
+        self.generations[id.idx] = id.generation;
+    }
+
+    /// Returns the node unless the identifier is stale.
+    fn node(&self, id: NodeId) -> Option<&Node<T>> {
+        let node = self.nodes.get(id.idx)?;
+        (self.id_at(id.idx) == id).then_some(node)
+    }
+
+    /// Returns the node unless the identifier is stale.
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut Node<T>> {
+        if self.id_at(id.idx) != id {
+            return None;
+        }
+        self.nodes.get_mut(id.idx)
+    }
+
+    /// Returns the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    fn expect_node(&self, id: NodeId) -> &Node<T> {
+        self.node(id).expect("node does not exist")
+    }
+
+    /// Returns the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    fn expect_node_mut(&mut self, id: NodeId) -> &mut Node<T> {
+        self.node_mut(id).expect("node does not exist")
+    }
+
+    /// Iterates over identifiers of all nodes.
+    fn node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.iter().map(|(idx, _)| self.id_at(idx))
+    }
+
+    /// Moves the node by `delta` in graph space, as if it was dragged.
+    ///
+    /// If the node is selected, the whole selection is moved together.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node((), egui::pos2(200.0, 0.0));
+    ///
+    /// snarl.select_node(a);
+    /// snarl.select_node(b);
+    /// snarl.drag_node(b, egui::vec2(10.0, 20.0));
+    ///
+    /// assert_eq!(
+    ///     snarl.node_positions().collect::<Vec<_>>(),
+    ///     [
+    ///         (a, egui::pos2(10.0, 20.0)),
+    ///         (b, egui::pos2(110.0, 20.0)),
+    ///         (c, egui::pos2(200.0, 0.0)),
+    ///     ]
+    /// );
+    /// ```
+    pub fn drag_node(&mut self, id: NodeId, delta: egui::Vec2) {
+        if self.is_selected(id) {
+            for i in 0..self.selection.len() {
+                self.move_node(self.selection[i], delta);
+            }
+        } else {
+            self.move_node(id, delta);
+        }
+    }
+
+    /// Rounds position of the node to the nearest cell of the `grid`.
+    ///
+    /// If the node is selected, the whole selection is snapped, each node separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    ///
+    /// snarl.drag_node(a, egui::vec2(27.0, -12.0));
+    /// snarl.snap_node(a, egui::vec2(10.0, 20.0));
+    /// assert_eq!(snarl.node_positions().next(), Some((a, egui::pos2(30.0, -20.0))));
+    /// ```
+    pub fn snap_node(&mut self, id: NodeId, grid: egui::Vec2) {
+        if self.is_selected(id) {
+            for i in 0..self.selection.len() {
+                self.snap_single_node(self.selection[i], grid);
+            }
+        } else {
+            self.snap_single_node(id, grid);
+        }
+    }
+
+    /// Rounds position of the node to the nearest cell of the `grid`, ignoring selection.
+    fn snap_single_node(&mut self, id: NodeId, grid: egui::Vec2) {
+        let pos = self.expect_node(id).pos;
+        let snapped = egui::pos2(
+            (pos.x / grid.x).round() * grid.x,
+            (pos.y / grid.y).round() * grid.y,
+        );
+        self.move_node(id, snapped - pos);
+    }
+
+    fn move_node(&mut self, id: NodeId, delta: egui::Vec2) {
+        let node = self.expect_node_mut(id);
+        let pos = node.pos;
+        node.pos += delta;
+        self.record(HistoryOp::MoveNode { node: id, pos });
+    }
+
+    /// Returns identifier of the node with given index if it exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, Snarl};
+    /// let mut snarl = Snarl::<&str>::new();
+    /// let a = snarl.add_node("a", egui::pos2(0.0, 0.0));
+    /// assert_eq!(snarl.node_id(a.idx()), Some(a));
+    /// assert_eq!(snarl.node_idx(a), Some(a.idx()));
+    ///
+    /// // New node takes the index of the removed one.
+    /// snarl.remove_node(a);
+    /// let b = snarl.add_node("b", egui::pos2(0.0, 0.0));
+    /// assert_eq!(b.idx(), a.idx());
+    ///
+    /// // Identifier of the removed node does not resolve to the new node.
+    /// assert_ne!(b, a);
+    /// assert_eq!(snarl.node_idx(a), None);
+    /// assert!(snarl.get_node(a).is_none());
+    ///
+    /// // Neither do pins of the removed node.
+    /// assert!(snarl.in_pin(InPinId { node: a, input: 0 }).is_none());
+    /// ```
+    pub fn node_id(&self, idx: usize) -> Option<NodeId> {
+        if !self.nodes.contains(idx) {
+            return None;
+        }
+        Some(self.id_at(idx))
+    }
+
+    /// Returns index of the node with given identifier
+    /// or `None` if the node was removed.
+    pub fn node_idx(&self, id: NodeId) -> Option<usize> {
+        self.node(id).map(|_| id.idx)
+    }
+
+    /// Returns true if the node exists.
+    pub fn contains_node(&self, id: NodeId) -> bool {
+        self.node(id).is_some()
+    }
+
+    /// Returns the node value if it exists.
+    pub fn get_node(&self, id: NodeId) -> Option<&RefCell<T>> {
+        self.node(id).map(|node| &node.value)
+    }
+
+    /// Returns mutable reference to the node value if it exists.
+    pub fn get_node_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.node_mut(id).map(|node| node.value.get_mut())
+    }
+
+    /// Iterates over all nodes with their identifiers.
+    /// Removed nodes are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<i32>::new();
+    /// let a = snarl.add_node(0, egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node(1, egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node(2, egui::pos2(200.0, 0.0));
+    /// snarl.remove_node(b);
+    ///
+    /// let nodes = snarl
+    ///     .nodes()
+    ///     .map(|(idx, value)| (idx, *value.borrow()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(nodes, [(a, 0), (c, 2)]);
+    /// assert!(snarl.get_node(b).is_none());
+    /// ```
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &RefCell<T>)> + '_ {
+        self.nodes
+            .iter()
+            .map(|(idx, node)| (self.id_at(idx), &node.value))
+    }
+
+    /// Iterates over mutable references to all nodes with their identifiers.
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = (NodeId, &mut T)> + '_ {
+        let generations = &self.generations;
+        self.nodes.iter_mut().map(|(idx, node)| {
+            let generation = generations.get(idx).copied().unwrap_or(0);
+            (NodeId { idx, generation }, node.value.get_mut())
+        })
+    }
+
+    /// Returns identifiers of nodes matching the predicate, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::new();
+    /// let add = snarl.add_node("Add", egui::pos2(0.0, 0.0));
+    /// let sub = snarl.add_node("Subtract", egui::pos2(100.0, 0.0));
+    /// let pad = snarl.add_node("Padding", egui::pos2(200.0, 0.0));
+    ///
+    /// let found = snarl.find_nodes(|_, name| name.to_lowercase().contains("ad"));
+    /// assert_eq!(found, [add, pad]);
+    ///
+    /// // Outline found nodes when the Snarl is shown.
+    /// snarl.set_highlighted(found);
+    /// assert!(snarl.is_highlighted(pad) && !snarl.is_highlighted(sub));
+    /// ```
+    pub fn find_nodes(&self, pred: impl Fn(NodeId, &T) -> bool) -> Vec<NodeId> {
+        self.nodes()
+            .filter(|(id, value)| pred(*id, &value.borrow()))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Sets nodes outlined with `SnarlStyle::search_highlight_stroke`
+    /// replacing previously highlighted nodes.
+    /// Nodes that do not exist are skipped.
+    pub fn set_highlighted(&mut self, nodes: impl IntoIterator<Item = NodeId>) {
+        self.highlighted.clear();
+        for id in nodes {
+            if self.contains_node(id) && !self.highlighted.contains(&id) {
+                self.highlighted.push(id);
+            }
+        }
+    }
+
+    /// Returns true if the node is highlighted.
+    pub fn is_highlighted(&self, id: NodeId) -> bool {
+        self.highlighted.contains(&id)
+    }
+
+    /// Returns highlighted nodes.
+    pub fn highlighted_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.highlighted.iter().copied()
+    }
+
+    /// Iterates over positions of all nodes with their identifiers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(10.0, 20.0));
+    ///
+    /// assert_eq!(
+    ///     snarl.node_positions().collect::<Vec<_>>(),
+    ///     [(a, egui::pos2(10.0, 20.0))]
+    /// );
+    /// ```
+    pub fn node_positions(&self) -> impl Iterator<Item = (NodeId, egui::Pos2)> + '_ {
+        self.nodes
+            .iter()
+            .map(|(idx, node)| (self.id_at(idx), node.pos))
+    }
+
+    /// Returns position of the node in graph space if it exists.
+    pub fn node_pos(&self, id: NodeId) -> Option<egui::Pos2> {
+        self.node(id).map(|node| node.pos)
+    }
+
+    /// Moves the node to `pos` in graph space.
+    ///
+    /// Node and its wires are drawn at the new position on the next `Snarl::show`.
+    /// Only this node is moved regardless of selection, unlike `Snarl::drag_node`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.add_node((), pos2(0.0, 0.0));
+    ///
+    /// snarl.set_node_pos(a, pos2(50.0, 30.0));
+    /// assert_eq!(snarl.node_pos(a), Some(pos2(50.0, 30.0)));
+    /// ```
+    pub fn set_node_pos(&mut self, id: NodeId, pos: egui::Pos2) {
+        let delta = pos - self.expect_node(id).pos;
+        self.move_node(id, delta);
+    }
+
+    /// Collapses node to its header or expands it.
+    ///
+    /// Wires of collapsed node are attached to the edges of its header.
+    /// Has no effect on how nodes are shown if `SnarlViewer::collapsible` returns false.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.add_node((), pos2(0.0, 0.0));
+    ///
+    /// snarl.set_collapsed(a, true);
+    /// assert!(snarl.is_collapsed(a));
+    /// ```
+    pub fn set_collapsed(&mut self, id: NodeId, collapsed: bool) {
+        self.expect_node_mut(id).collapsed = collapsed;
+    }
+
+    /// Returns true if the node is collapsed.
+    pub fn is_collapsed(&self, id: NodeId) -> bool {
+        self.node(id).is_some_and(|node| node.collapsed)
+    }
+
+    /// Returns size of the node set by resizing it, in graph space.
+    ///
+    /// Returns `None` if the node does not exist or was not resized,
+    /// in which case `SnarlViewer::size_hint` is used.
+    pub fn node_size(&self, id: NodeId) -> Option<egui::Vec2> {
+        self.node(id).and_then(|node| node.size)
+    }
+
+    /// Sets size of the node overriding `SnarlViewer::size_hint`.
+    /// `None` restores the size hint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    pub fn set_node_size(&mut self, id: NodeId, size: Option<egui::Vec2>) {
+        self.expect_node_mut(id).size = size;
+    }
+
+    /// Grows node by `delta` in graph space, keeping it at least `min_size`.
+    ///
+    /// Node that was not resized before starts from its `SnarlViewer::size_hint`.
+    /// This is what dragging resize handle of a `SnarlViewer::resizable` node does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    pub fn resize_node<V>(
+        &mut self,
+        id: NodeId,
+        delta: egui::Vec2,
+        min_size: egui::Vec2,
+        viewer: &V,
+    ) where
+        V: ui::SnarlViewer<T>,
+    {
+        let node = self.expect_node_mut(id);
+        let size = node
+            .size
+            .unwrap_or_else(|| viewer.size_hint(node.value.get_mut()));
+        node.size = Some((size + delta).max(min_size));
+    }
+
+    /// Replaces value of the node in place.
+    /// Returns the old value.
+    ///
+    /// Wires are preserved, except those connected to pins
+    /// that no longer exist according to the viewer's
+    /// `SnarlViewer::inputs` and `SnarlViewer::outputs` for the new value.
+    /// If pin counts change, `SnarlViewer::on_pin_count_changed` is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    pub fn replace_node<V>(&mut self, id: NodeId, value: T, viewer: &mut V) -> T
+    where
+        V: ui::SnarlViewer<T>,
+    {
+        let node = self.expect_node_mut(id);
+
+        let old_inputs = viewer.inputs(node.value.get_mut());
+        let old_outputs = viewer.outputs(node.value.get_mut());
+
+        let old = node.value.replace(value);
+
+        let inputs = viewer.inputs(node.value.get_mut());
+        let outputs = viewer.outputs(node.value.get_mut());
+
+        if inputs != old_inputs || outputs != old_outputs {
+            let dropped = self.wires.drop_excess_pins(id, inputs, outputs);
+            self.record_dropped_wires(dropped);
+            viewer.on_pin_count_changed(id, &self.nodes[id.idx].value, inputs, outputs);
+        }
+        if let Some(log) = &mut self.history_log {
+            log.record_replaced(id, &old);
+        }
+
+        old
+    }
+
     /// Connects two nodes.
     /// Returns true if the connection was successful.
-    /// Returns false if the connection already exists.
+    /// Returns false if the connection already exists
+    /// or either node does not exist.
     pub fn connect(&mut self, from: OutPinId, to: InPinId) -> bool {
-        debug_assert!(self.nodes.contains(from.node));
-        debug_assert!(self.nodes.contains(to.node));
+        if !self.contains_node(from.node) || !self.contains_node(to.node) {
+            return false;
+        }
 
         let wire = Wire {
             out_pin: from,
             in_pin: to,
         };
-        self.wires.insert(wire)
+        let inserted = self.wires.insert(wire);
+        if inserted {
+            self.record(HistoryOp::Disconnect { from, to });
+        }
+        inserted
+    }
+
+    /// Disconnects two nodes.
+    /// Returns true if the connection existed.
+    pub fn disconnect(&mut self, from: OutPinId, to: InPinId) -> bool {
+        let removed = self.wires.remove(&wire_pins(from, to));
+        if removed {
+            self.record(HistoryOp::Connect { from, to });
+        }
+        removed
+    }
+
+    /// Connects many pins at once, skipping wires to pins that do not exist.
+    ///
+    /// Pin exists if its node exists and its index is less than
+    /// `SnarlViewer::outputs` or `SnarlViewer::inputs` of the node.
+    /// Viewer's `SnarlViewer::connect` is not consulted.
+    ///
+    /// Returns for each wire true if it was connected
+    /// and false if it was skipped or already existed.
+    pub fn connect_many<V>(
+        &mut self,
+        wires: impl IntoIterator<Item = (OutPinId, InPinId)>,
+        viewer: &mut V,
+    ) -> Vec<bool>
+    where
+        V: ui::SnarlViewer<T>,
+    {
+        wires
+            .into_iter()
+            .map(|(from, to)| self.pins_exist(from, to, viewer) && self.connect(from, to))
+            .collect()
+    }
+
+    /// Disconnects many pins at once.
+    ///
+    /// Returns for each wire true if it existed.
+    pub fn disconnect_many(
+        &mut self,
+        wires: impl IntoIterator<Item = (OutPinId, InPinId)>,
+    ) -> Vec<bool> {
+        wires
+            .into_iter()
+            .map(|(from, to)| self.disconnect(from, to))
+            .collect()
+    }
+
+    /// Removes wires to pins that no longer exist, e.g. after node's inputs shrink.
+    /// Returns number of removed wires.
+    ///
+    /// Pins are checked the same way as in `Snarl::connect_many`.
+    /// `SnarlStyle::prune_invalid_wires` makes `Snarl::show` call it every frame.
+    pub fn prune_invalid_wires<V>(&mut self, viewer: &mut V) -> usize
+    where
+        V: ui::SnarlViewer<T>,
+    {
+        let invalid = self
+            .wires()
+            .filter(|&(from, to)| !self.pins_exist(from, to, viewer))
+            .collect::<Vec<_>>();
+
+        for &(from, to) in &invalid {
+            self.disconnect(from, to);
+        }
+        invalid.len()
+    }
+
+    /// Returns pins a wire dragged from the pin `from` can be connected to
+    /// according to `SnarlViewer::can_connect`, in ascending order.
+    ///
+    /// Only pins of the other kind are considered, i.e. inputs for an output and vice versa.
+    /// `Snarl::show` uses it to tint pins while a wire is dragged.
+    pub fn compatible_pins<V>(&self, from: AnyPinId, viewer: &mut V) -> Vec<AnyPinId>
+    where
+        V: ui::SnarlViewer<T>,
+    {
+        if !self.contains_node(from.node()) {
+            return Vec::new();
+        }
+
+        let mut pins = Vec::new();
+        for (id, node) in self.nodes() {
+            match from {
+                AnyPinId::Out(out_pin) => {
+                    let from = ui::OutPin::output(self, out_pin);
+                    for input in 0..viewer.inputs(&node.borrow()) {
+                        let to = ui::InPin::input(self, InPinId { node: id, input });
+                        if viewer.can_connect(&from, &to) {
+                            pins.push(AnyPinId::In(to.id));
+                        }
+                    }
+                }
+                AnyPinId::In(in_pin) => {
+                    let to = ui::InPin::input(self, in_pin);
+                    for output in 0..viewer.outputs(&node.borrow()) {
+                        let from = ui::OutPin::output(self, OutPinId { node: id, output });
+                        if viewer.can_connect(&from, &to) {
+                            pins.push(AnyPinId::Out(from.id));
+                        }
+                    }
+                }
+            }
+        }
+        pins.sort_unstable();
+        pins
+    }
+
+    /// Removes all wires connected to any pin of the node.
+    /// Returns number of removed wires.
+    ///
+    /// `Effects::drop_node_wires` queues the same from the viewer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node((), egui::pos2(200.0, 0.0));
+    ///
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: c, input: 1 });
+    ///
+    /// assert_eq!(snarl.drop_node_wires(b), 2);
+    /// assert_eq!(
+    ///     snarl.wires().collect::<Vec<_>>(),
+    ///     [(OutPinId { node: a, output: 0 }, InPinId { node: c, input: 1 })]
+    /// );
+    /// assert_eq!(snarl.drop_node_wires(b), 0);
+    /// ```
+    pub fn drop_node_wires(&mut self, node: NodeId) -> usize {
+        let dropped = self.wires.drop_node(node);
+        let count = dropped.len();
+        self.record_dropped_wires(dropped);
+        count
+    }
+
+    /// Returns true if both pins exist according to the viewer.
+    fn pins_exist<V>(&self, from: OutPinId, to: InPinId, viewer: &mut V) -> bool
+    where
+        V: ui::SnarlViewer<T>,
+    {
+        let (Some(out_node), Some(in_node)) = (self.node(from.node), self.node(to.node)) else {
+            return false;
+        };
+        from.output < viewer.outputs(&out_node.value.borrow())
+            && to.input < viewer.inputs(&in_node.value.borrow())
+    }
+
+    /// Records wires dropped from the Snarl.
+    fn record_dropped_wires(&mut self, dropped: Vec<Wire>) {
+        for wire in dropped {
+            self.record(HistoryOp::Connect {
+                from: wire.out_pin,
+                to: wire.in_pin,
+            });
+        }
+    }
+
+    /// Returns pan and zoom of the viewport with given id.
+    ///
+    /// Returns `None` if the Snarl was not shown with this id yet
+    /// and the transform was not set with `Snarl::set_transform`.
+    ///
+    /// `Snarl::show` also keeps the transform in egui memory under the same id,
+    /// so a new Snarl shown with that id continues where the previous one stopped.
+    /// With `serde` feature it is persisted with egui memory.
+    /// Viewports with different ids start fresh.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{vec2, Id};
+    /// # use egui_snarl::{Snarl, ui::SnarlTransform};
+    /// let id = Id::new("document-1");
+    /// let mut snarl = Snarl::<()>::new();
+    /// assert_eq!(snarl.transform(id), None);
+    ///
+    /// let transform = SnarlTransform { offset: vec2(-40.0, 25.0), scale: 1.5 };
+    /// snarl.set_transform(id, transform);
+    /// assert_eq!(snarl.transform(id), Some(transform));
+    /// ```
+    pub fn transform(&self, id: egui::Id) -> Option<ui::SnarlTransform> {
+        self.viewports.get(&id).copied()
+    }
+
+    /// Sets pan and zoom of the viewport with given id.
+    ///
+    /// Takes effect the next time the Snarl is shown with this id.
+    pub fn set_transform(&mut self, id: egui::Id, transform: ui::SnarlTransform) {
+        self.viewports.insert(id, transform);
+    }
+
+    /// Returns screen space rect of the node from the last call to `Snarl::show`.
+    ///
+    /// Rect reflects pan and zoom of the viewport.
+    /// Returns `None` if the node was not shown in the last frame.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::new();
+    /// let node = snarl.add_node((), pos2(100.0, 40.0));
+    ///
+    /// // Not shown yet.
+    /// assert_eq!(snarl.node_rect(node), None);
+    /// ```
+    pub fn node_rect(&self, id: NodeId) -> Option<egui::Rect> {
+        self.layout.node_rect(id)
+    }
+
+    /// Returns geometry of nodes, pins and wires from the last call to `Snarl::show`.
+    ///
+    /// Snapshot is empty until the Snarl is shown for the first time.
+    /// Positions are in screen space.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let snarl = Snarl::<()>::new();
+    /// assert!(snarl.debug_layout().nodes.is_empty());
+    /// ```
+    pub fn debug_layout(&self) -> &ui::LayoutSnapshot {
+        &self.layout
+    }
+
+    /// Freezes the layout of the last rendered frame.
+    ///
+    /// While frozen, `Snarl::show` places nodes and wires exactly where they were
+    /// when this method was called, ignoring viewer size hints and node dragging.
+    /// This guarantees that exports match the interactive view.
+    ///
+    /// Call `Snarl::thaw_layout` to resume normal layout.
+    pub fn freeze_layout(&mut self) {
+        self.frozen_layout = Some(self.layout.clone());
+    }
+
+    /// Resumes normal layout after `Snarl::freeze_layout`.
+    pub fn thaw_layout(&mut self) {
+        self.frozen_layout = None;
+    }
+
+    /// Returns true if the layout is frozen.
+    pub fn is_layout_frozen(&self) -> bool {
+        self.frozen_layout.is_some()
+    }
+
+    /// Returns true if the output pin is connected to the input pin.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    ///
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let to = InPinId { node: b, input: 0 };
+    ///
+    /// assert!(!snarl.connected(from, to));
+    /// snarl.connect(from, to);
+    /// assert!(snarl.connected(from, to));
+    /// ```
+    pub fn connected(&self, from: OutPinId, to: InPinId) -> bool {
+        self.wires.contains(&wire_pins(from, to))
+    }
+
+    /// Returns true if the pin has at least one wire.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{AnyPinId, InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    ///
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let to = InPinId { node: b, input: 0 };
+    /// snarl.connect(from, to);
+    ///
+    /// assert!(snarl.has_any_connection(AnyPinId::Out(from)));
+    /// assert!(snarl.has_any_connection(AnyPinId::In(to)));
+    /// assert!(!snarl.has_any_connection(AnyPinId::In(InPinId { node: a, input: 0 })));
+    /// ```
+    pub fn has_any_connection(&self, pin: AnyPinId) -> bool {
+        self.wires.has_pin(pin)
+    }
+
+    /// Returns true if the input pin has a wire.
+    ///
+    /// Same as `Snarl::has_any_connection` for an input pin.
+    pub fn is_input_connected(&self, id: InPinId) -> bool {
+        self.wires.has_pin(AnyPinId::In(id))
+    }
+
+    /// Returns true if the output pin has at least one wire.
+    ///
+    /// Same as `Snarl::has_any_connection` for an output pin.
+    pub fn is_output_connected(&self, id: OutPinId) -> bool {
+        self.wires.has_pin(AnyPinId::Out(id))
+    }
+
+    /// Returns number of wires connected to inputs of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node((), egui::pos2(200.0, 0.0));
+    ///
+    /// let a_out = OutPinId { node: a, output: 0 };
+    /// snarl.connect(a_out, InPinId { node: b, input: 0 });
+    /// snarl.connect(a_out, InPinId { node: c, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 1 });
+    ///
+    /// assert!(snarl.is_output_connected(a_out));
+    /// assert!(!snarl.is_output_connected(OutPinId { node: c, output: 0 }));
+    /// assert!(snarl.is_input_connected(InPinId { node: c, input: 1 }));
+    /// assert!(!snarl.is_input_connected(InPinId { node: b, input: 1 }));
+    ///
+    /// assert_eq!((snarl.in_degree(a), snarl.out_degree(a)), (0, 2));
+    /// assert_eq!((snarl.in_degree(b), snarl.out_degree(b)), (1, 1));
+    /// assert_eq!((snarl.in_degree(c), snarl.out_degree(c)), (2, 0));
+    /// ```
+    pub fn in_degree(&self, node: NodeId) -> usize {
+        self.wires
+            .iter()
+            .filter(|wire| wire.in_pin.node == node)
+            .count()
+    }
+
+    /// Returns number of wires connected to outputs of the node.
+    ///
+    /// Output connected to several inputs counts each of its wires.
+    pub fn out_degree(&self, node: NodeId) -> usize {
+        self.wires
+            .iter()
+            .filter(|wire| wire.out_pin.node == node)
+            .count()
+    }
+
+    /// Returns output pin with its node and connected input pins.
+    ///
+    /// This is the same value `SnarlViewer::show_output` receives,
+    /// but it can be built outside of the UI pass.
+    /// Returns `None` if the node does not exist.
+    pub fn out_pin(&self, id: OutPinId) -> Option<ui::OutPin<'_, T>> {
+        self.contains_node(id.node)
+            .then(|| ui::OutPin::output(self, id))
+    }
+
+    /// Returns input pin with its node and connected output pins.
+    ///
+    /// This is the same value `SnarlViewer::show_input` receives,
+    /// but it can be built outside of the UI pass.
+    /// Returns `None` if the node does not exist.
+    pub fn in_pin(&self, id: InPinId) -> Option<ui::InPin<'_, T>> {
+        self.contains_node(id.node)
+            .then(|| ui::InPin::input(self, id))
+    }
+
+    /// Returns input pins connected to the output pin, sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    /// let c = snarl.add_node((), egui::pos2(100.0, 100.0));
+    ///
+    /// let from = OutPinId { node: a, output: 0 };
+    /// snarl.connect(from, InPinId { node: c, input: 1 });
+    /// snarl.connect(from, InPinId { node: b, input: 0 });
+    ///
+    /// assert_eq!(
+    ///     snarl.out_pin_remotes(from),
+    ///     [InPinId { node: b, input: 0 }, InPinId { node: c, input: 1 }]
+    /// );
+    /// assert_eq!(snarl.in_pin_remotes(InPinId { node: b, input: 0 }), [from]);
+    /// assert_eq!(snarl.out_pin(from).unwrap().remotes.len(), 2);
+    /// ```
+    pub fn out_pin_remotes(&self, id: OutPinId) -> Vec<InPinId> {
+        let mut remotes = self.wires.wired_inputs(id).collect::<Vec<_>>();
+        remotes.sort_unstable();
+        remotes
+    }
+
+    /// Returns output pins connected to the input pin, sorted.
+    pub fn in_pin_remotes(&self, id: InPinId) -> Vec<OutPinId> {
+        let mut remotes = self.wires.wired_outputs(id).collect::<Vec<_>>();
+        remotes.sort_unstable();
+        remotes
+    }
+
+    /// Iterates over all wires in the graph.
+    ///
+    /// Wires are sorted by output pin and then by input pin,
+    /// so the order does not depend on the order of insertion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    ///
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: a, input: 0 });
+    /// snarl.connect(OutPinId { node: a, output: 1 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 1 });
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// assert_eq!(
+    ///     snarl.wires().collect::<Vec<_>>(),
+    ///     [
+    ///         (OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 }),
+    ///         (OutPinId { node: a, output: 0 }, InPinId { node: b, input: 1 }),
+    ///         (OutPinId { node: a, output: 1 }, InPinId { node: b, input: 0 }),
+    ///         (OutPinId { node: b, output: 0 }, InPinId { node: a, input: 0 }),
+    ///     ]
+    /// );
+    /// ```
+    pub fn wires(&self) -> impl Iterator<Item = (OutPinId, InPinId)> {
+        let mut wires = self
+            .wires
+            .iter()
+            .map(|wire| (wire.out_pin, wire.in_pin))
+            .collect::<Vec<_>>();
+        wires.sort_unstable();
+        wires.into_iter()
+    }
+
+    /// Adds node to the selection.
+    /// Does nothing if the node is already selected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    pub fn select_node(&mut self, id: NodeId) {
+        assert!(self.contains_node(id));
+
+        if !self.selection.contains(&id) {
+            self.selection.push(id);
+        }
+    }
+
+    /// Removes node from the selection.
+    pub fn deselect_node(&mut self, id: NodeId) {
+        self.selection.retain(|&i| i != id);
+    }
+
+    /// Clears the selection.
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    /// Returns true if the node is selected.
+    pub fn is_selected(&self, id: NodeId) -> bool {
+        self.selection.contains(&id)
+    }
+
+    /// Returns selected nodes in the order they were selected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    ///
+    /// snarl.select_node(b);
+    /// snarl.select_node(a);
+    /// assert_eq!(snarl.selected_nodes().collect::<Vec<_>>(), [b, a]);
+    /// assert_eq!(snarl.selection_anchor(), Some(b));
+    /// ```
+    pub fn selected_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.selection.iter().copied()
+    }
+
+    /// Returns the primary selected node, that is the first one selected.
+    ///
+    /// Operations that align nodes use it as the reference.
+    pub fn selection_anchor(&self) -> Option<NodeId> {
+        self.selection.first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use egui::{pos2, vec2, InnerResponse, Ui, Vec2};
+
+    use super::*;
+    use crate::ui::{Effects, InPin, OutPin, PinInfo, SnarlViewer};
+
+    /// Viewer of nodes whose value is the number of their outputs.
+    #[derive(Default)]
+    struct OutputsViewer {
+        pin_count_changes: Vec<(NodeId, usize, usize)>,
+    }
+
+    impl SnarlViewer<usize> for OutputsViewer {
+        fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<usize>> {
+            unimplemented!()
+        }
+
+        fn size_hint(&self, _: &usize) -> Vec2 {
+            vec2(100.0, 80.0)
+        }
+
+        fn title<'a>(&'a mut self, _: &'a usize) -> &'a str {
+            "Node"
+        }
+
+        fn outputs(&mut self, node: &usize) -> usize {
+            *node
+        }
+
+        fn inputs(&mut self, _: &usize) -> usize {
+            1
+        }
+
+        fn show_input(
+            &mut self,
+            _: &InPin<usize>,
+            _: &mut Ui,
+            _: &mut Effects<usize>,
+        ) -> InnerResponse<PinInfo> {
+            unimplemented!()
+        }
+
+        fn show_output(
+            &mut self,
+            _: &OutPin<usize>,
+            _: &mut Ui,
+            _: &mut Effects<usize>,
+        ) -> InnerResponse<PinInfo> {
+            unimplemented!()
+        }
+
+        fn on_pin_count_changed(
+            &mut self,
+            id: NodeId,
+            _: &RefCell<usize>,
+            inputs: usize,
+            outputs: usize,
+        ) {
+            self.pin_count_changes.push((id, inputs, outputs));
+        }
+    }
+
+    #[test]
+    fn replace_node_drops_wires_of_removed_outputs() {
+        let mut snarl = Snarl::new();
+        let a = snarl.add_node(2, pos2(0.0, 0.0));
+        let b = snarl.add_node(0, pos2(100.0, 0.0));
+        let c = snarl.add_node(0, pos2(100.0, 100.0));
+        let kept = (
+            OutPinId { node: a, output: 0 },
+            InPinId { node: b, input: 0 },
+        );
+        let dropped = [
+            (
+                OutPinId { node: a, output: 1 },
+                InPinId { node: b, input: 0 },
+            ),
+            (
+                OutPinId { node: a, output: 1 },
+                InPinId { node: c, input: 0 },
+            ),
+        ];
+        snarl.connect(kept.0, kept.1);
+        for (from, to) in dropped {
+            snarl.connect(from, to);
+        }
+
+        let mut viewer = OutputsViewer::default();
+        assert_eq!(snarl.replace_node(a, 1, &mut viewer), 2);
+
+        assert_eq!(snarl.wires().collect::<Vec<_>>(), [kept]);
+        assert_eq!(viewer.pin_count_changes, [(a, 1, 1)]);
+        assert_eq!(*snarl.get_node(a).unwrap().borrow(), 1);
+
+        // Same pin counts do not notify the viewer.
+        snarl.replace_node(a, 1, &mut viewer);
+        assert_eq!(viewer.pin_count_changes.len(), 1);
+    }
+
+    #[test]
+    fn apply_effects_skips_missing_nodes() {
+        let mut snarl = Snarl::new();
+        let a = snarl.add_node((), pos2(0.0, 0.0));
+        let b = snarl.add_node((), pos2(100.0, 0.0));
+
+        let mut effects = snarl.effects();
+        effects.set_collapsed(a, true);
+        effects.set_node_size(a, Some(vec2(10.0, 10.0)));
+        effects.drop_node_wires(a);
+        effects.connect(
+            OutPinId { node: a, output: 0 },
+            InPinId { node: b, input: 0 },
+        );
+        effects.remove_node(a);
+        effects.set_collapsed(b, true);
+        snarl.remove_node(a);
+
+        snarl.apply_effects(effects);
+        assert!(!snarl.contains_node(a));
+        assert!(snarl.is_collapsed(b));
+        assert_eq!(snarl.wires().count(), 0);
+    }
+
+    #[test]
+    fn stale_in_pin_does_not_resolve() {
+        let mut snarl = Snarl::new();
+        let a = snarl.add_node((), pos2(0.0, 0.0));
+        let b = snarl.add_node((), pos2(100.0, 0.0));
+        let from = OutPinId { node: a, output: 0 };
+        let stale = InPinId { node: b, input: 0 };
+        assert!(snarl.connect(from, stale));
+
+        snarl.remove_node(b);
+        let c = snarl.add_node((), pos2(100.0, 0.0));
+        assert_eq!(c.idx(), b.idx());
+        assert!(snarl.connect(from, InPinId { node: c, input: 0 }));
+
+        assert!(snarl.in_pin(stale).is_none());
+        assert!(snarl.in_pin_remotes(stale).is_empty());
+        assert!(!snarl.connect(from, stale));
+        assert_eq!(snarl.out_pin_remotes(from), [InPinId { node: c, input: 0 }]);
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use egui::pos2;
+
+    use super::*;
+
+    #[test]
+    fn round_trip_keeps_indices_wires_and_positions() {
+        let mut snarl = Snarl::new();
+        let a = snarl.add_node(1, pos2(0.0, 0.0));
+        let removed = snarl.add_node(2, pos2(50.0, 0.0));
+        let b = snarl.add_node(3, pos2(100.0, 20.0));
+        let c = snarl.add_node(4, pos2(200.0, 40.0));
+        snarl.connect(
+            OutPinId { node: a, output: 0 },
+            InPinId { node: b, input: 0 },
+        );
+        snarl.connect(
+            OutPinId { node: b, output: 1 },
+            InPinId { node: c, input: 2 },
+        );
+        snarl.remove_node(removed);
+
+        let json = serde_json::to_string(&snarl).unwrap();
+        let mut restored: Snarl<i32> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            restored.node_positions().collect::<Vec<_>>(),
+            snarl.node_positions().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            restored.wires().collect::<Vec<_>>(),
+            snarl.wires().collect::<Vec<_>>()
+        );
+        assert_eq!(*restored.get_node(c).unwrap().borrow(), 4);
+
+        // Gap is kept and the removed identifier stays stale.
+        assert!(restored.get_node(removed).is_none());
+        let d = restored.add_node(5, pos2(0.0, 0.0));
+        assert_eq!(d.idx(), removed.idx());
+        assert_ne!(d, removed);
     }
 }
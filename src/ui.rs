@@ -1,13 +1,83 @@
-use std::cell::RefCell;
-
-use egui::{ahash::HashMap, epaint::PathShape, *};
-
-use crate::{wire_pins, InPinId, OutPinId, Snarl};
+use std::{
+    cell::RefCell,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use egui::{
+    ahash::{HashMap, HashSet},
+    epaint::PathShape,
+    util::id_type_map::SerializableAny,
+    *,
+};
+
+use crate::{history::HistoryOp, wire_pins, AnyPinId, InPinId, NodeId, OutPinId, Snarl, Wire};
+
+use self::zoom::Zoom;
+
+pub(crate) use self::{anim::NodesAnimation, wire_cache::WireCache};
+
+pub use self::{
+    anim::Easing,
+    minimap::MinimapTransform,
+    state::{node_state, node_state_mut, remove_node_state, SnarlTransform},
+};
+
+mod anim;
+mod minimap;
+mod state;
+mod wire_cache;
+mod zoom;
 
 /// Error returned from methods where `Viewer` forbids the operation.
 pub struct Forbidden;
 
+/// Reason why `SnarlViewer::connect` rejected a connection.
+///
+/// `Snarl::show` shows it in a tooltip next to the pin the wire was dropped on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConnectionError {
+    /// Pins carry values of incompatible types.
+    TypeMismatch,
+
+    /// Connection would create a cycle in the graph.
+    WouldCycle,
+
+    /// Pin cannot have more wires.
+    CapacityFull,
+
+    /// Reason described by the viewer.
+    Custom(String),
+
+    /// Connection is rejected without a reason.
+    Forbidden,
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::TypeMismatch => f.write_str("Pin types do not match"),
+            ConnectionError::WouldCycle => f.write_str("Connection would create a cycle"),
+            ConnectionError::CapacityFull => f.write_str("Pin cannot have more wires"),
+            ConnectionError::Custom(reason) => f.write_str(reason),
+            ConnectionError::Forbidden => f.write_str("Connection is forbidden"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+impl From<Forbidden> for ConnectionError {
+    fn from(Forbidden: Forbidden) -> Self {
+        ConnectionError::Forbidden
+    }
+}
+
 pub enum Effect<T> {
+    /// Adds a node under identifier reserved by `Effects::add_node`.
+    AddNode { node: T, pos: Pos2, id: NodeId },
+
     /// Adds connection between two nodes.
     Connect { from: OutPinId, to: InPinId },
 
@@ -20,33 +90,148 @@ pub enum Effect<T> {
     /// Removes all connections to the input pin.
     DropInputs { pin: InPinId },
 
+    /// Removes all connections of the node's pins.
+    DropNodeWires { node: NodeId },
+
     /// Removes a node from snarl.
-    RemoveNode { node: usize },
+    RemoveNode { node: NodeId },
+
+    /// Selects nodes.
+    /// Replaces current selection unless `add` is true.
+    SelectNodes { nodes: Vec<NodeId>, add: bool },
+
+    /// Collapses or expands a node.
+    SetCollapsed { node: NodeId, collapsed: bool },
+
+    /// Sets size of a node overriding `SnarlViewer::size_hint`.
+    SetNodeSize { node: NodeId, size: Option<Vec2> },
+
+    /// Moves a comment together with given nodes.
+    MoveComment {
+        comment: usize,
+        delta: Vec2,
+        nodes: Vec<NodeId>,
+    },
+
+    /// Sets size of a comment.
+    SetCommentSize { comment: usize, size: Vec2 },
 
     /// Executes a closure with mutable reference to the Snarl.
+    #[allow(clippy::type_complexity)]
     Closure(Box<dyn FnOnce(&mut Snarl<T>)>),
 }
 
+impl<T> Effect<T> {
+    /// Replaces node identifiers the effect refers to.
+    fn map_nodes(&mut self, f: impl Fn(NodeId) -> NodeId) {
+        match self {
+            Effect::AddNode { .. } | Effect::SetCommentSize { .. } | Effect::Closure(_) => {}
+            Effect::Connect { from, to } | Effect::Disconnect { from, to } => {
+                from.node = f(from.node);
+                to.node = f(to.node);
+            }
+            Effect::DropOutputs { pin } => pin.node = f(pin.node),
+            Effect::DropInputs { pin } => pin.node = f(pin.node),
+            Effect::DropNodeWires { node }
+            | Effect::RemoveNode { node }
+            | Effect::SetCollapsed { node, .. }
+            | Effect::SetNodeSize { node, .. } => *node = f(*node),
+            Effect::SelectNodes { nodes, .. } | Effect::MoveComment { nodes, .. } => {
+                for node in nodes {
+                    *node = f(*node);
+                }
+            }
+        }
+    }
+}
+
 pub struct Effects<T> {
     effects: Vec<Effect<T>>,
+
+    /// Vacant node indices below `next_idx`, in ascending order.
+    vacant: Vec<usize>,
+
+    /// Index after the last existing node.
+    next_idx: usize,
+
+    /// Generations of node slots, see `Snarl::generations`.
+    generations: Vec<u32>,
 }
 
 impl<T> Default for Effects<T> {
     #[inline]
     fn default() -> Self {
-        Effects {
-            effects: Default::default(),
-        }
+        Effects::new()
     }
 }
 
 impl<T> Effects<T> {
+    /// Creates empty effects.
+    ///
+    /// Identifiers returned by `Effects::add_node` are predicted for a new `Snarl`.
+    /// If the predicted identifier is taken when effects are applied,
+    /// the node receives a vacant one and later effects of the batch are redirected to it.
+    /// Use `Snarl::effects` to create effects with identifiers that hold for a particular `Snarl`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl, ui::Effects};
+    /// let mut snarl = Snarl::<i32>::new();
+    /// let a = snarl.add_node(1, egui::pos2(0.0, 0.0));
+    ///
+    /// let mut effects = Effects::new();
+    /// let b = effects.add_node(2, egui::pos2(100.0, 0.0));
+    /// let c = effects.add_node(3, egui::pos2(200.0, 0.0));
+    /// assert_eq!(b, a);
+    /// effects.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    /// snarl.apply_effects(effects);
+    ///
+    /// assert_eq!(snarl.node_count(), 3);
+    /// assert_eq!(*snarl.get_node(a).unwrap().borrow(), 1);
+    /// let ids = snarl.nodes().map(|(id, _)| id).collect::<Vec<_>>();
+    /// let from = OutPinId { node: ids[1], output: 0 };
+    /// let to = InPinId { node: ids[2], input: 0 };
+    /// assert!(snarl.connected(from, to));
+    /// ```
     pub fn new() -> Self {
         Effects {
             effects: Vec::new(),
+            vacant: Vec::new(),
+            next_idx: 0,
+            generations: Vec::new(),
+        }
+    }
+
+    /// Returns the identifier the next node added with `Effects::add_node` will receive.
+    fn next_node_id(&self) -> NodeId {
+        let idx = self.vacant.first().copied().unwrap_or(self.next_idx);
+        self.id_at(idx)
+    }
+
+    fn id_at(&self, idx: usize) -> NodeId {
+        NodeId {
+            idx,
+            generation: self.generations.get(idx).copied().unwrap_or(0),
         }
     }
 
+    /// Queues a node to be added at `pos` in graph space.
+    /// Returns the identifier the node will receive.
+    ///
+    /// Returned identifier may be used in other effects queued after this one.
+    pub fn add_node(&mut self, node: T, pos: Pos2) -> NodeId {
+        let idx = if self.vacant.is_empty() {
+            self.next_idx += 1;
+            self.next_idx - 1
+        } else {
+            self.vacant.remove(0)
+        };
+        let id = self.id_at(idx);
+        self.effects.push(Effect::AddNode { node, pos, id });
+        id
+    }
+
     pub fn connect(&mut self, from: OutPinId, to: InPinId) {
         self.effects.push(Effect::Connect { from, to });
     }
@@ -63,9 +248,65 @@ impl<T> Effects<T> {
         self.effects.push(Effect::DropOutputs { pin });
     }
 
-    pub fn remove_node(&mut self, node: usize) {
+    /// Queues removal of all wires of the node, see `Snarl::drop_node_wires`.
+    pub fn drop_node_wires(&mut self, node: NodeId) {
+        self.effects.push(Effect::DropNodeWires { node });
+    }
+
+    /// Queues removal of the node together with all its wires.
+    ///
+    /// Effects queued for the same node after its removal are skipped,
+    /// so viewer callbacks may remove nodes without coordinating with each other.
+    /// Index of the removed node is not reused by `Effects::add_node` in the same batch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{AnyPinId, InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.add_node((), egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node((), egui::pos2(100.0, 0.0));
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let to = InPinId { node: b, input: 0 };
+    /// snarl.connect(from, to);
+    ///
+    /// // Queued from a viewer callback, e.g. `SnarlViewer::show_content`.
+    /// let mut effects = snarl.effects();
+    /// effects.remove_node(b);
+    /// effects.disconnect(from, to);
+    /// effects.remove_node(b);
+    /// snarl.apply_effects(effects);
+    ///
+    /// assert!(snarl.get_node(b).is_none());
+    /// assert!(!snarl.has_any_connection(AnyPinId::Out(from)));
+    /// ```
+    pub fn remove_node(&mut self, node: NodeId) {
         self.effects.push(Effect::RemoveNode { node });
     }
+
+    pub fn select_nodes(&mut self, nodes: Vec<NodeId>, add: bool) {
+        self.effects.push(Effect::SelectNodes { nodes, add });
+    }
+
+    pub fn set_collapsed(&mut self, node: NodeId, collapsed: bool) {
+        self.effects.push(Effect::SetCollapsed { node, collapsed });
+    }
+
+    pub fn set_node_size(&mut self, node: NodeId, size: Option<Vec2>) {
+        self.effects.push(Effect::SetNodeSize { node, size });
+    }
+
+    pub fn move_comment(&mut self, comment: usize, delta: Vec2, nodes: Vec<NodeId>) {
+        self.effects.push(Effect::MoveComment {
+            comment,
+            delta,
+            nodes,
+        });
+    }
+
+    pub fn set_comment_size(&mut self, comment: usize, size: Vec2) {
+        self.effects.push(Effect::SetCommentSize { comment, size });
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -85,6 +326,8 @@ pub struct RemoteInPin<'a, T> {
 pub struct OutPin<'a, T> {
     pub id: OutPinId,
     pub node: &'a RefCell<T>,
+
+    /// Connected input pins, from the oldest wire to the newest.
     pub remotes: Vec<RemoteInPin<'a, T>>,
 }
 
@@ -93,19 +336,23 @@ pub struct OutPin<'a, T> {
 pub struct InPin<'a, T> {
     pub id: InPinId,
     pub node: &'a RefCell<T>,
+
+    /// Connected output pins, from the oldest wire to the newest.
     pub remotes: Vec<RemoteOutPin<'a, T>>,
 }
 
 impl<'a, T> OutPin<'a, T> {
     pub fn output(snarl: &'a Snarl<T>, pin: OutPinId) -> Self {
+        let mut remotes = snarl.wires.wired_inputs(pin).collect::<Vec<_>>();
+        remotes.sort_unstable_by_key(|&remote| (snarl.wires.age(&wire_pins(pin, remote)), remote));
+
         OutPin {
             id: pin,
-            node: &snarl.nodes[pin.node].value,
-            remotes: snarl
-                .wires
-                .wired_inputs(pin)
+            node: &snarl.expect_node(pin.node).value,
+            remotes: remotes
+                .into_iter()
                 .map(|pin| RemoteInPin {
-                    node: &snarl.nodes[pin.node].value,
+                    node: &snarl.expect_node(pin.node).value,
                     id: pin,
                 })
                 .collect(),
@@ -115,14 +362,16 @@ impl<'a, T> OutPin<'a, T> {
 
 impl<'a, T> InPin<'a, T> {
     pub fn input(snarl: &'a Snarl<T>, pin: InPinId) -> Self {
+        let mut remotes = snarl.wires.wired_outputs(pin).collect::<Vec<_>>();
+        remotes.sort_unstable_by_key(|&remote| (snarl.wires.age(&wire_pins(remote, pin)), remote));
+
         InPin {
             id: pin,
-            node: &snarl.nodes[pin.node].value,
-            remotes: snarl
-                .wires
-                .wired_outputs(pin)
+            node: &snarl.expect_node(pin.node).value,
+            remotes: remotes
+                .into_iter()
                 .map(|pin| RemoteOutPin {
-                    node: &snarl.nodes[pin.node].value,
+                    node: &snarl.expect_node(pin.node).value,
                     id: pin,
                 })
                 .collect(),
@@ -130,16 +379,154 @@ impl<'a, T> InPin<'a, T> {
     }
 }
 
+/// Node and its input or output pin.
+#[derive(Clone, Debug)]
+pub enum AnyPin<'a, T> {
+    Out(OutPin<'a, T>),
+    In(InPin<'a, T>),
+}
+
+impl<'a, T> AnyPin<'a, T> {
+    pub fn new(snarl: &'a Snarl<T>, pin: AnyPinId) -> Self {
+        match pin {
+            AnyPinId::Out(pin) => AnyPin::Out(OutPin::output(snarl, pin)),
+            AnyPinId::In(pin) => AnyPin::In(InPin::input(snarl, pin)),
+        }
+    }
+}
+
+/// How dragging of a new wire ended, see `SnarlViewer::on_wire_drag_end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WireDragResult {
+    /// Wire was released on a pin and `SnarlViewer::connect` accepted it.
+    Connected { from: OutPinId, to: InPinId },
+
+    /// Wire was released on empty canvas at `pos` in graph space.
+    /// `SnarlViewer::dropped_wire_menu` is shown there.
+    DroppedOnEmpty { pos: Pos2 },
+
+    /// Wire was released elsewhere, rejected by the viewer or dropped with Escape.
+    Cancelled,
+}
+
+/// Context passed to `SnarlViewer::handle_shortcut`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShortcutContext {
+    /// Node under the pointer.
+    pub hovered_node: Option<NodeId>,
+
+    /// Pointer position in graph space.
+    pub pointer_pos: Option<Pos2>,
+}
+
+/// Context passed to `SnarlViewer::show_content`.
+#[derive(Clone, Debug)]
+pub struct NodeContext {
+    /// Identifier of the shown node.
+    pub id: NodeId,
+
+    /// Id passed to `Snarl::show`.
+    pub snarl_id: Id,
+
+    cx: Context,
+}
+
+impl NodeContext {
+    /// Returns a copy of the UI state of the node, see `node_state`.
+    pub fn node_state<S>(&self) -> Option<S>
+    where
+        S: SerializableAny,
+    {
+        node_state(&self.cx, self.snarl_id, self.id)
+    }
+
+    /// Gives mutable access to the UI state of the node, see `node_state_mut`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::cell::RefCell;
+    /// # use egui::{Response, Ui};
+    /// # use egui_snarl::ui::{Effects, InPin, NodeContext, OutPin};
+    /// // In `SnarlViewer::show_content`.
+    /// fn show_content(ctx: &NodeContext, _: &RefCell<()>, _: &[InPin<()>], _: &[OutPin<()>], ui: &mut Ui, _: &mut Effects<()>) -> Response {
+    ///     let clicks = ctx.node_state_mut(|clicks: &mut u32| *clicks);
+    ///     let response = ui.button(format!("Clicked {clicks} times"));
+    ///     if response.clicked() {
+    ///         ctx.node_state_mut(|clicks: &mut u32| *clicks += 1);
+    ///     }
+    ///     response
+    /// }
+    /// ```
+    pub fn node_state_mut<S, R>(&self, f: impl FnOnce(&mut S) -> R) -> R
+    where
+        S: SerializableAny + Default,
+    {
+        node_state_mut(&self.cx, self.snarl_id, self.id, f)
+    }
+
+    /// Removes the UI state of the node, see `remove_node_state`.
+    pub fn remove_node_state<S>(&self)
+    where
+        S: 'static,
+    {
+        remove_node_state::<S>(&self.cx, self.snarl_id, self.id);
+    }
+}
+
 /// Shape of a pin.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PinShape {
     Cirle,
     Triangle,
     Square,
+
+    /// Shape drawn by a callback, see `PinInfo::custom`.
+    Custom(CustomPinShape),
+}
+
+type DrawPinFn = dyn Fn(&Painter, Rect, Color32) + Send + Sync;
+
+/// Callback that draws a pin shape.
+///
+/// Called with the painter, the rect of the pin and its fill color.
+/// Callbacks are compared by identity.
+#[derive(Clone)]
+pub struct CustomPinShape(Arc<DrawPinFn>);
+
+impl CustomPinShape {
+    pub fn new(draw: impl Fn(&Painter, Rect, Color32) + Send + Sync + 'static) -> Self {
+        CustomPinShape(Arc::new(draw))
+    }
+
+    /// Draws the shape in the `rect`.
+    pub fn draw(&self, painter: &Painter, rect: Rect, fill: Color32) {
+        (self.0)(painter, rect, fill);
+    }
+}
+
+impl fmt::Debug for CustomPinShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CustomPinShape").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for CustomPinShape {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CustomPinShape {}
+
+impl Hash for CustomPinShape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<()>().hash(state);
+    }
 }
 
 /// Information about a pin returned by `SnarlViewer::show_input` and `SnarlViewer::show_output`.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PinInfo {
     pub shape: PinShape,
     pub size: f32,
@@ -164,6 +551,19 @@ impl PinInfo {
         self
     }
 
+    /// Sets size of the pin relative to `SnarlStyle::pin_size`.
+    ///
+    /// Pin row is tall enough to fit the pin,
+    /// and wires attach to the pin center regardless of its size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::ui::PinInfo;
+    /// // Twice as large as the default pin.
+    /// let pin = PinInfo::circle().with_size(2.0);
+    /// assert_eq!(pin.size, 2.0);
+    /// ```
     pub fn with_size(mut self, size: f32) -> Self {
         self.size = size;
         self
@@ -174,6 +574,16 @@ impl PinInfo {
         self
     }
 
+    /// Sets outline of the pin.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{Color32, Stroke};
+    /// # use egui_snarl::ui::PinInfo;
+    /// let stroke = Stroke::new(2.0, Color32::WHITE);
+    /// assert_eq!(PinInfo::square().with_stroke(stroke).stroke, stroke);
+    /// ```
     pub fn with_stroke(mut self, stroke: Stroke) -> Self {
         self.stroke = stroke;
         self
@@ -199,6 +609,33 @@ impl PinInfo {
             ..Default::default()
         }
     }
+
+    /// Creates pin with shape drawn by `draw`.
+    ///
+    /// `draw` is called with the painter, the rect of the pin and its fill color.
+    /// Pin is still hit by its rect, regardless of the drawn shape.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{epaint::PathShape, Color32, Stroke};
+    /// # use egui_snarl::ui::{PinInfo, PinShape};
+    /// let diamond = PinInfo::custom(|painter, rect, fill| {
+    ///     let points = vec![rect.center_top(), rect.right_center(), rect.center_bottom(), rect.left_center()];
+    ///     painter.add(PathShape::convex_polygon(points, fill, Stroke::NONE));
+    /// })
+    /// .with_fill(Color32::YELLOW);
+    ///
+    /// assert!(matches!(diamond.shape, PinShape::Custom(_)));
+    /// assert_eq!(diamond.fill, Color32::YELLOW);
+    /// assert_eq!(diamond.clone(), diamond);
+    /// ```
+    pub fn custom(draw: impl Fn(&Painter, Rect, Color32) + Send + Sync + 'static) -> Self {
+        PinInfo {
+            shape: PinShape::Custom(CustomPinShape::new(draw)),
+            ..Default::default()
+        }
+    }
 }
 
 /// SnarlViewer is a trait for viewing a Snarl.
@@ -216,25 +653,114 @@ pub trait SnarlViewer<T> {
     #[inline]
     fn add_node(
         &mut self,
-        idx: usize,
+        id: NodeId,
         node: &T,
         effects: &mut Effects<T>,
     ) -> Result<(), Forbidden> {
-        let _ = (idx, node, effects);
+        let _ = (id, node, effects);
         Ok(())
     }
 
+    /// Returns label to draw at the middle of the wire.
+    ///
+    /// Default implementation returns `None`.
+    #[inline]
+    fn wire_label(&mut self, from: &OutPin<T>, to: &InPin<T>) -> Option<WidgetText> {
+        let _ = (from, to);
+        None
+    }
+
+    /// Returns color of the wire.
+    ///
+    /// `blend` is the mix of pin fills, see `mix_colors`.
+    /// Called once per wire per frame.
+    ///
+    /// Default implementation returns `blend`.
+    #[inline]
+    fn wire_color(&mut self, from: &OutPin<T>, to: &InPin<T>, blend: Color32) -> Color32 {
+        let _ = (from, to);
+        blend
+    }
+
+    /// Checks if two pins can be connected.
+    ///
+    /// Used by default `connect` implementation to validate connections.
+    /// `Snarl::show` also calls it while a wire is dragged over a pin
+    /// to draw the wire with `SnarlStyle::forbidden_wire_stroke` if the pins cannot be connected.
+    /// Must not have side effects.
+    /// Default implementation allows any connection.
+    #[inline]
+    fn can_connect(&self, from: &OutPin<T>, to: &InPin<T>) -> bool {
+        let _ = (from, to);
+        true
+    }
+
+    /// Returns maximum number of wires the input pin can have.
+    ///
+    /// Used by default `connect` implementation,
+    /// which disconnects the oldest wires to make room for the new one.
+    /// `None` means unlimited and `Some(0)` forbids connecting the pin.
+    ///
+    /// Default implementation returns `Some(1)`.
+    #[inline]
+    fn max_input_wires(&self, pin: &InPin<T>) -> Option<usize> {
+        let _ = pin;
+        Some(1)
+    }
+
+    /// Returns maximum number of wires the output pin can have.
+    ///
+    /// Used by default `connect` implementation the same way as `max_input_wires`.
+    ///
+    /// Default implementation returns `None`.
+    #[inline]
+    fn max_output_wires(&self, pin: &OutPin<T>) -> Option<usize> {
+        let _ = pin;
+        None
+    }
+
     /// Asks the viewer to connect two pins.
     ///
     /// This is usually happens when user drags a wire from one node's output pin to another node's input pin or vice versa.
-    /// By default this method connects the pins and returns `Ok(())`.
+    /// By default this method checks `can_connect`, disconnects the oldest wires of both pins
+    /// that exceed `max_input_wires` and `max_output_wires`, connects the pins and returns `Ok(())`.
+    /// Pins rejected by `can_connect` are reported as `ConnectionError::TypeMismatch`
+    /// and pins that allow no wires as `ConnectionError::CapacityFull`.
     #[inline]
     fn connect(
         &mut self,
         from: &OutPin<T>,
         to: &InPin<T>,
         effects: &mut Effects<T>,
-    ) -> Result<(), Forbidden> {
+    ) -> Result<(), ConnectionError> {
+        if !self.can_connect(from, to) {
+            return Err(ConnectionError::TypeMismatch);
+        }
+
+        if to.remotes.iter().any(|remote| remote.id == from.id) {
+            return Ok(());
+        }
+
+        let max_inputs = self.max_input_wires(to);
+        let max_outputs = self.max_output_wires(from);
+        if max_inputs == Some(0) || max_outputs == Some(0) {
+            return Err(ConnectionError::CapacityFull);
+        }
+
+        if let Some(max) = max_inputs {
+            let excess = (to.remotes.len() + 1).saturating_sub(max);
+            for remote in &to.remotes[..excess] {
+                effects.disconnect(remote.id, to.id);
+            }
+        }
+
+        if let Some(max) = max_outputs {
+            let excess = (from.remotes.len() + 1).saturating_sub(max);
+            for remote in &from.remotes[..excess] {
+                effects.disconnect(from.id, remote.id);
+            }
+        }
+
         effects.connect(from.id, to.id);
         Ok(())
     }
@@ -287,46 +813,394 @@ pub trait SnarlViewer<T> {
     #[inline]
     fn remove_node(
         &mut self,
-        idx: usize,
+        id: NodeId,
         node: &RefCell<T>,
         inputs: &[InPin<T>],
         outputs: &[OutPin<T>],
         effects: &mut Effects<T>,
     ) -> Result<(), Forbidden> {
-        let _ = (idx, node, inputs, outputs);
-        effects.remove_node(idx);
+        let _ = (id, node, inputs, outputs);
+        effects.remove_node(id);
         Ok(())
     }
 
     fn node_picker(&mut self, ui: &mut Ui) -> egui::InnerResponse<Option<T>>;
 
+    /// Paints behind comments, wires and nodes, over the background grid.
+    ///
+    /// `viewport` is the screen rect of the Snarl
+    /// and `transform` maps graph space to it, e.g. with `SnarlTransform::graph_pos_to_screen`,
+    /// so images and region shading can be painted in graph space.
+    ///
+    /// Default implementation paints nothing.
+    #[inline]
+    fn draw_background(&mut self, painter: &Painter, transform: &SnarlTransform, viewport: Rect) {
+        let _ = (painter, transform, viewport);
+    }
+
+    /// Shows menu opened by right-clicking empty canvas.
+    ///
+    /// `pos` is the position in graph space where the menu was opened,
+    /// suitable for placing new nodes.
+    /// Menu is closed when it queues any effect, when clicked outside of it or on `Escape`.
+    ///
+    /// Default implementation shows nothing.
+    #[inline]
+    fn graph_menu(&mut self, pos: Pos2, ui: &mut Ui, effects: &mut Effects<T>) {
+        let _ = (pos, ui, effects);
+    }
+
+    /// Shows menu opened by releasing a new wire on empty canvas.
+    ///
+    /// `pos` is the position in graph space where the wire was released.
+    /// `src` is the pin the wire was dragged from.
+    /// Menu is closed the same way as `SnarlViewer::graph_menu`.
+    ///
+    /// Default implementation shows `node_picker`, adds picked node
+    /// and connects its first pin of the opposite kind to `src` with `SnarlViewer::connect`.
+    #[inline]
+    fn dropped_wire_menu(
+        &mut self,
+        pos: Pos2,
+        ui: &mut Ui,
+        src: AnyPin<T>,
+        effects: &mut Effects<T>,
+    ) {
+        let Some(node) = self.node_picker(ui).inner else {
+            return;
+        };
+
+        // New node is not in the Snarl yet, so pins are built around a temporary cell
+        // and wires are queued after the node is added.
+        let id = effects.next_node_id();
+        let node = RefCell::new(node);
+        let mut connect_effects = Effects::new();
+        match &src {
+            AnyPin::Out(from) if self.inputs(&node.borrow()) > 0 => {
+                let to = InPin {
+                    id: InPinId { node: id, input: 0 },
+                    node: &node,
+                    remotes: Vec::new(),
+                };
+                let _ = self.connect(from, &to, &mut connect_effects);
+            }
+            AnyPin::In(to) if self.outputs(&node.borrow()) > 0 => {
+                let from = OutPin {
+                    id: OutPinId {
+                        node: id,
+                        output: 0,
+                    },
+                    node: &node,
+                    remotes: Vec::new(),
+                };
+                let _ = self.connect(&from, to, &mut connect_effects);
+            }
+            _ => {}
+        }
+
+        effects.add_node(node.into_inner(), pos);
+        effects.effects.append(&mut connect_effects.effects);
+    }
+
+    /// Shows menu opened by right-clicking the node.
+    ///
+    /// Menu is closed the same way as `SnarlViewer::graph_menu`.
+    ///
+    /// Default implementation shows nothing.
+    #[inline]
+    fn node_menu(&mut self, node: NodeId, ui: &mut Ui, effects: &mut Effects<T>) {
+        let _ = (node, ui, effects);
+    }
+
     fn size_hint(&self, node: &T) -> Vec2;
 
+    /// Returns true if the node can be resized by dragging its bottom-right corner.
+    ///
+    /// Size set by resizing overrides `size_hint`, see `Snarl::node_size`.
+    ///
+    /// Default implementation returns false.
+    #[inline]
+    fn resizable(&self, node: &T) -> bool {
+        let _ = node;
+        false
+    }
+
+    /// Returns true if the node can be dragged in `Snarl::show`.
+    ///
+    /// Node that is not movable stays in place when the selection it is part of is dragged
+    /// and when a comment it is in is dragged.
+    /// It still can be moved programmatically and its pins can be connected.
+    ///
+    /// Default implementation returns true.
+    #[inline]
+    fn movable(&self, node: &T) -> bool {
+        let _ = node;
+        true
+    }
+
+    /// Returns true if the node can be removed by keyboard in `Snarl::show`,
+    /// see `SnarlStyle::enable_keyboard`.
+    ///
+    /// Default implementation returns true.
+    #[inline]
+    fn deletable(&self, node: &T) -> bool {
+        let _ = node;
+        true
+    }
+
+    /// Returns true if the node can be collapsed to its header.
+    ///
+    /// Nodes are collapsed by the toggle in the header,
+    /// so only nodes shown with `NodeLayout::Default` can be collapsed.
+    /// Pins of collapsed node are not shown, they are stacked at the header edges.
+    ///
+    /// Default implementation returns true.
+    #[inline]
+    fn collapsible(&self, node: &T) -> bool {
+        let _ = node;
+        true
+    }
+
+    /// Returns frame the node is drawn with.
+    ///
+    /// `default` is the frame used for all nodes, `SnarlStyle::node_frame`
+    /// or the one derived from the window style.
+    /// Override it to give certain nodes distinct fill, stroke or rounding,
+    /// e.g. to mark nodes in error state.
+    ///
+    /// Default implementation returns `default`.
+    #[inline]
+    fn node_frame(&mut self, node: &T, default: Frame) -> Frame {
+        let _ = node;
+        default
+    }
+
     fn title<'a>(&'a mut self, node: &'a T) -> &'a str;
 
+    /// Shows the header row of the node in `NodeLayout::Default`.
+    ///
+    /// Override it to add icons or buttons next to the title.
+    /// Widgets shown here take precedence over dragging the node by its header.
+    ///
+    /// Default implementation shows the `title` as a label.
+    #[inline]
+    fn show_header(
+        &mut self,
+        id: NodeId,
+        node: &RefCell<T>,
+        ui: &mut Ui,
+        effects: &mut Effects<T>,
+    ) {
+        let _ = (id, effects);
+        ui.label(self.title(&node.borrow()));
+    }
+
+    /// Shows the node body between its input and output pins.
+    ///
+    /// `ctx` identifies the node and gives access to its UI state,
+    /// see `NodeContext::node_state_mut`.
     fn show_content(
         &mut self,
-        idx: usize,
+        ctx: &NodeContext,
         node: &RefCell<T>,
         inputs: &[InPin<T>],
         outputs: &[OutPin<T>],
         ui: &mut Ui,
         effects: &mut Effects<T>,
     ) -> Response {
-        let _ = (idx, node, inputs, outputs, effects);
+        let _ = (ctx, node, inputs, outputs, effects);
         ui.interact(Rect::ZERO, Id::NULL, Sense::hover())
     }
 
-    fn outputs(&mut self, node: &T) -> usize;
+    /// Returns true if node's content must be updated every frame.
+    ///
+    /// Nodes that are entirely outside of the viewport are culled:
+    /// neither `show_header`, `show_content` nor `show_input` and `show_output` are called for them.
+    /// Their pins are placed where they were when the node was last shown,
+    /// so wires to culled nodes are still drawn.
+    /// Nodes that drive side effects from `show_content` may return true here
+    /// to keep it called even when they are off-screen.
+    ///
+    /// This defeats culling for such nodes, so use it sparingly.
+    /// Default implementation returns false.
+    #[inline]
+    fn always_update(&self, node: &T) -> bool {
+        let _ = node;
+        false
+    }
 
-    fn inputs(&mut self, node: &T) -> usize;
+    /// Returns maximum height of the node body in graph space.
+    ///
+    /// When set, content shown by `show_content` and pin rows are wrapped
+    /// into a vertical scroll area of that height.
+    /// Wires of pins scrolled out of view are attached to the body edge.
+    /// Default implementation returns `None`, body grows with its content.
+    #[inline]
+    fn body_max_height(&self, node: &T) -> Option<f32> {
+        let _ = node;
+        None
+    }
 
-    fn show_input(
+    /// Called for each key press while pointer is over the Snarl
+    /// and no widget has keyboard focus.
+    ///
+    /// Returns true if the event is consumed.
+    /// Consumed events are not handled by default shortcuts.
+    /// See `SnarlStyle::default_shortcuts` for the list of default bindings.
+    #[inline]
+    fn handle_shortcut(
         &mut self,
-        pin: &InPin<T>,
-        ui: &mut Ui,
+        event: &Event,
+        ctx: ShortcutContext,
         effects: &mut Effects<T>,
-    ) -> egui::InnerResponse<PinInfo>;
+    ) -> bool {
+        let _ = (event, ctx, effects);
+        false
+    }
+
+    /// Called when dragging a node in `Snarl::show` changes its position,
+    /// including snapping to the grid when the drag is released.
+    ///
+    /// Called at most once per node per frame, with the position at the end of the frame.
+    /// Each of the dragged selected nodes is reported separately.
+    #[inline]
+    fn on_node_moved(&mut self, node: NodeId, new_pos: Pos2) {
+        let _ = (node, new_pos);
+    }
+
+    /// Called from `Snarl::show` when the set of selected nodes differs from the one
+    /// at the end of the previous call, whether it was changed in the UI or through `Snarl` methods.
+    ///
+    /// `selected` nodes are in the order they were selected.
+    #[inline]
+    fn on_selection_changed(&mut self, selected: &[NodeId]) {
+        let _ = selected;
+    }
+
+    /// Called by `Snarl::show` for each wire created in the frame,
+    /// after all effects are applied.
+    ///
+    /// Called once per actual change, so re-connecting existing wire does not trigger it.
+    /// Unlike `SnarlViewer::connect` it is not involved in validation,
+    /// which makes it the place for side effects of new wires.
+    #[inline]
+    fn on_connect(&mut self, from: &OutPin<T>, to: &InPin<T>) {
+        let _ = (from, to);
+    }
+
+    /// Called by `Snarl::show` for each wire removed in the frame,
+    /// after all effects are applied.
+    ///
+    /// Called once per actual change.
+    /// Wires of nodes removed with `Effects::remove_node` are reported right before
+    /// the node is removed, while their pins still exist.
+    #[inline]
+    fn on_disconnect(&mut self, from: &OutPin<T>, to: &InPin<T>) {
+        let _ = (from, to);
+    }
+
+    /// Called when the user starts dragging a new wire from the pin `from`.
+    ///
+    /// Every call is followed by `SnarlViewer::on_wire_drag_end` when the drag ends.
+    /// Use it to e.g. dim nodes that cannot accept the wire.
+    #[inline]
+    fn on_wire_drag_start(&mut self, from: &AnyPin<T>) {
+        let _ = from;
+    }
+
+    /// Called when dragging of a new wire ends, telling how it ended.
+    ///
+    /// For `WireDragResult::Connected` the wire is created when effects are applied.
+    #[inline]
+    fn on_wire_drag_end(&mut self, result: WireDragResult) {
+        let _ = result;
+    }
+
+    /// Called when a node is double-clicked in `Snarl::show`
+    /// by its header or, for nodes without header, by its background.
+    ///
+    /// Uses egui double-click detection, so two clicks must be close in time and
+    /// a press that starts a drag is not a click.
+    #[inline]
+    fn on_node_double_click(&mut self, node: NodeId, effects: &mut Effects<T>) {
+        let _ = (node, effects);
+    }
+
+    fn outputs(&mut self, node: &T) -> usize;
+
+    fn inputs(&mut self, node: &T) -> usize;
+
+    /// Called when number of node's pins changes after `Snarl::replace_node`.
+    ///
+    /// Wires connected to pins that no longer exist are already dropped.
+    #[inline]
+    fn on_pin_count_changed(
+        &mut self,
+        id: NodeId,
+        node: &RefCell<T>,
+        inputs: usize,
+        outputs: usize,
+    ) {
+        let _ = (id, node, inputs, outputs);
+    }
+
+    /// Returns text shown when the pointer hovers the input pin,
+    /// like the type or the value of the input.
+    ///
+    /// Called only for the hovered pin.
+    /// Default implementation returns `None`, so no tooltip is shown.
+    #[inline]
+    fn input_tooltip(&mut self, pin: &InPin<T>) -> Option<WidgetText> {
+        let _ = pin;
+        None
+    }
+
+    /// Returns true if the input pin must be connected.
+    ///
+    /// `Snarl::show` fills required pins without wires
+    /// with `SnarlStyle::unconnected_required_fill`, so users see what still needs wiring.
+    /// Default implementation returns false.
+    #[inline]
+    fn input_required(&self, pin: &InPin<T>) -> bool {
+        let _ = pin;
+        false
+    }
+
+    /// Returns name of the input pin shown between the pin and the widgets from `SnarlViewer::show_input`.
+    ///
+    /// Saves showing a label in every `SnarlViewer::show_input`.
+    /// Default implementation returns `None`, so no name is shown.
+    #[inline]
+    fn input_name(&mut self, pin: &InPin<T>) -> Option<WidgetText> {
+        let _ = pin;
+        None
+    }
+
+    /// Returns name of the output pin shown between the widgets from `SnarlViewer::show_output` and the pin.
+    ///
+    /// Default implementation returns `None`, so no name is shown.
+    #[inline]
+    fn output_name(&mut self, pin: &OutPin<T>) -> Option<WidgetText> {
+        let _ = pin;
+        None
+    }
+
+    /// Returns text shown when the pointer hovers the output pin.
+    ///
+    /// Called only for the hovered pin.
+    /// Default implementation returns `None`, so no tooltip is shown.
+    #[inline]
+    fn output_tooltip(&mut self, pin: &OutPin<T>) -> Option<WidgetText> {
+        let _ = pin;
+        None
+    }
+
+    fn show_input(
+        &mut self,
+        pin: &InPin<T>,
+        ui: &mut Ui,
+        effects: &mut Effects<T>,
+    ) -> egui::InnerResponse<PinInfo>;
 
     fn show_output(
         &mut self,
@@ -336,463 +1210,3629 @@ pub trait SnarlViewer<T> {
     ) -> egui::InnerResponse<PinInfo>;
 }
 
+/// Layout of the nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NodeLayout {
+    /// Header with title on top, then content and pins.
+    /// Node is dragged by its header.
+    #[default]
+    Default,
+
+    /// No header, only content and pins.
+    /// Node is dragged by its background.
+    Headerless,
+}
+
+/// Layout of the input and output pin columns of the nodes.
+///
+/// Input rows are placed in the left column and output rows in the right column.
+/// Columns are sized independently, so a node with many wide inputs
+/// does not stretch the column of its outputs.
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct SnarlStyle {
-    pub pin_size: Option<f32>,
-    pub wire_width: Option<f32>,
-    pub wire_frame_size: Option<f32>,
-    pub downscale_wire: bool,
-    pub upscale_wire: bool,
+pub struct PinColumnLayout {
+    /// Width of the input column in graph space.
+    ///
+    /// When `None` the column is as wide as its widest row.
+    pub input_width: Option<f32>,
+
+    /// Width of the output column in graph space.
+    ///
+    /// When `None` the column is as wide as its widest row.
+    pub output_width: Option<f32>,
+
+    /// Horizontal alignment of the input rows in their column.
+    pub input_align: Align,
+
+    /// Horizontal alignment of the output rows in their column.
+    pub output_align: Align,
 }
 
-impl Default for SnarlStyle {
+impl Default for PinColumnLayout {
     fn default() -> Self {
-        SnarlStyle {
-            pin_size: None,
-            wire_width: None,
-            wire_frame_size: None,
-            downscale_wire: false,
-            upscale_wire: true,
+        PinColumnLayout {
+            input_width: None,
+            output_width: None,
+            input_align: Align::Min,
+            output_align: Align::Max,
         }
     }
 }
 
-impl SnarlStyle {
-    pub fn upscale_wire(mut self, upscale: bool) -> Self {
-        self.upscale_wire = upscale;
-        self
+/// Shape of the wires.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WireStyle {
+    /// Smooth curve leaving and entering pins horizontally.
+    #[default]
+    Bezier,
+
+    /// Straight line between pins.
+    Straight,
+
+    /// Horizontal and vertical segments joined with rounded corners.
+    AxisAligned {
+        /// Radius of the corners. Zero makes sharp corners.
+        corner_radius: f32,
+    },
+}
+
+impl WireStyle {
+    /// Returns polyline of the wire from output pin at `from` to input pin at `to`.
+    ///
+    /// `frame_size` is the distance the wire goes horizontally
+    /// away from the pins before turning.
+    /// Curves are sampled with segments not longer than `tolerance`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::ui::WireStyle;
+    /// let from = pos2(0.0, 0.0);
+    /// let to = pos2(100.0, 50.0);
+    ///
+    /// let path = WireStyle::Bezier.path(from, to, 10.0, 1.0);
+    /// assert_eq!((path[0], *path.last().unwrap()), (from, to));
+    /// assert!(path.len() > 2);
+    ///
+    /// assert_eq!(WireStyle::Straight.path(from, to, 10.0, 1.0), [from, to]);
+    ///
+    /// let sharp = WireStyle::AxisAligned { corner_radius: 0.0 };
+    /// assert_eq!(
+    ///     sharp.path(from, to, 10.0, 1.0),
+    ///     [from, pos2(50.0, 0.0), pos2(50.0, 50.0), to]
+    /// );
+    ///
+    /// // Wire going backwards loops around the pins.
+    /// assert_eq!(
+    ///     sharp.path(to, from, 10.0, 1.0),
+    ///     [to, pos2(110.0, 50.0), pos2(110.0, 25.0), pos2(-10.0, 25.0), pos2(-10.0, 0.0), from]
+    /// );
+    ///
+    /// let rounded = WireStyle::AxisAligned { corner_radius: 5.0 };
+    /// let path = rounded.path(from, to, 10.0, 1.0);
+    /// assert!(path.iter().all(|p| p.x >= 0.0 && p.x <= 100.0 && p.y >= 0.0 && p.y <= 50.0));
+    /// assert!(!path.contains(&pos2(50.0, 0.0)));
+    /// ```
+    pub fn path(self, from: Pos2, to: Pos2, frame_size: f32, tolerance: f32) -> Vec<Pos2> {
+        match self {
+            WireStyle::Bezier => {
+                sample_bezier_path(&wire_bezier(frame_size, false, false, from, to), tolerance)
+            }
+            WireStyle::Straight => vec![from, to],
+            WireStyle::AxisAligned { corner_radius } => {
+                let corners = axis_aligned_corners(from, to, frame_size);
+                round_corners(&corners, corner_radius, tolerance)
+            }
+        }
     }
 
-    pub fn downscale_wire(mut self, downscale: bool) -> Self {
-        self.downscale_wire = downscale;
-        self
+    /// Returns distance from `pos` to the wire from `from` to `to`.
+    ///
+    /// Arguments are the same as for `WireStyle::path`.
+    /// Curves are measured along their sampled polyline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::ui::WireStyle;
+    /// let from = pos2(0.0, 0.0);
+    /// let to = pos2(100.0, 0.0);
+    ///
+    /// let straight = WireStyle::Straight;
+    /// assert_eq!(straight.distance(pos2(50.0, 10.0), from, to, 10.0, 1.0), 10.0);
+    /// assert_eq!(straight.distance(pos2(-3.0, 4.0), from, to, 10.0, 1.0), 5.0);
+    ///
+    /// // Bezier between pins on the same height is a straight line.
+    /// let bezier = WireStyle::Bezier;
+    /// assert_eq!(bezier.distance(pos2(50.0, -10.0), from, to, 10.0, 1.0), 10.0);
+    ///
+    /// // Bezier leaves pins horizontally, so its middle is away from both pin heights.
+    /// let to = pos2(100.0, 100.0);
+    /// let d = bezier.distance(pos2(50.0, 50.0), from, to, 10.0, 1.0);
+    /// assert!(d < 1.0);
+    /// assert!(bezier.distance(pos2(0.0, 100.0), from, to, 10.0, 1.0) > 50.0);
+    /// ```
+    pub fn distance(self, pos: Pos2, from: Pos2, to: Pos2, frame_size: f32, tolerance: f32) -> f32 {
+        path_distance(pos, &self.path(from, to, frame_size, tolerance))
+    }
+
+    /// Returns point in the middle of the wire, measured along the wire.
+    /// Labels from `SnarlViewer::wire_label` are centered at it.
+    ///
+    /// Arguments are the same as for `WireStyle::path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::ui::WireStyle;
+    /// let from = pos2(0.0, 0.0);
+    /// let to = pos2(100.0, 50.0);
+    ///
+    /// assert_eq!(WireStyle::Straight.midpoint(from, to, 10.0, 1.0), pos2(50.0, 25.0));
+    ///
+    /// // Wire is symmetric, so its middle is at the center between the pins.
+    /// let mid = WireStyle::Bezier.midpoint(from, to, 10.0, 1.0);
+    /// assert!(mid.distance(pos2(50.0, 25.0)) < 0.5);
+    ///
+    /// let sharp = WireStyle::AxisAligned { corner_radius: 0.0 };
+    /// assert_eq!(sharp.midpoint(from, to, 10.0, 1.0), pos2(50.0, 25.0));
+    ///
+    /// // Middle of the loop around the pins.
+    /// assert_eq!(sharp.midpoint(to, from, 10.0, 1.0), pos2(50.0, 25.0));
+    ///
+    /// // Middle of the longer leg of an asymmetric wire.
+    /// let to = pos2(20.0, 100.0);
+    /// assert_eq!(sharp.midpoint(from, to, 10.0, 1.0), pos2(10.0, 50.0));
+    /// ```
+    pub fn midpoint(self, from: Pos2, to: Pos2, frame_size: f32, tolerance: f32) -> Pos2 {
+        let path = self.path(from, to, frame_size, tolerance);
+        path_point_at(&path, path_length(&path) * 0.5)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-enum AnyPin {
-    Out(OutPinId),
-    In(InPinId),
+/// Style of the background grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GridStyle {
+    /// Size of the grid cell in graph space.
+    ///
+    /// When `None` matches `SnarlStyle::snap_to_grid`
+    /// or defaults to 50 points if snapping is disabled.
+    pub spacing: Option<Vec2>,
+
+    /// Stroke of the grid lines.
+    ///
+    /// When `None` noninteractive widget stroke of the UI visuals is used.
+    pub stroke: Option<Stroke>,
+
+    /// Draws every N-th line with `major_stroke`.
+    pub major_every: Option<usize>,
+
+    /// Stroke of the major grid lines.
+    ///
+    /// When `None` it is `stroke` twice as wide.
+    pub major_stroke: Option<Stroke>,
 }
 
-impl<T> Snarl<T> {
-    fn apply_effects(&mut self, response: Effects<T>) {
-        for effect in response.effects {
-            self.apply_effect(effect);
-        }
-    }
+/// Grid lines closer than this on screen are thinned out.
+const MIN_GRID_SPACING: f32 = 4.0;
 
-    fn apply_effect(&mut self, effect: Effect<T>) {
-        match effect {
-            Effect::Connect { from, to } => {
-                assert!(self.nodes.contains(from.node));
-                assert!(self.nodes.contains(to.node));
-                self.wires.insert(wire_pins(from, to));
-            }
-            Effect::Disconnect { from, to } => {
-                assert!(self.nodes.contains(from.node));
-                assert!(self.nodes.contains(to.node));
-                self.wires.remove(&wire_pins(from, to));
-            }
-            Effect::DropOutputs { pin } => {
-                assert!(self.nodes.contains(pin.node));
-                self.wires.drop_outputs(pin);
+impl GridStyle {
+    /// Returns screen positions of the grid lines visible in the viewport.
+    ///
+    /// First vector holds x coordinates of vertical lines,
+    /// second vector holds y coordinates of horizontal lines.
+    /// Each coordinate is paired with a flag that marks major lines.
+    /// When lines are too dense on screen, only every second, fourth, etc. line is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{pos2, vec2, Rect};
+    /// # use egui_snarl::ui::{GridStyle, SnarlTransform};
+    /// let grid = GridStyle {
+    ///     major_every: Some(4),
+    ///     ..Default::default()
+    /// };
+    /// let viewport = Rect::from_min_size(pos2(0.0, 0.0), vec2(400.0, 300.0));
+    ///
+    /// let mut transform = SnarlTransform::default();
+    /// transform.scale = 0.5;
+    ///
+    /// let (vertical, horizontal) = grid.visible_lines(vec2(50.0, 50.0), &transform, viewport);
+    /// assert_eq!(vertical.len(), 17);
+    /// assert_eq!(horizontal.len(), 13);
+    /// assert_eq!(vertical.iter().filter(|(_, major)| *major).count(), 5);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn visible_lines(
+        &self,
+        spacing: Vec2,
+        transform: &SnarlTransform,
+        viewport: Rect,
+    ) -> (Vec<(f32, bool)>, Vec<(f32, bool)>) {
+        let lines = |spacing: f32, min: f32, max: f32, offset: f32| {
+            let mut lines = Vec::new();
+            if spacing <= 0.0 || transform.scale <= 0.0 {
+                return lines;
             }
-            Effect::DropInputs { pin } => {
-                assert!(self.nodes.contains(pin.node));
-                self.wires.drop_inputs(pin);
+
+            let mut step = 1;
+            while spacing * transform.scale * step as f32 <= MIN_GRID_SPACING {
+                step *= 2;
             }
-            Effect::RemoveNode { node } => {
-                assert!(self.nodes.contains(node));
-                self.remove_node(node);
+
+            let screen_spacing = spacing * transform.scale;
+            let first = ((min - offset) / screen_spacing).ceil() as i64;
+            let last = ((max - offset) / screen_spacing).floor() as i64;
+
+            let first = first.div_euclid(step) * step;
+            let mut idx = first;
+            while idx <= last {
+                if idx as f32 * screen_spacing + offset >= min {
+                    let major = match self.major_every {
+                        Some(n) if n > 0 => idx.rem_euclid(n as i64) == 0,
+                        _ => false,
+                    };
+                    lines.push((idx as f32 * screen_spacing + offset, major));
+                }
+                idx += step;
             }
-            Effect::Closure(f) => f(self),
-        }
+            lines
+        };
+
+        let origin = transform.graph_pos_to_screen(Pos2::ZERO, viewport);
+        (
+            lines(spacing.x, viewport.min.x, viewport.max.x, origin.x),
+            lines(spacing.y, viewport.min.y, viewport.max.y, origin.y),
+        )
     }
+}
 
-    pub fn show<V>(&mut self, viewer: &mut V, style: &SnarlStyle, snarl_id: Id, ui: &mut Ui)
-    where
-        V: SnarlViewer<T>,
-    {
-        let mut effects = Effects::new();
-        let mut nodes_moved = Vec::new();
-        let mut node_order_to_top = None;
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnarlStyle {
+    pub pin_size: Option<f32>,
 
-        self._show(
-            viewer,
-            style,
-            snarl_id,
-            ui,
-            &mut effects,
-            &mut nodes_moved,
-            &mut node_order_to_top,
-        );
-        self.apply_effects(effects);
+    /// Width of the wires in graph space, scaled with zoom.
+    ///
+    /// When `None` it is one fifth of the pin size.
+    /// See `SnarlStyle::effective_wire_width`.
+    pub wire_width: Option<f32>,
 
-        for (node_idx, delta) in nodes_moved {
-            let node = &mut self.nodes[node_idx];
-            node.pos += delta;
-        }
+    /// Minimum width of the wires on screen, so that they stay visible when zoomed out.
+    pub min_wire_width: Option<f32>,
 
-        if let Some(order) = node_order_to_top {
-            let node_idx = self.draw_order.remove(order);
-            self.draw_order.push(node_idx);
-        }
-    }
+    /// Maximum width of the wires on screen, so that they do not cover nodes when zoomed in.
+    pub max_wire_width: Option<f32>,
 
-    fn _show<V>(
-        &self,
-        viewer: &mut V,
-        style: &SnarlStyle,
-        snarl_id: Id,
-        ui: &mut Ui,
-        effects: &mut Effects<T>,
-        nodes_moved: &mut Vec<(usize, Vec2)>,
-        node_order_to_top: &mut Option<usize>,
-    ) where
-        V: SnarlViewer<T>,
-    {
-        Frame::none()
-            .fill(ui.style().visuals.widgets.inactive.bg_fill)
-            .stroke(ui.style().visuals.widgets.inactive.bg_stroke)
-            .show(ui, |ui| {
-                let pin_size = style
-                    .pin_size
-                    .unwrap_or_else(|| ui.style().spacing.interact_size.y * 0.5);
+    pub wire_frame_size: Option<f32>,
 
-                let wire_frame_size = style.wire_frame_size.unwrap_or(pin_size * 5.0);
-                let wire_width = style.wire_width.unwrap_or_else(|| pin_size * 0.2);
+    /// Shrinks the bend of the wire frame for pins closer than the frame size.
+    pub downscale_wire: bool,
 
-                let max_rect = ui.max_rect();
+    /// Grows the bend of the wire frame for pins far apart.
+    pub upscale_wire: bool,
 
-                let r = ui.allocate_rect(max_rect, Sense::click());
+    /// Shape of the wires.
+    pub wire_style: WireStyle,
 
-                let mut input_positions = HashMap::with_hasher(egui::ahash::RandomState::new());
-                let mut output_positions = HashMap::with_hasher(egui::ahash::RandomState::new());
+    /// Color of the wires passed to `SnarlViewer::wire_color`.
+    ///
+    /// When `None` it is the mix of the colors of the wire pins.
+    pub wire_color: Option<Color32>,
 
-                let mut input_colors = HashMap::with_hasher(egui::ahash::RandomState::new());
-                let mut output_colors = HashMap::with_hasher(egui::ahash::RandomState::new());
+    /// Dash pattern of the wires.
+    ///
+    /// Alternating lengths of dashes and gaps measured along the wire.
+    /// When `None` wires are drawn solid.
+    pub wire_dash: Option<Vec<f32>>,
 
-                let mut part_wire_drag_released = false;
-                let mut pin_hovered = None;
+    /// Animates dashes moving along the wires from outputs to inputs.
+    ///
+    /// Uses `wire_dash` pattern, or a default one if it is not set.
+    pub wire_flow: bool,
 
-                for (order, &node_idx) in self.draw_order.iter().enumerate() {
-                    let node = &self.nodes[node_idx];
-                    let node_rect = Rect::from_min_size(
-                        node.pos + vec2(max_rect.min.x, max_rect.min.y),
-                        viewer.size_hint(&node.value.borrow()),
-                    );
+    /// Speed of the wire flow animation in points per second.
+    pub wire_flow_speed: f32,
 
-                    let ref mut ui = ui.child_ui_with_id_source(
-                        node_rect,
-                        Layout::top_down(Align::Center),
-                        node_idx,
-                    );
-                    ui.set_clip_rect(max_rect);
+    /// Spreads wires leaving the same output pin apart, so they do not overlap at the pin.
+    ///
+    /// Wires are ordered by their input pins, so the spread is stable between frames.
+    pub fan_out_wires: bool,
 
-                    Frame::window(ui.style()).show(ui, |ui| {
-                        let r = ui.vertical(|ui| {
-                            ui.label(viewer.title(&node.value.borrow()));
-                            ui.separator();
-                        });
+    /// Stroke of the wire being dragged from a pin, with width in graph space.
+    ///
+    /// Semi-transparent stroke tells the wire is not connected yet.
+    /// When `None` the wire is drawn like connected wires in the color of the pin.
+    pub pending_wire_stroke: Option<Stroke>,
 
-                        let r = ui.interact(r.response.rect, r.response.id, Sense::drag());
-                        if r.dragged_by(PointerButton::Primary) {
-                            nodes_moved.push((node_idx, r.drag_delta()));
-                        }
-                        if r.clicked_by(PointerButton::Primary)
-                            || r.dragged_by(PointerButton::Primary)
-                        {
-                            *node_order_to_top = Some(order);
-                        }
+    /// Dash pattern of the wire being dragged from a pin.
+    ///
+    /// When `None` `wire_dash` is used.
+    pub pending_wire_dash: Option<Vec<f32>>,
 
-                        let inputs_count = viewer.inputs(&node.value.borrow());
-                        let outputs_count = viewer.outputs(&node.value.borrow());
+    /// Stroke of the wire being dragged over a pin it cannot be connected to,
+    /// with width in graph space.
+    ///
+    /// Pins are checked with `SnarlViewer::can_connect`.
+    /// When `None` the wire is drawn in the error color of the UI visuals.
+    pub forbidden_wire_stroke: Option<Stroke>,
 
-                        let inputs = (0..inputs_count)
-                            .map(|idx| {
-                                InPin::input(
-                                    &self,
-                                    InPinId {
-                                        node: node_idx,
-                                        input: idx,
-                                    },
-                                )
-                            })
-                            .collect::<Vec<_>>();
-
-                        let outputs = (0..outputs_count)
-                            .map(|idx| {
-                                OutPin::output(
-                                    &self,
-                                    OutPinId {
-                                        node: node_idx,
-                                        output: idx,
-                                    },
-                                )
-                            })
-                            .collect::<Vec<_>>();
+    /// Length of the stub drawn from unconnected pins in the direction of the wire.
+    ///
+    /// Stubs help to find grab targets and show pin direction.
+    /// Zero disables stubs.
+    pub pin_stub_length: f32,
 
-                        viewer.show_content(node_idx, &node.value, &inputs, &outputs, ui, effects);
+    /// Radius of the area around pin that reacts to the pointer.
+    ///
+    /// Enlarges pin hitboxes without changing how pins look,
+    /// which helps on touch screens.
+    /// When hitboxes overlap, the pin closest to the pointer wins.
+    /// When `None` hitbox matches visual pin size.
+    pub pin_hit_radius: Option<f32>,
 
-                        // let r = ui.interact(r.response.rect, r.response.id, Sense::drag());
+    /// Radius around the pointer in which a dragged wire snaps to a pin.
+    ///
+    /// Releasing the wire connects it to the nearest pin within the radius
+    /// that `SnarlViewer::can_connect` accepts, incompatible pins are ignored.
+    /// Zero disables snapping beyond pin hitboxes.
+    pub pin_snap_radius: f32,
 
-                        ui.horizontal(|ui| {
-                            ui.with_layout(Layout::top_down(Align::Min), |ui| {
-                                for input_idx in 0..inputs_count {
-                                    let in_pin = InPin::input(
-                                        &self,
-                                        InPinId {
-                                            node: node_idx,
-                                            input: input_idx,
-                                        },
-                                    );
+    /// Outline of the pins, replaces stroke of `PinInfo` returned by the viewer.
+    ///
+    /// When `None` pins are outlined as the viewer tells.
+    pub pin_stroke: Option<Stroke>,
 
-                                    ui.horizontal(|ui| {
-                                        ui.allocate_space(vec2(pin_size, pin_size));
+    /// Layout of the nodes.
+    pub node_layout: NodeLayout,
 
-                                        let r = viewer.show_input(&in_pin, ui, effects);
-                                        let pin_info = r.inner;
+    /// Frame of the nodes in graph space, passed to `SnarlViewer::node_frame`.
+    ///
+    /// When `None` it is the window frame of the UI style.
+    pub node_frame: Option<Frame>,
 
-                                        let x = r.response.rect.left()
-                                            - pin_size / 2.0
-                                            - ui.style().spacing.item_spacing.x;
+    /// Minimum height of the node header in graph space.
+    ///
+    /// When `None` header is as high as its content.
+    pub header_height: Option<f32>,
 
-                                        let y = (r.response.rect.top() + r.response.rect.bottom())
-                                            / 2.0;
+    /// Space between the node frame and its content in graph space.
+    ///
+    /// Replaces inner margin of the node frame. When `None` the frame margin is kept.
+    pub node_padding: Option<Margin>,
 
-                                        let r = ui.allocate_rect(
-                                            Rect::from_center_size(
-                                                pos2(x, y),
-                                                vec2(pin_size, pin_size),
-                                            ),
-                                            Sense::click_and_drag(),
-                                        );
+    /// Vertical space between pin rows in graph space.
+    ///
+    /// When `None` item spacing of the UI style is used.
+    pub row_spacing: Option<f32>,
 
-                                        let mut pin_size = pin_size;
-                                        if r.hovered() {
-                                            pin_size *= 1.2;
-                                        }
+    /// Layout of the input and output pin columns of the nodes.
+    pub pin_column_layout: PinColumnLayout,
 
-                                        draw_pin(ui.painter(), pin_info, r.rect.center(), pin_size);
+    /// Shows node title in a tooltip when node is hovered.
+    ///
+    /// Useful with `NodeLayout::Headerless` to identify nodes on demand.
+    pub title_as_tooltip: bool,
 
-                                        if r.clicked_by(PointerButton::Secondary) {
-                                            let _ = viewer.drop_inputs(&in_pin, effects);
-                                        }
-                                        if r.drag_started_by(PointerButton::Primary) {
-                                            set_part_wire(ui, snarl_id, AnyPin::In(in_pin.id));
-                                        }
-                                        if r.drag_released_by(PointerButton::Primary) {
-                                            part_wire_drag_released = true;
-                                        }
-                                        if r.hovered() {
-                                            pin_hovered = Some(AnyPin::In(in_pin.id));
-                                        }
+    /// Makes plain mouse wheel zoom the graph.
+    ///
+    /// When disabled mouse wheel scrolls the graph.
+    /// Ctrl+wheel and pinch gestures zoom regardless of this setting.
+    pub scroll_to_zoom: bool,
 
-                                        input_positions.insert(in_pin.id, r.rect.center());
-                                        input_colors.insert(in_pin.id, pin_info.fill);
-                                    });
-                                }
-                            });
+    /// Modifiers that turn mouse wheel into zoom.
+    ///
+    /// When set, overrides `scroll_to_zoom`:
+    /// plain mouse wheel scrolls the graph
+    /// and mouse wheel with these modifiers held zooms it.
+    pub zoom_modifier: Option<Modifiers>,
 
-                            ui.with_layout(Layout::top_down(Align::Max), |ui| {
-                                for output_idx in 0..outputs_count {
-                                    let out_pin = OutPin::output(
-                                        &self,
-                                        OutPinId {
-                                            node: node_idx,
-                                            output: output_idx,
-                                        },
-                                    );
+    /// Minimum scale of the graph.
+    pub min_scale: f32,
 
-                                    ui.horizontal(|ui| {
-                                        let r = viewer.show_output(&out_pin, ui, effects);
-                                        let pin_info = r.inner;
+    /// Maximum scale of the graph.
+    pub max_scale: f32,
 
-                                        ui.allocate_space(vec2(pin_size, pin_size));
+    /// Enables default keyboard shortcuts.
+    ///
+    /// Built-in key bindings of `Snarl::show`:
+    ///
+    /// | Key                    | Action                                                      | Enabled by          |
+    /// |------------------------|-------------------------------------------------------------|---------------------|
+    /// | `Escape`               | Cancel wire that is being dragged and deselect pin and wire | `default_shortcuts` |
+    /// | `Delete`               | Disconnect selected wire                                    | `default_shortcuts` |
+    /// | `Delete`, `Backspace`  | Remove selected nodes when no wire is selected              | `enable_keyboard`   |
+    /// | `Home`                 | Reset pan and zoom                                          | `default_shortcuts` |
+    /// | `Escape`               | Close open context menu                                     | always              |
+    /// | `Space` + primary drag | Pan the graph                                               | always              |
+    ///
+    /// Each key press triggers at most one action,
+    /// so `Delete` with a wire selected does not remove selected nodes.
+    /// Events consumed by `SnarlViewer::handle_shortcut` are not handled here.
+    pub default_shortcuts: bool,
 
-                                        let x = r.response.rect.right()
-                                            + pin_size / 2.0
-                                            + ui.style().spacing.item_spacing.x;
+    /// Enables keyboard editing of the graph.
+    ///
+    /// `Delete` and `Backspace` remove selected nodes through `SnarlViewer::remove_node`,
+    /// unless `Delete` disconnects selected wire, see `SnarlStyle::default_shortcuts`.
+    /// Keys are ignored while any widget has keyboard focus,
+    /// so editing text inside nodes is not affected.
+    pub enable_keyboard: bool,
 
-                                        let y = (r.response.rect.top() + r.response.rect.bottom())
-                                            / 2.0;
+    /// Allows editing the graph in the UI.
+    ///
+    /// When false, pins, nodes, comments and wires cannot be dragged, connected,
+    /// selected, resized, collapsed or removed, context menus are not opened
+    /// and `SnarlViewer::handle_shortcut` is not called.
+    /// Everything is still drawn and the graph can be panned and zoomed.
+    /// Widgets shown by the viewer are not affected.
+    pub editable: bool,
+
+    /// Size of the grid cell node positions are snapped to, in graph space.
+    ///
+    /// Nodes snap to the grid when dragging ends.
+    /// When `None` nodes are placed freely.
+    pub snap_to_grid: Option<Vec2>,
+
+    /// Grid drawn behind the nodes.
+    ///
+    /// When `None` no grid is drawn.
+    pub background_grid: Option<GridStyle>,
+
+    /// Moves nodes intersecting a comment when the comment is dragged.
+    ///
+    /// See `Snarl::comment_nodes`.
+    pub comment_drags_nodes: bool,
+
+    /// Stroke of the border drawn around selected nodes.
+    ///
+    /// When `None` selection stroke of the UI visuals is used.
+    pub selection_stroke: Option<Stroke>,
+
+    /// Color the fill of pins that can accept the dragged wire is tinted towards,
+    /// see `Snarl::compatible_pins`.
+    ///
+    /// When `None` compatible pins are lightened.
+    pub compatible_pin_tint: Option<Color32>,
+
+    /// Color the fill of pins that cannot accept the dragged wire is tinted towards.
+    ///
+    /// When `None` incompatible pins fade into the background color of the UI visuals.
+    pub incompatible_pin_tint: Option<Color32>,
+
+    /// Fill of required input pins that have no wires, see `SnarlViewer::input_required`.
+    ///
+    /// When `None` warning color of the UI visuals is used.
+    pub unconnected_required_fill: Option<Color32>,
+
+    /// Stroke of the border drawn around highlighted nodes, see `Snarl::set_highlighted`.
+    ///
+    /// When `None` warning color of the UI visuals is used.
+    pub search_highlight_stroke: Option<Stroke>,
+
+    /// Fill of the rectangle drawn while selecting nodes by dragging on empty canvas.
+    ///
+    /// When `None` selection background of the UI visuals is used.
+    pub select_rect_fill: Option<Color32>,
+
+    /// Stroke of the rectangle drawn while selecting nodes by dragging on empty canvas.
+    ///
+    /// When `None` selection stroke of the UI visuals is used.
+    pub select_rect_stroke: Option<Stroke>,
+
+    /// Highlights wires of the hovered pin and pins at their other ends.
+    pub highlight_connections: bool,
+
+    /// Maximum distance from the pointer to the wire for the wire to be hovered and clicked.
+    ///
+    /// When `None` it is 1.5 times the wire width.
+    pub wire_hit_distance: Option<f32>,
+
+    /// Minimum size of nodes resized by dragging, in graph space.
+    pub node_min_size: Vec2,
+
+    /// Removes wires to pins that no longer exist before showing the Snarl.
+    ///
+    /// Useful when viewer changes number of pins of a node.
+    /// See `Snarl::prune_invalid_wires`.
+    pub prune_invalid_wires: bool,
+
+    /// Shows minimap with node rects and the viewport in a corner of the Snarl.
+    ///
+    /// Clicking or dragging on the minimap pans the graph.
+    pub show_minimap: bool,
+
+    /// Size of the minimap panel.
+    pub minimap_size: Vec2,
+
+    /// Corner or side of the Snarl where the minimap is placed.
+    pub minimap_anchor: Align2,
+}
+
+impl Default for SnarlStyle {
+    fn default() -> Self {
+        SnarlStyle {
+            pin_size: None,
+            wire_width: None,
+            min_wire_width: None,
+            max_wire_width: None,
+            wire_frame_size: None,
+            downscale_wire: false,
+            upscale_wire: true,
+            wire_style: WireStyle::Bezier,
+            wire_color: None,
+            wire_dash: None,
+            wire_flow: false,
+            wire_flow_speed: 20.0,
+            fan_out_wires: false,
+            pending_wire_stroke: None,
+            pending_wire_dash: None,
+            forbidden_wire_stroke: None,
+            pin_stub_length: 0.0,
+            pin_hit_radius: None,
+            pin_snap_radius: 0.0,
+            pin_stroke: None,
+            node_layout: NodeLayout::Default,
+            node_frame: None,
+            header_height: None,
+            node_padding: None,
+            row_spacing: None,
+            pin_column_layout: PinColumnLayout::default(),
+            title_as_tooltip: false,
+            scroll_to_zoom: true,
+            zoom_modifier: None,
+            min_scale: 0.2,
+            max_scale: 5.0,
+            default_shortcuts: true,
+            enable_keyboard: false,
+            editable: true,
+            snap_to_grid: None,
+            background_grid: None,
+            comment_drags_nodes: true,
+            selection_stroke: None,
+            compatible_pin_tint: None,
+            incompatible_pin_tint: None,
+            unconnected_required_fill: None,
+            search_highlight_stroke: None,
+            select_rect_fill: None,
+            select_rect_stroke: None,
+            highlight_connections: false,
+            wire_hit_distance: None,
+            node_min_size: vec2(32.0, 32.0),
+            prune_invalid_wires: false,
+            show_minimap: false,
+            minimap_size: vec2(200.0, 150.0),
+            minimap_anchor: Align2::RIGHT_BOTTOM,
+        }
+    }
+}
+
+impl SnarlStyle {
+    /// Creates style with wire, pin, grid and node colors taken from the egui `style`,
+    /// so that the Snarl matches the rest of the UI.
+    ///
+    /// Wires are colored with the noninteractive widget foreground stroke,
+    /// pins are outlined with the window stroke,
+    /// grid lines use the noninteractive widget background stroke
+    /// and nodes are framed as windows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{Style, Visuals};
+    /// # use egui_snarl::ui::SnarlStyle;
+    /// let style = Style {
+    ///     visuals: Visuals::light(),
+    ///     ..Style::default()
+    /// };
+    /// let snarl_style = SnarlStyle::from_egui(&style);
+    ///
+    /// let widgets = &style.visuals.widgets;
+    /// assert_eq!(snarl_style.wire_color, Some(widgets.noninteractive.fg_stroke.color));
+    /// assert_eq!(
+    ///     snarl_style.background_grid.unwrap().stroke,
+    ///     Some(widgets.noninteractive.bg_stroke)
+    /// );
+    ///
+    /// assert_eq!(SnarlStyle::light(), snarl_style);
+    /// assert_ne!(SnarlStyle::dark().wire_color, snarl_style.wire_color);
+    /// ```
+    pub fn from_egui(style: &Style) -> Self {
+        let visuals = &style.visuals;
+        let widgets = &visuals.widgets;
+
+        SnarlStyle {
+            wire_color: Some(widgets.noninteractive.fg_stroke.color),
+            pin_stroke: Some(visuals.window_stroke()),
+            node_frame: Some(Frame::window(style)),
+            background_grid: Some(GridStyle {
+                stroke: Some(widgets.noninteractive.bg_stroke),
+                ..GridStyle::default()
+            }),
+            selection_stroke: Some(visuals.selection.stroke),
+            unconnected_required_fill: Some(visuals.warn_fg_color),
+            ..SnarlStyle::default()
+        }
+    }
+
+    /// Creates style matching the dark egui theme.
+    pub fn dark() -> Self {
+        Self::from_egui(&Style {
+            visuals: Visuals::dark(),
+            ..Style::default()
+        })
+    }
+
+    /// Creates style matching the light egui theme.
+    pub fn light() -> Self {
+        Self::from_egui(&Style {
+            visuals: Visuals::light(),
+            ..Style::default()
+        })
+    }
+
+    /// Returns pin info with the outline overridden by `pin_stroke`.
+    fn styled_pin(&self, mut pin: PinInfo) -> PinInfo {
+        if let Some(stroke) = self.pin_stroke {
+            pin.stroke = stroke;
+        }
+        pin
+    }
+
+    /// Returns pin size in graph space.
+    fn base_pin_size(&self, ui_style: &Style) -> f32 {
+        self.pin_size
+            .unwrap_or(ui_style.spacing.interact_size.y * 0.5)
+    }
+
+    /// Returns width of the wires on screen at zoom `scale`.
+    ///
+    /// `wire_width` is scaled and then clamped
+    /// between `min_wire_width` and `max_wire_width`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::ui::SnarlStyle;
+    /// let ui_style = egui::Style::default();
+    /// let mut style = SnarlStyle::default();
+    /// style.wire_width = Some(2.0);
+    ///
+    /// assert_eq!(style.effective_wire_width(0.2, &ui_style), 0.4);
+    /// assert_eq!(style.effective_wire_width(1.0, &ui_style), 2.0);
+    /// assert_eq!(style.effective_wire_width(5.0, &ui_style), 10.0);
+    ///
+    /// style.min_wire_width = Some(1.0);
+    /// style.max_wire_width = Some(4.0);
+    /// assert_eq!(style.effective_wire_width(0.2, &ui_style), 1.0);
+    /// assert_eq!(style.effective_wire_width(1.0, &ui_style), 2.0);
+    /// assert_eq!(style.effective_wire_width(5.0, &ui_style), 4.0);
+    /// ```
+    pub fn effective_wire_width(&self, scale: f32, ui_style: &Style) -> f32 {
+        let mut width = self
+            .wire_width
+            .unwrap_or_else(|| self.base_pin_size(ui_style) * 0.2)
+            * scale;
+        if let Some(min) = self.min_wire_width {
+            width = width.max(min);
+        }
+        if let Some(max) = self.max_wire_width {
+            width = width.min(max);
+        }
+        width
+    }
+
+    pub fn wire_width_range(mut self, min: Option<f32>, max: Option<f32>) -> Self {
+        self.min_wire_width = min;
+        self.max_wire_width = max;
+        self
+    }
+
+    pub fn upscale_wire(mut self, upscale: bool) -> Self {
+        self.upscale_wire = upscale;
+        self
+    }
+
+    pub fn downscale_wire(mut self, downscale: bool) -> Self {
+        self.downscale_wire = downscale;
+        self
+    }
+
+    pub fn wire_style(mut self, wire_style: WireStyle) -> Self {
+        self.wire_style = wire_style;
+        self
+    }
+
+    pub fn wire_color(mut self, color: Option<Color32>) -> Self {
+        self.wire_color = color;
+        self
+    }
+
+    pub fn wire_dash(mut self, pattern: Option<Vec<f32>>) -> Self {
+        self.wire_dash = pattern;
+        self
+    }
+
+    pub fn wire_flow(mut self, enable: bool) -> Self {
+        self.wire_flow = enable;
+        self
+    }
+
+    pub fn wire_flow_speed(mut self, speed: f32) -> Self {
+        self.wire_flow_speed = speed;
+        self
+    }
+
+    /// Enables spreading of wires leaving the same output pin.
+    pub fn fan_out_wires(mut self, enable: bool) -> Self {
+        self.fan_out_wires = enable;
+        self
+    }
+
+    pub fn pending_wire_stroke(mut self, stroke: Option<Stroke>) -> Self {
+        self.pending_wire_stroke = stroke;
+        self
+    }
+
+    pub fn pending_wire_dash(mut self, pattern: Option<Vec<f32>>) -> Self {
+        self.pending_wire_dash = pattern;
+        self
+    }
+
+    pub fn forbidden_wire_stroke(mut self, stroke: Option<Stroke>) -> Self {
+        self.forbidden_wire_stroke = stroke;
+        self
+    }
+
+    /// Returns phase of the wire dash pattern at `time` in seconds.
+    ///
+    /// Phase is zero when wire flow is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::ui::SnarlStyle;
+    /// let style = SnarlStyle::default().wire_flow(true).wire_flow_speed(10.0);
+    /// assert_eq!(style.wire_flow_phase(0.5) - style.wire_flow_phase(2.0), 15.0);
+    /// assert_eq!(style.wire_flow(false).wire_flow_phase(2.0), 0.0);
+    /// ```
+    pub fn wire_flow_phase(&self, time: f64) -> f32 {
+        if self.wire_flow {
+            // Pattern moves towards the end of the wire.
+            -(time * self.wire_flow_speed as f64) as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn pin_stub_length(mut self, length: f32) -> Self {
+        self.pin_stub_length = length;
+        self
+    }
+
+    pub fn pin_hit_radius(mut self, radius: Option<f32>) -> Self {
+        self.pin_hit_radius = radius;
+        self
+    }
+
+    pub fn pin_snap_radius(mut self, radius: f32) -> Self {
+        self.pin_snap_radius = radius;
+        self
+    }
+
+    pub fn pin_stroke(mut self, stroke: Option<Stroke>) -> Self {
+        self.pin_stroke = stroke;
+        self
+    }
+
+    pub fn node_layout(mut self, layout: NodeLayout) -> Self {
+        self.node_layout = layout;
+        self
+    }
+
+    pub fn node_frame(mut self, frame: Option<Frame>) -> Self {
+        self.node_frame = frame;
+        self
+    }
+
+    pub fn header_height(mut self, height: Option<f32>) -> Self {
+        self.header_height = height;
+        self
+    }
+
+    pub fn node_padding(mut self, padding: Option<Margin>) -> Self {
+        self.node_padding = padding;
+        self
+    }
+
+    /// Sets vertical space between pin rows.
+    pub fn row_spacing(mut self, spacing: Option<f32>) -> Self {
+        self.row_spacing = spacing;
+        self
+    }
+
+    pub fn pin_column_layout(mut self, layout: PinColumnLayout) -> Self {
+        self.pin_column_layout = layout;
+        self
+    }
+
+    pub fn title_as_tooltip(mut self, enable: bool) -> Self {
+        self.title_as_tooltip = enable;
+        self
+    }
+
+    pub fn scroll_to_zoom(mut self, enable: bool) -> Self {
+        self.scroll_to_zoom = enable;
+        self
+    }
+
+    pub fn zoom_modifier(mut self, modifiers: Option<Modifiers>) -> Self {
+        self.zoom_modifier = modifiers;
+        self
+    }
+
+    pub fn scale_range(mut self, min_scale: f32, max_scale: f32) -> Self {
+        self.min_scale = min_scale;
+        self.max_scale = max_scale;
+        self
+    }
+
+    pub fn default_shortcuts(mut self, enable: bool) -> Self {
+        self.default_shortcuts = enable;
+        self
+    }
+
+    /// Enables keyboard editing of the graph, see `SnarlStyle::enable_keyboard`.
+    pub fn enable_keyboard(mut self, enable: bool) -> Self {
+        self.enable_keyboard = enable;
+        self
+    }
+
+    /// Allows editing the graph in the UI, see `SnarlStyle::editable`.
+    pub fn editable(mut self, editable: bool) -> Self {
+        self.editable = editable;
+        self
+    }
+
+    pub fn snap_to_grid(mut self, grid: Option<Vec2>) -> Self {
+        self.snap_to_grid = grid;
+        self
+    }
+
+    pub fn background_grid(mut self, grid: Option<GridStyle>) -> Self {
+        self.background_grid = grid;
+        self
+    }
+
+    pub fn comment_drags_nodes(mut self, enable: bool) -> Self {
+        self.comment_drags_nodes = enable;
+        self
+    }
+
+    pub fn selection_stroke(mut self, stroke: Option<Stroke>) -> Self {
+        self.selection_stroke = stroke;
+        self
+    }
+
+    pub fn compatible_pin_tint(mut self, tint: Option<Color32>) -> Self {
+        self.compatible_pin_tint = tint;
+        self
+    }
+
+    pub fn incompatible_pin_tint(mut self, tint: Option<Color32>) -> Self {
+        self.incompatible_pin_tint = tint;
+        self
+    }
+
+    pub fn unconnected_required_fill(mut self, fill: Option<Color32>) -> Self {
+        self.unconnected_required_fill = fill;
+        self
+    }
+
+    pub fn search_highlight_stroke(mut self, stroke: Option<Stroke>) -> Self {
+        self.search_highlight_stroke = stroke;
+        self
+    }
+
+    pub fn select_rect_fill(mut self, fill: Option<Color32>) -> Self {
+        self.select_rect_fill = fill;
+        self
+    }
+
+    pub fn select_rect_stroke(mut self, stroke: Option<Stroke>) -> Self {
+        self.select_rect_stroke = stroke;
+        self
+    }
+
+    pub fn highlight_connections(mut self, enable: bool) -> Self {
+        self.highlight_connections = enable;
+        self
+    }
+
+    pub fn wire_hit_distance(mut self, distance: Option<f32>) -> Self {
+        self.wire_hit_distance = distance;
+        self
+    }
+
+    pub fn node_min_size(mut self, size: Vec2) -> Self {
+        self.node_min_size = size;
+        self
+    }
+
+    pub fn prune_invalid_wires(mut self, enable: bool) -> Self {
+        self.prune_invalid_wires = enable;
+        self
+    }
+
+    pub fn show_minimap(mut self, enable: bool) -> Self {
+        self.show_minimap = enable;
+        self
+    }
+
+    pub fn minimap_size(mut self, size: Vec2) -> Self {
+        self.minimap_size = size;
+        self
+    }
+
+    pub fn minimap_anchor(mut self, anchor: Align2) -> Self {
+        self.minimap_anchor = anchor;
+        self
+    }
+
+    /// Returns rect of the minimap panel placed within the Snarl `viewport`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{pos2, vec2, Align2, Rect};
+    /// # use egui_snarl::ui::SnarlStyle;
+    /// let viewport = Rect::from_min_size(pos2(0.0, 0.0), vec2(800.0, 600.0));
+    /// let style = SnarlStyle::default()
+    ///     .minimap_size(vec2(100.0, 50.0))
+    ///     .minimap_anchor(Align2::LEFT_TOP);
+    /// assert_eq!(
+    ///     style.minimap_rect(viewport),
+    ///     Rect::from_min_size(pos2(8.0, 8.0), vec2(100.0, 50.0))
+    /// );
+    /// ```
+    pub fn minimap_rect(&self, viewport: Rect) -> Rect {
+        self.minimap_anchor
+            .align_size_within_rect(self.minimap_size, viewport.shrink(MINIMAP_MARGIN))
+    }
+}
+
+/// Element of the Snarl under the pointer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SnarlHover {
+    /// Node, including its header and content.
+    Node(NodeId),
+
+    /// Input pin.
+    InPin(InPinId),
+
+    /// Output pin.
+    OutPin(OutPinId),
+
+    /// Wire between the pins.
+    Wire(OutPinId, InPinId),
+
+    /// Empty space of the Snarl.
+    Background,
+}
+
+/// What happened in a frame of `Snarl::show`.
+///
+/// # Examples
+///
+/// ```
+/// # use egui::{Id, Ui};
+/// # use egui_snarl::{Snarl, ui::{SnarlHover, SnarlStyle, SnarlViewer}};
+/// fn show(snarl: &mut Snarl<()>, viewer: &mut impl SnarlViewer<()>, ui: &mut Ui) {
+///     let response = snarl.show(viewer, &SnarlStyle::default(), Id::new("snarl"), ui);
+///     if let Some(SnarlHover::Node(node)) = response.hovered {
+///         ui.label(format!("Hovered node {node:?}"));
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnarlResponse {
+    /// Element under the pointer.
+    /// Resolved by the same hit tests as interactions,
+    /// so pins take precedence over nodes and nodes over wires.
+    ///
+    /// `None` if the pointer is outside of the Snarl.
+    pub hovered: Option<SnarlHover>,
+
+    /// Wires connected in the frame, sorted.
+    pub connected: Vec<(OutPinId, InPinId)>,
+
+    /// Wires disconnected in the frame, including wires of removed nodes, sorted.
+    pub disconnected: Vec<(OutPinId, InPinId)>,
+
+    /// Nodes that changed position in the frame, in ascending order.
+    pub moved: Vec<NodeId>,
+
+    /// True if the set of selected nodes changed in the frame
+    /// or since the previous frame, see `SnarlViewer::on_selection_changed`.
+    pub selection_changed: bool,
+}
+
+/// Geometry of the nodes, pins and wires as they were rendered in the last frame.
+///
+/// Positions are in screen space.
+/// Useful for tests and overlays that need to know where things are without
+/// comparing pixels.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LayoutSnapshot {
+    /// Rects of the rendered nodes.
+    pub nodes: Vec<(NodeId, Rect)>,
+
+    /// Centers of the rendered input pins.
+    pub inputs: Vec<(InPinId, Pos2)>,
+
+    /// Centers of the rendered output pins.
+    pub outputs: Vec<(OutPinId, Pos2)>,
+
+    /// Rendered wires with their endpoints.
+    pub wires: Vec<(OutPinId, InPinId, Pos2, Pos2)>,
+
+    /// Pin under the pointer.
+    pub hovered_pin: Option<AnyPinId>,
+}
+
+impl LayoutSnapshot {
+    /// Returns rect of the node if it was rendered.
+    pub fn node_rect(&self, node: NodeId) -> Option<Rect> {
+        self.nodes
+            .iter()
+            .find(|(idx, _)| *idx == node)
+            .map(|(_, rect)| *rect)
+    }
+
+    /// Returns center of the input pin if it was rendered.
+    pub fn input_pos(&self, pin: InPinId) -> Option<Pos2> {
+        self.inputs
+            .iter()
+            .find(|(id, _)| *id == pin)
+            .map(|(_, pos)| *pos)
+    }
+
+    /// Returns center of the output pin if it was rendered.
+    pub fn output_pos(&self, pin: OutPinId) -> Option<Pos2> {
+        self.outputs
+            .iter()
+            .find(|(id, _)| *id == pin)
+            .map(|(_, pos)| *pos)
+    }
+
+    /// Returns pin closest to `pos` within `radius`.
+    fn closest_pin(&self, pos: Pos2, radius: f32) -> Option<AnyPinId> {
+        let inputs = self.inputs.iter().map(|(id, p)| (AnyPinId::In(*id), *p));
+        let outputs = self.outputs.iter().map(|(id, p)| (AnyPinId::Out(*id), *p));
+
+        inputs
+            .chain(outputs)
+            .map(|(pin, p)| (pin, p.distance(pos)))
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(pin, _)| pin)
+    }
+
+    /// Returns pin a wire dragged from pin `from` snaps to when the pointer is at `pos`.
+    ///
+    /// It is the pin closest to `pos` within `radius` on the other side of a wire,
+    /// i.e. an input for a wire dragged from an output and vice versa.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::{AnyPinId, InPinId, OutPinId, Snarl, ui::LayoutSnapshot};
+    /// # let mut snarl = Snarl::new();
+    /// # let [a, b, c] = [(); 3].map(|()| snarl.add_node((), pos2(0.0, 0.0)));
+    /// let out_a = OutPinId { node: a, output: 0 };
+    /// let out_b = OutPinId { node: b, output: 0 };
+    /// let in_a = InPinId { node: c, input: 0 };
+    /// let in_b = InPinId { node: c, input: 1 };
+    ///
+    /// let layout = LayoutSnapshot {
+    ///     inputs: vec![(in_a, pos2(100.0, 0.0)), (in_b, pos2(100.0, 20.0))],
+    ///     outputs: vec![(out_a, pos2(0.0, 0.0)), (out_b, pos2(96.0, 10.0))],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// // Output `out_b` is the closest pin, but it cannot be connected to `out_a`.
+    /// let from = AnyPinId::Out(out_a);
+    /// assert_eq!(layout.snap_pin(from, pos2(97.0, 12.0), 10.0), Some(AnyPinId::In(in_b)));
+    /// assert_eq!(layout.snap_pin(from, pos2(97.0, 6.0), 10.0), Some(AnyPinId::In(in_a)));
+    /// assert_eq!(layout.snap_pin(from, pos2(50.0, 0.0), 10.0), None);
+    ///
+    /// let from = AnyPinId::In(in_a);
+    /// assert_eq!(layout.snap_pin(from, pos2(97.0, 12.0), 10.0), Some(AnyPinId::Out(out_b)));
+    /// ```
+    pub fn snap_pin(&self, from: AnyPinId, pos: Pos2, radius: f32) -> Option<AnyPinId> {
+        self.snap_compatible_pin(from, pos, radius, |_, _| true)
+    }
+
+    /// Returns pin a wire dragged from pin `from` snaps to when the pointer is at `pos`,
+    /// considering only pins for which `compatible` returns true.
+    ///
+    /// Same as `LayoutSnapshot::snap_pin`, but incompatible pins are skipped
+    /// even if they are closer to `pos`.
+    /// `compatible` is called with output and input pins of the would-be wire.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::{AnyPinId, InPinId, OutPinId, Snarl, ui::LayoutSnapshot};
+    /// # let mut snarl = Snarl::new();
+    /// # let [a, b] = [(); 2].map(|()| snarl.add_node((), pos2(0.0, 0.0)));
+    /// let out = OutPinId { node: a, output: 0 };
+    /// let near = InPinId { node: b, input: 0 };
+    /// let middle = InPinId { node: b, input: 1 };
+    /// let far = InPinId { node: b, input: 2 };
+    ///
+    /// let layout = LayoutSnapshot {
+    ///     inputs: vec![
+    ///         (near, pos2(100.0, 0.0)),
+    ///         (middle, pos2(100.0, 20.0)),
+    ///         (far, pos2(100.0, 40.0)),
+    ///     ],
+    ///     outputs: vec![(out, pos2(0.0, 0.0))],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// // Pin `near` is incompatible, so the nearest compatible pin wins.
+    /// let compatible = |_: OutPinId, to: InPinId| to != near;
+    /// let from = AnyPinId::Out(out);
+    /// assert_eq!(
+    ///     layout.snap_compatible_pin(from, pos2(100.0, 2.0), 50.0, compatible),
+    ///     Some(AnyPinId::In(middle))
+    /// );
+    /// assert_eq!(
+    ///     layout.snap_compatible_pin(from, pos2(100.0, 35.0), 50.0, compatible),
+    ///     Some(AnyPinId::In(far))
+    /// );
+    ///
+    /// // Compatible pins outside of the radius are not snapped to.
+    /// assert_eq!(layout.snap_compatible_pin(from, pos2(100.0, 2.0), 10.0, compatible), None);
+    /// assert_eq!(
+    ///     layout.snap_compatible_pin(from, pos2(100.0, 2.0), 10.0, |_, _| true),
+    ///     Some(AnyPinId::In(near))
+    /// );
+    /// ```
+    pub fn snap_compatible_pin(
+        &self,
+        from: AnyPinId,
+        pos: Pos2,
+        radius: f32,
+        compatible: impl Fn(OutPinId, InPinId) -> bool,
+    ) -> Option<AnyPinId> {
+        let inputs = self.inputs.iter().map(|(id, p)| (AnyPinId::In(*id), *p));
+        let outputs = self.outputs.iter().map(|(id, p)| (AnyPinId::Out(*id), *p));
+
+        inputs
+            .chain(outputs)
+            .map(|(pin, p)| (pin, p.distance(pos)))
+            .filter(|(_, distance)| *distance <= radius)
+            .filter(|(pin, _)| match (from, *pin) {
+                (AnyPinId::Out(out_pin), AnyPinId::In(in_pin))
+                | (AnyPinId::In(in_pin), AnyPinId::Out(out_pin)) => compatible(out_pin, in_pin),
+                _ => false,
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(pin, _)| pin)
+    }
+
+    /// Returns rendered wires connected to the pin.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::pos2;
+    /// # use egui_snarl::{AnyPinId, InPinId, OutPinId, Snarl, ui::LayoutSnapshot};
+    /// # let mut snarl = Snarl::new();
+    /// # let [a, b, c] = [(); 3].map(|()| snarl.add_node((), pos2(0.0, 0.0)));
+    /// let out_a = OutPinId { node: a, output: 0 };
+    /// let out_b = OutPinId { node: b, output: 0 };
+    /// let in_a = InPinId { node: c, input: 0 };
+    /// let in_b = InPinId { node: c, input: 1 };
+    ///
+    /// let p = pos2(0.0, 0.0);
+    /// let layout = LayoutSnapshot {
+    ///     wires: vec![(out_a, in_a, p, p), (out_a, in_b, p, p), (out_b, in_b, p, p)],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     layout.pin_wires(AnyPinId::Out(out_a)).collect::<Vec<_>>(),
+    ///     [(out_a, in_a), (out_a, in_b)]
+    /// );
+    /// assert_eq!(
+    ///     layout.pin_wires(AnyPinId::In(in_b)).collect::<Vec<_>>(),
+    ///     [(out_a, in_b), (out_b, in_b)]
+    /// );
+    /// assert_eq!(layout.pin_wires(AnyPinId::Out(OutPinId { node: c, output: 0 })).count(), 0);
+    /// ```
+    pub fn pin_wires(&self, pin: AnyPinId) -> impl Iterator<Item = (OutPinId, InPinId)> + '_ {
+        self.wires
+            .iter()
+            .filter(move |(out_pin, in_pin, _, _)| match pin {
+                AnyPinId::Out(pin) => *out_pin == pin,
+                AnyPinId::In(pin) => *in_pin == pin,
+            })
+            .map(|(out_pin, in_pin, _, _)| (*out_pin, *in_pin))
+    }
+
+    /// Returns nodes which rects intersect `rect`, in draw order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{pos2, Rect};
+    /// # use egui_snarl::{Snarl, ui::LayoutSnapshot};
+    /// # let mut snarl = Snarl::new();
+    /// # let [a, b, c] = [(); 3].map(|()| snarl.add_node((), pos2(0.0, 0.0)));
+    /// let layout = LayoutSnapshot {
+    ///     nodes: vec![
+    ///         (a, Rect::from_min_max(pos2(0.0, 0.0), pos2(50.0, 50.0))),
+    ///         (b, Rect::from_min_max(pos2(100.0, 0.0), pos2(150.0, 50.0))),
+    ///         (c, Rect::from_min_max(pos2(0.0, 100.0), pos2(50.0, 150.0))),
+    ///     ],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let rect = Rect::from_min_max(pos2(40.0, 10.0), pos2(110.0, 20.0));
+    /// assert_eq!(layout.nodes_in_rect(rect).collect::<Vec<_>>(), [a, b]);
+    /// ```
+    pub fn nodes_in_rect(&self, rect: Rect) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .filter(move |(_, node_rect)| node_rect.intersects(rect))
+            .map(|(idx, _)| *idx)
+    }
+
+    /// Returns true if the wire between two pins was rendered.
+    pub fn has_wire(&self, from: OutPinId, to: InPinId) -> bool {
+        self.wires
+            .iter()
+            .any(|(out_pin, in_pin, _, _)| *out_pin == from && *in_pin == to)
+    }
+}
+
+/// Rects of the nodes and centers of their pins computed by `Snarl::layout`.
+///
+/// Positions are in screen space of the viewport passed to `Snarl::layout`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnarlLayout {
+    /// Rects of the nodes.
+    pub nodes: Vec<(NodeId, Rect)>,
+
+    /// Centers of the input pins.
+    pub inputs: Vec<(InPinId, Pos2)>,
+
+    /// Centers of the output pins.
+    pub outputs: Vec<(OutPinId, Pos2)>,
+}
+
+impl SnarlLayout {
+    /// Returns rect of the node.
+    pub fn node_rect(&self, node: NodeId) -> Option<Rect> {
+        self.nodes
+            .iter()
+            .find(|(idx, _)| *idx == node)
+            .map(|(_, rect)| *rect)
+    }
+
+    /// Returns center of the input pin.
+    pub fn input_pos(&self, pin: InPinId) -> Option<Pos2> {
+        self.inputs
+            .iter()
+            .find(|(id, _)| *id == pin)
+            .map(|(_, pos)| *pos)
+    }
+
+    /// Returns center of the output pin.
+    pub fn output_pos(&self, pin: OutPinId) -> Option<Pos2> {
+        self.outputs
+            .iter()
+            .find(|(id, _)| *id == pin)
+            .map(|(_, pos)| *pos)
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Creates empty effects for this Snarl.
+    ///
+    /// `Effects::add_node` reserves identifiers that are vacant in this Snarl.
+    pub fn effects(&self) -> Effects<T> {
+        let mut vacant = Vec::new();
+        let mut next_idx = 0;
+        for (idx, _) in self.nodes.iter() {
+            vacant.extend(next_idx..idx);
+            next_idx = idx + 1;
+        }
+
+        Effects {
+            effects: Vec::new(),
+            vacant,
+            next_idx,
+            generations: self.generations.clone(),
+        }
+    }
+
+    /// Applies effects in the order they were queued.
+    ///
+    /// `Snarl::show` applies effects produced by the viewer after the UI pass.
+    /// Effects for nodes or comments that no longer exist are skipped,
+    /// including ones removed by earlier effects in the batch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<i32>::new();
+    /// let a = snarl.add_node(1, egui::pos2(0.0, 0.0));
+    /// let b = snarl.add_node(2, egui::pos2(0.0, 0.0));
+    /// snarl.remove_node(a);
+    ///
+    /// let mut effects = snarl.effects();
+    /// let c = effects.add_node(3, egui::pos2(10.0, 20.0));
+    /// let d = effects.add_node(4, egui::pos2(30.0, 40.0));
+    /// assert!(snarl.get_node(c).is_none());
+    ///
+    /// snarl.apply_effects(effects);
+    /// assert_eq!(*snarl.get_node(c).unwrap().borrow(), 3);
+    /// assert_eq!(*snarl.get_node(d).unwrap().borrow(), 4);
+    /// assert_eq!(
+    ///     snarl.node_positions().collect::<Vec<_>>(),
+    ///     [(c, egui::pos2(10.0, 20.0)), (b, egui::pos2(0.0, 0.0)), (d, egui::pos2(30.0, 40.0))]
+    /// );
+    /// ```
+    pub fn apply_effects(&mut self, effects: Effects<T>) {
+        self.apply_effects_checked(effects, |_, _| true);
+    }
+
+    /// Applies effects, calling `before` with each effect right before it is applied.
+    /// Effects for which `before` returns false are skipped.
+    fn apply_effects_checked(
+        &mut self,
+        effects: Effects<T>,
+        mut before: impl FnMut(&Self, &Effect<T>) -> bool,
+    ) {
+        // Identifiers predicted by `Effects::add_node` that were taken,
+        // mapped to identifiers the nodes actually received.
+        let mut added: HashMap<NodeId, NodeId> = HashMap::default();
+
+        for mut effect in effects.effects {
+            if !added.is_empty() {
+                effect.map_nodes(|id| added.get(&id).copied().unwrap_or(id));
+            }
+            if let Effect::AddNode { id, .. } = &mut effect {
+                let actual = self.vacant_id(*id);
+                if actual != *id {
+                    added.insert(*id, actual);
+                    *id = actual;
+                }
+            }
+
+            if let Effect::Connect { from, to } = effect {
+                if !self.contains_node(from.node) || !self.contains_node(to.node) {
+                    continue;
+                }
+            }
+            if before(self, &effect) {
+                self.apply_effect(effect);
+            }
+        }
+    }
+
+    /// Returns `id` if its slot is vacant and it is not stale,
+    /// otherwise the identifier `Snarl::add_node` would assign.
+    fn vacant_id(&self, id: NodeId) -> NodeId {
+        if !self.nodes.contains(id.idx) && self.id_at(id.idx) == id {
+            id
+        } else {
+            self.id_at(self.nodes.vacant_key())
+        }
+    }
+
+    fn apply_effect(&mut self, effect: Effect<T>) {
+        match effect {
+            Effect::AddNode { node, pos, id } => {
+                self.insert_node_at(id, node, pos)
+                    .expect("node index is already occupied");
+                self.record(HistoryOp::RemoveNode { node: id });
+            }
+            Effect::Connect { from, to } => {
+                if self.contains_node(from.node) && self.contains_node(to.node) {
+                    self.connect(from, to);
+                }
+            }
+            Effect::Disconnect { from, to } => {
+                if self.contains_node(from.node) && self.contains_node(to.node) {
+                    self.disconnect(from, to);
+                }
+            }
+            Effect::DropOutputs { pin } => {
+                if self.contains_node(pin.node) {
+                    let dropped = self.wires.drop_outputs(pin);
+                    self.record_dropped_wires(dropped);
+                }
+            }
+            Effect::DropInputs { pin } => {
+                if self.contains_node(pin.node) {
+                    let dropped = self.wires.drop_inputs(pin);
+                    self.record_dropped_wires(dropped);
+                }
+            }
+            Effect::DropNodeWires { node } => {
+                if self.contains_node(node) {
+                    self.drop_node_wires(node);
+                }
+            }
+            Effect::RemoveNode { node } => {
+                if self.contains_node(node) {
+                    self.remove_node(node);
+                }
+            }
+            Effect::SelectNodes { nodes, add } => {
+                if !add {
+                    self.clear_selection();
+                }
+                for node in nodes {
+                    if self.contains_node(node) {
+                        self.select_node(node);
+                    }
+                }
+            }
+            Effect::SetCollapsed { node, collapsed } => {
+                if self.contains_node(node) {
+                    self.set_collapsed(node, collapsed);
+                }
+            }
+            Effect::SetNodeSize { node, size } => {
+                if self.contains_node(node) {
+                    self.set_node_size(node, size);
+                }
+            }
+            Effect::MoveComment {
+                comment,
+                delta,
+                nodes,
+            } => {
+                if self.comments.contains(comment) {
+                    self.move_comment(comment, delta, &nodes);
+                }
+            }
+            Effect::SetCommentSize { comment, size } => {
+                if self.comments.contains(comment) {
+                    self.comments[comment].size = size;
+                }
+            }
+            Effect::Closure(f) => f(self),
+        }
+    }
+
+    /// Shows the Snarl in the `ui` and applies changes made in it.
+    ///
+    /// Returns what happened in this frame, which can be ignored.
+    ///
+    /// Wires the viewer connects to pins beyond `SnarlViewer::inputs`
+    /// or `SnarlViewer::outputs` of their nodes are not created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{Id, Ui};
+    /// # use egui_snarl::{Snarl, ui::{SnarlStyle, SnarlViewer}};
+    /// fn show(snarl: &mut Snarl<()>, viewer: &mut impl SnarlViewer<()>, ui: &mut Ui) {
+    ///     snarl.show(viewer, &SnarlStyle::default(), Id::new("snarl"), ui);
+    /// }
+    /// ```
+    pub fn show<V>(
+        &mut self,
+        viewer: &mut V,
+        style: &SnarlStyle,
+        snarl_id: Id,
+        ui: &mut Ui,
+    ) -> SnarlResponse
+    where
+        V: SnarlViewer<T>,
+    {
+        self.advance_animation(ui);
+
+        if style.prune_invalid_wires {
+            self.prune_invalid_wires(viewer);
+        }
+
+        let mut effects = self.effects();
+        let mut nodes_moved = Vec::new();
+        let mut node_order_to_top = None;
+        let mut node_drag_released = None;
+        let mut layout = LayoutSnapshot::default();
+        let mut response = SnarlResponse::default();
+
+        // Transform kept in the Snarl takes precedence,
+        // so that it can be changed without access to the egui context.
+        let mut transform = self
+            .viewports
+            .get(&snarl_id)
+            .copied()
+            .or_else(|| SnarlTransform::load(ui.ctx(), snarl_id))
+            .unwrap_or_default();
+
+        let mut wire_cache = take_wire_cache(ui, snarl_id);
+
+        self._show(
+            viewer,
+            style,
+            snarl_id,
+            ui,
+            &mut effects,
+            &mut nodes_moved,
+            &mut node_order_to_top,
+            &mut node_drag_released,
+            &mut layout,
+            &mut transform,
+            &mut wire_cache,
+            &mut response.hovered,
+        );
+        transform.store(ui.ctx(), snarl_id);
+        self.viewports.insert(snarl_id, transform);
+        set_wire_cache(ui, snarl_id, wire_cache);
+
+        match &self.frozen_layout {
+            None => self.layout = layout,
+            Some(frozen) => self.layout = frozen.clone(),
+        }
+        // State before changes, to report what changed in the frame.
+        let wires_before = self.wires.iter().collect::<HashSet<_>>();
+        let positions_before = self.node_positions().collect::<Vec<_>>();
+
+        self.apply_effects_checked(effects, |snarl, effect| match *effect {
+            // Viewer may declare fewer pins than it tries to connect, e.g. after its bindings shrink.
+            Effect::Connect { from, to } => snarl.pins_exist(from, to, viewer),
+            // Pins of wires dropped with the node can only be built before it is removed.
+            Effect::RemoveNode { node } if snarl.contains_node(node) => {
+                let wires = snarl
+                    .wires
+                    .iter()
+                    .filter(|wire| wire.out_pin.node == node || wire.in_pin.node == node);
+                for wire in wires {
+                    viewer.on_disconnect(
+                        &OutPin::output(snarl, wire.out_pin),
+                        &InPin::input(snarl, wire.in_pin),
+                    );
+                }
+                true
+            }
+            _ => true,
+        });
+
+        if self.frozen_layout.is_none() {
+            // Nodes that may be moved by dragging and snapping with their positions before.
+            // Dragged selected node moves the whole selection, except nodes that are not movable.
+            let mut moved = Vec::new();
+            for node_id in nodes_moved
+                .iter()
+                .map(|(id, _)| *id)
+                .chain(node_drag_released)
+            {
+                if self.is_selected(node_id) {
+                    moved.extend_from_slice(&self.selection);
+                } else {
+                    moved.push(node_id);
+                }
+            }
+            moved.sort_unstable();
+            moved.dedup();
+            let moved = moved
+                .into_iter()
+                .filter_map(|id| {
+                    let node = self.node(id)?;
+                    viewer
+                        .movable(&node.value.borrow())
+                        .then_some((id, node.pos))
+                })
+                .collect::<Vec<_>>();
+
+            let follows = |snarl: &Self, dragged: NodeId, id: NodeId| {
+                id == dragged || (snarl.is_selected(dragged) && snarl.is_selected(id))
+            };
+
+            for (node_id, delta) in nodes_moved {
+                for &(id, _) in &moved {
+                    if follows(self, node_id, id) {
+                        self.move_node(id, delta);
+                    }
+                }
+            }
+
+            if let (Some(grid), Some(node_id)) = (style.snap_to_grid, node_drag_released) {
+                for &(id, _) in &moved {
+                    if follows(self, node_id, id) {
+                        self.snap_single_node(id, grid);
+                    }
+                }
+            }
+
+            for (node_id, old) in moved {
+                if let Some(node) = self.node(node_id) {
+                    if node.pos != old {
+                        viewer.on_node_moved(node_id, node.pos);
+                    }
+                }
+            }
+        }
+
+        if let Some(log) = &mut self.history_log {
+            log.hold = ui.input(|i| i.pointer.any_down());
+        }
+
+        if let Some(order) = node_order_to_top {
+            let node_idx = self.draw_order.remove(order);
+            self.draw_order.push(node_idx);
+        }
+
+        let changed = self.selection.len() != self.shown_selection.len()
+            || self
+                .selection
+                .iter()
+                .any(|id| !self.shown_selection.contains(id));
+        if changed {
+            self.shown_selection.clone_from(&self.selection);
+            viewer.on_selection_changed(&self.selection);
+        }
+        response.selection_changed = changed;
+
+        response.connected = self
+            .wires
+            .iter()
+            .filter(|wire| !wires_before.contains(wire))
+            .map(|wire| (wire.out_pin, wire.in_pin))
+            .collect();
+        response.connected.sort_unstable();
+        response.disconnected = wires_before
+            .into_iter()
+            .filter(|wire| !self.wires.contains(wire))
+            .map(|wire| (wire.out_pin, wire.in_pin))
+            .collect();
+        response.disconnected.sort_unstable();
+
+        for &(from, to) in &response.disconnected {
+            // Wires of removed nodes were reported before the removal.
+            if self.contains_node(from.node) && self.contains_node(to.node) {
+                viewer.on_disconnect(&OutPin::output(self, from), &InPin::input(self, to));
+            }
+        }
+        for &(from, to) in &response.connected {
+            viewer.on_connect(&OutPin::output(self, from), &InPin::input(self, to));
+        }
+
+        response.moved = positions_before
+            .into_iter()
+            .filter(|&(id, pos)| self.node_pos(id).is_some_and(|new| new != pos))
+            .map(|(id, _)| id)
+            .collect();
+
+        response
+    }
+
+    /// Computes where the Snarl would place nodes and pins in `viewport`
+    /// without showing it in any UI.
+    ///
+    /// Runs the geometry pass `Snarl::show` uses for nodes it does not show,
+    /// with graph origin at the top-left corner of the viewport and scale of 1.
+    /// Nodes get their stored size or the viewer's size hint,
+    /// pins are spread evenly along the left and right edges of the node.
+    /// Only `SnarlViewer::size_hint`, `SnarlViewer::inputs` and `SnarlViewer::outputs` are called,
+    /// so it does not need an egui context.
+    /// Useful for tests, server-side processing and custom renderers.
+    pub fn layout<V>(&self, viewer: &mut V, style: &SnarlStyle, viewport: Rect) -> SnarlLayout
+    where
+        V: SnarlViewer<T>,
+    {
+        let mut layout = SnarlLayout::default();
+        let transform = SnarlTransform::default();
+
+        for &idx in &self.draw_order {
+            let node_id = self.id_at(idx);
+            let (node_rect, full_rect) =
+                self.place_node(viewer, idx, None, &transform, viewport, None);
+            let (inputs, outputs) =
+                self.place_hidden_pins(viewer, style, idx, node_rect, full_rect, 1.0, None);
+
+            layout.nodes.push((node_id, full_rect));
+            layout
+                .inputs
+                .extend(inputs.into_iter().map(|(id, pos, _)| (id, pos)));
+            layout
+                .outputs
+                .extend(outputs.into_iter().map(|(id, pos, _)| (id, pos)));
+        }
+
+        layout
+    }
+
+    /// Returns rect the node at `idx` is placed at on screen
+    /// and rect it covers with the size it had when it was last shown.
+    ///
+    /// Frozen layout takes precedence over the node position.
+    fn place_node<V>(
+        &self,
+        viewer: &V,
+        idx: usize,
+        frozen: Option<&LayoutSnapshot>,
+        transform: &SnarlTransform,
+        viewport: Rect,
+        geometry: Option<&NodeGeometry>,
+    ) -> (Rect, Rect)
+    where
+        V: SnarlViewer<T>,
+    {
+        let node = &self.nodes[idx];
+        let node_rect = match frozen.and_then(|frozen| frozen.node_rect(self.id_at(idx))) {
+            Some(rect) => rect,
+            None => Rect::from_min_size(
+                transform.graph_pos_to_screen(node.pos, viewport),
+                node.size
+                    .unwrap_or_else(|| viewer.size_hint(&node.value.borrow()))
+                    * transform.scale,
+            ),
+        };
+
+        let full_rect = match geometry {
+            Some(geometry) => Rect::from_min_size(node_rect.min, geometry.size * transform.scale),
+            None => node_rect,
+        };
+
+        (node_rect, full_rect)
+    }
+
+    /// Places pins of the node at `idx` that is not shown.
+    ///
+    /// Pins remembered in `geometry` keep their place and color,
+    /// others are spread along the edges of `full_rect`.
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    fn place_hidden_pins<V>(
+        &self,
+        viewer: &mut V,
+        style: &SnarlStyle,
+        idx: usize,
+        node_rect: Rect,
+        full_rect: Rect,
+        scale: f32,
+        geometry: Option<&NodeGeometry>,
+    ) -> (
+        Vec<(InPinId, Pos2, Color32)>,
+        Vec<(OutPinId, Pos2, Color32)>,
+    )
+    where
+        V: SnarlViewer<T>,
+    {
+        let node = self.id_at(idx);
+        let value = &self.nodes[idx].value;
+        let default_fill = style.styled_pin(PinInfo::default()).fill;
+        let (shown_inputs, shown_outputs) = match geometry {
+            Some(geometry) => (&geometry.inputs[..], &geometry.outputs[..]),
+            None => (&[][..], &[][..]),
+        };
+
+        let inputs = place_culled_pins(
+            viewer.inputs(&value.borrow()),
+            shown_inputs,
+            node_rect.min,
+            scale,
+            full_rect.left(),
+            full_rect.y_range(),
+            default_fill,
+        )
+        .enumerate()
+        .map(|(input, (pos, fill))| (InPinId { node, input }, pos, fill))
+        .collect();
+
+        let outputs = place_culled_pins(
+            viewer.outputs(&value.borrow()),
+            shown_outputs,
+            node_rect.min,
+            scale,
+            full_rect.right(),
+            full_rect.y_range(),
+            default_fill,
+        )
+        .enumerate()
+        .map(|(output, (pos, fill))| (OutPinId { node, output }, pos, fill))
+        .collect();
+
+        (inputs, outputs)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn _show<V>(
+        &self,
+        viewer: &mut V,
+        style: &SnarlStyle,
+        snarl_id: Id,
+        ui: &mut Ui,
+        effects: &mut Effects<T>,
+        nodes_moved: &mut Vec<(NodeId, Vec2)>,
+        node_order_to_top: &mut Option<usize>,
+        node_drag_released: &mut Option<NodeId>,
+        layout: &mut LayoutSnapshot,
+        transform: &mut SnarlTransform,
+        wire_cache: &mut WireCache,
+        hovered: &mut Option<SnarlHover>,
+    ) where
+        V: SnarlViewer<T>,
+    {
+        Frame::none()
+            .fill(ui.style().visuals.widgets.inactive.bg_fill)
+            .stroke(ui.style().visuals.widgets.inactive.bg_stroke)
+            .show(ui, |ui| {
+                let max_rect = ui.max_rect();
+
+                let r = ui.allocate_rect(max_rect, Sense::click_and_drag());
+
+                // Canvas is panned by dragging with middle mouse button
+                // or with primary button while space is held.
+                let space_held = ui.input(|i| i.key_down(Key::Space));
+                if r.dragged_by(PointerButton::Middle)
+                    || (space_held && r.dragged_by(PointerButton::Primary))
+                {
+                    transform.offset += r.drag_delta();
+                }
+
+                if let Some(hover_pos) = r.hover_pos() {
+                    let (scroll_delta, zoom_delta, modifiers) =
+                        ui.input(|i| (i.scroll_delta, i.zoom_delta(), i.modifiers));
+
+                    let wheel_zooms = match style.zoom_modifier {
+                        None => style.scroll_to_zoom,
+                        Some(zoom_modifier) => modifiers.matches(zoom_modifier),
+                    };
+
+                    let mut zoom = zoom_delta;
+                    if wheel_zooms {
+                        zoom *= (scroll_delta.y * WHEEL_ZOOM_SPEED).exp();
+                    } else {
+                        transform.offset += scroll_delta;
+                    }
+
+                    if zoom != 1.0 {
+                        let new_scale =
+                            (transform.scale * zoom).clamp(style.min_scale, style.max_scale);
+                        transform.zoom_around(new_scale / transform.scale, hover_pos, max_rect);
+                    }
+                }
+
+                let scale = transform.scale;
+
+                if let Some(grid) = &style.background_grid {
+                    let spacing = grid
+                        .spacing
+                        .or(style.snap_to_grid)
+                        .unwrap_or(Vec2::splat(50.0));
+                    let stroke = grid
+                        .stroke
+                        .unwrap_or(ui.visuals().widgets.noninteractive.bg_stroke);
+                    let major_stroke = grid
+                        .major_stroke
+                        .unwrap_or(Stroke::new(stroke.width * 2.0, stroke.color));
+
+                    let (vertical, horizontal) = grid.visible_lines(spacing, transform, max_rect);
+                    let painter = ui.painter();
+                    for (x, major) in vertical {
+                        painter.vline(
+                            x,
+                            max_rect.y_range(),
+                            if major { major_stroke } else { stroke },
+                        );
+                    }
+                    for (y, major) in horizontal {
+                        painter.hline(
+                            max_rect.x_range(),
+                            y,
+                            if major { major_stroke } else { stroke },
+                        );
+                    }
+                }
+
+                viewer.draw_background(ui.painter(), transform, max_rect);
+
+                // Comments are drawn before nodes, so nodes are on top.
+                for (comment_idx, comment) in self.comments.iter() {
+                    let rect = Rect::from_min_max(
+                        transform.graph_pos_to_screen(comment.pos, max_rect),
+                        transform.graph_pos_to_screen(comment.pos + comment.size, max_rect),
+                    );
+                    if !max_rect.intersects(rect) {
+                        continue;
+                    }
+
+                    let title_rect = Rect::from_min_size(
+                        rect.min,
+                        vec2(rect.width(), ui.spacing().interact_size.y * scale),
+                    );
+                    let mut rounding = ui.visuals().window_rounding;
+                    rounding.zoom(scale);
+
+                    let painter = ui.painter().with_clip_rect(max_rect);
+                    painter.rect(
+                        rect,
+                        rounding,
+                        comment.color.gamma_multiply(0.3),
+                        Stroke::new(scale, comment.color),
+                    );
+                    painter.rect_filled(
+                        title_rect,
+                        Rounding {
+                            sw: 0.0,
+                            se: 0.0,
+                            ..rounding
+                        },
+                        comment.color,
+                    );
+                    painter.with_clip_rect(title_rect.intersect(max_rect)).text(
+                        title_rect.left_center() + vec2(ui.spacing().item_spacing.x * scale, 0.0),
+                        Align2::LEFT_CENTER,
+                        &comment.text,
+                        FontId::proportional(ui.style().text_styles[&TextStyle::Body].size * scale),
+                        ui.visuals().strong_text_color(),
+                    );
+
+                    let id = snarl_id.with(("comment", comment_idx));
+                    if !style.editable {
+                        continue;
+                    }
+
+                    let title = ui.interact(title_rect, id, Sense::drag());
+                    if title.dragged_by(PointerButton::Primary) {
+                        let mut nodes = if style.comment_drags_nodes {
+                            self.comment_nodes(comment_idx, &*viewer)
+                        } else {
+                            Vec::new()
+                        };
+                        nodes.retain(|&id| viewer.movable(&self.expect_node(id).value.borrow()));
+                        effects.move_comment(comment_idx, title.drag_delta() / scale, nodes);
+                    }
+
+                    let corner = rect.right_bottom();
+                    let handle_rect = Rect::from_min_max(
+                        corner - Vec2::splat(RESIZE_HANDLE_SIZE * scale),
+                        corner,
+                    );
+                    let handle = ui.interact(handle_rect, id.with("resize"), Sense::drag());
+                    if handle.dragged_by(PointerButton::Primary) {
+                        let size = (comment.size + handle.drag_delta() / scale)
+                            .max(style.node_min_size);
+                        effects.set_comment_size(comment_idx, size);
+                    }
+                    if handle.hovered() || handle.dragged() {
+                        ui.ctx().set_cursor_icon(CursorIcon::ResizeNwSe);
+                    }
+                    let stroke = ui.style().interact(&handle).fg_stroke;
+                    paint_resize_handle(&painter, handle_rect, stroke);
+                }
+
+                let pin_size = style.base_pin_size(ui.style()) * scale;
+
+                let wire_frame_size = style.wire_frame_size.map_or(pin_size * 5.0, |s| s * scale);
+                let wire_width = style.effective_wire_width(scale, ui.style());
+                let pin_stub_length = style.pin_stub_length * scale;
+
+                let wire_dash = style
+                    .wire_dash
+                    .as_ref()
+                    .map(|pattern| pattern.iter().map(|len| len * scale).collect::<Vec<_>>())
+                    .or_else(|| {
+                        style
+                            .wire_flow
+                            .then(|| vec![wire_width * 4.0, wire_width * 3.0])
+                    });
+
+                let wire_phase = style.wire_flow_phase(ui.input(|i| i.time)) * scale;
+                if style.wire_flow {
+                    ui.ctx().request_repaint();
+                }
+
+                let wire_style = match style.wire_style {
+                    WireStyle::AxisAligned { corner_radius } => WireStyle::AxisAligned {
+                        corner_radius: corner_radius * scale,
+                    },
+                    wire_style => wire_style,
+                };
+
+                let wire_params = WireParams {
+                    frame_size: wire_frame_size,
+                    upscale: style.upscale_wire,
+                    downscale: style.downscale_wire,
+                    style: wire_style,
+                    dash: wire_dash.as_deref(),
+                    phase: wire_phase,
+                    fan: Vec2::ZERO,
+                };
+
+                let pin_hit_size = style
+                    .pin_hit_radius
+                    .map_or(pin_size, |radius| (radius * 2.0 * scale).max(pin_size));
+
+                let required_fill = style
+                    .unconnected_required_fill
+                    .unwrap_or(ui.visuals().warn_fg_color);
+
+                // Pins that can accept the wire being dragged, re-evaluated every frame.
+                let drag_source = get_part_wire(ui, snarl_id);
+                let compatible_pins = drag_source.map(|from| self.compatible_pins(from, viewer));
+                let compatible_tint = style.compatible_pin_tint.unwrap_or(Color32::WHITE);
+                let incompatible_tint = style
+                    .incompatible_pin_tint
+                    .unwrap_or(ui.visuals().extreme_bg_color);
+                let drag_tint = |pin: AnyPinId, fill: Color32| match &compatible_pins {
+                    Some(_) if drag_source == Some(pin) => fill,
+                    Some(pins) if pins.binary_search(&pin).is_ok() => {
+                        ecolor::tint_color_towards(fill, compatible_tint)
+                    }
+                    Some(_) => ecolor::tint_color_towards(fill, incompatible_tint),
+                    None => fill,
+                };
+
+                // Pin closest to the pointer according to the last frame layout.
+                // Used to resolve enlarged pin hitboxes that overlap.
+                let closest_pin = match (style.pin_hit_radius, ui.ctx().pointer_hover_pos()) {
+                    (Some(_), Some(pointer)) => {
+                        self.layout.closest_pin(pointer, pin_hit_size * 0.5)
+                    }
+                    _ => None,
+                };
+
+                // Hovered pin and pins connected to it according to the last frame layout.
+                let mut highlighted_pins = Vec::new();
+                if let (true, Some(pin)) = (style.highlight_connections, self.layout.hovered_pin) {
+                    highlighted_pins.push(pin);
+                    highlighted_pins.extend(self.layout.pin_wires(pin).map(
+                        |(out_pin, in_pin)| match pin {
+                            AnyPinId::Out(_) => AnyPinId::In(in_pin),
+                            AnyPinId::In(_) => AnyPinId::Out(out_pin),
+                        },
+                    ));
+                }
+
+                let mut input_positions = HashMap::with_hasher(egui::ahash::RandomState::new());
+                let mut output_positions = HashMap::with_hasher(egui::ahash::RandomState::new());
+
+                let mut input_colors = HashMap::with_hasher(egui::ahash::RandomState::new());
+                let mut output_colors = HashMap::with_hasher(egui::ahash::RandomState::new());
+
+                let mut part_wire_drag_released = false;
+                let mut pin_hovered = None;
+
+                // Shift-clicking pins connects them to the selected pin.
+                // Pin is forgotten if its node was removed since it was selected.
+                let selected_pin = get_selected_pin(ui, snarl_id)
+                    .filter(|pin| self.contains_node(pin.node()));
+                let shift_held = ui.input(|i| i.modifiers.shift);
+                let mut node_hovered = None;
+                let mut node_menu_request = None;
+
+                let frozen = self.frozen_layout.as_ref();
+
+                for (order, &idx) in self.draw_order.iter().enumerate() {
+                    let node_id = self.id_at(idx);
+                    let node = &self.nodes[idx];
+
+                    // Geometry from the last frame the node was shown in.
+                    let geometry_id = ui.id().with(node_id).with("geometry");
+                    let geometry = get_node_geometry(ui, geometry_id);
+
+                    let (node_rect, full_rect) = self.place_node(
+                        viewer,
+                        idx,
+                        frozen,
+                        transform,
+                        max_rect,
+                        geometry.as_ref(),
+                    );
+
+                    // Single culling decision for the node,
+                    // made with the size it had when it was last shown.
+                    let visible = max_rect.expand(CULL_MARGIN * scale).intersects(full_rect)
+                        || viewer.always_update(&node.value.borrow());
+
+                    if !visible {
+                        // Node is off-screen, so it is not shown.
+                        // Only its pins are placed, so wires to it are still drawn.
+                        let (inputs, outputs) = self.place_hidden_pins(
+                            viewer,
+                            style,
+                            idx,
+                            node_rect,
+                            full_rect,
+                            scale,
+                            geometry.as_ref(),
+                        );
+                        for (id, pos, fill) in inputs {
+                            input_positions.insert(id, pos);
+                            input_colors.insert(id, fill);
+                        }
+                        for (id, pos, fill) in outputs {
+                            output_positions.insert(id, pos);
+                            output_colors.insert(id, fill);
+                        }
+
+                        layout.nodes.push((node_id, full_rect));
+                        continue;
+                    }
+
+                    let ui = &mut ui.child_ui_with_id_source(
+                        node_rect,
+                        Layout::top_down(Align::Center),
+                        node_id,
+                    );
+                    ui.set_clip_rect(max_rect);
+                    ui.style_mut().zoom(scale);
+
+                    let collapsible = style.node_layout == NodeLayout::Default
+                        && viewer.collapsible(&node.value.borrow());
+                    let collapsed = collapsible && node.collapsed;
+
+                    let mut default_frame = match style.node_frame {
+                        Some(mut frame) => {
+                            frame.zoom(scale);
+                            frame
+                        }
+                        None => Frame::window(ui.style()),
+                    };
+                    if let Some(mut padding) = style.node_padding {
+                        padding.zoom(scale);
+                        default_frame.inner_margin = padding;
+                    }
+                    let node_frame = viewer.node_frame(&node.value.borrow(), default_frame);
+                    let r = node_frame.show(ui, |ui| {
+                        let mut header_rect = None;
+                        let header = match style.node_layout {
+                            NodeLayout::Default => {
+                                // Interacting before the header content lets widgets
+                                // shown by the viewer and the toggle take precedence.
+                                // Header rect is known only after the content is shown,
+                                // so the rect from the previous frame is used when there is one.
+                                let header_id = ui.id().with("header");
+                                let prev_header = ui
+                                    .data(|d| d.get_temp::<Rect>(header_id))
+                                    .map(|rect| {
+                                        let rect = rect.translate(node_rect.min.to_vec2());
+                                        ui.interact(rect, header_id, Sense::click_and_drag())
+                                    });
+
+                                let mut icon_rect = None;
+                                let r = ui.vertical(|ui| {
+                                    ui.horizontal(|ui| {
+                                        if let Some(height) = style.header_height {
+                                            ui.set_min_height(height * scale);
+                                        }
+                                        if collapsible {
+                                            let size = Vec2::splat(ui.spacing().icon_width);
+                                            icon_rect = Some(ui.allocate_space(size).1);
+                                        }
+                                        viewer.show_header(node_id, &node.value, ui, effects);
+                                    });
+                                    if !collapsed {
+                                        ui.separator();
+                                    }
+                                });
+                                ui.data_mut(|d| {
+                                    d.insert_temp(
+                                        header_id,
+                                        r.response.rect.translate(-node_rect.min.to_vec2()),
+                                    )
+                                });
+                                header_rect = Some(r.response.rect);
+                                let header = prev_header.unwrap_or_else(|| {
+                                    ui.interact(r.response.rect, header_id, Sense::click_and_drag())
+                                });
+
+                                if let Some(icon_rect) = icon_rect {
+                                    let icon = ui.interact(
+                                        icon_rect,
+                                        r.response.id.with("collapse"),
+                                        Sense::click(),
+                                    );
+                                    let openness = if collapsed { 0.0 } else { 1.0 };
+                                    collapsing_header::paint_default_icon(ui, openness, &icon);
+                                    if icon.clicked() && style.editable {
+                                        effects.set_collapsed(node_id, !collapsed);
+                                    }
+                                }
+                                Some(header)
+                            }
+                            NodeLayout::Headerless => None,
+                        };
+
+                        if collapsed {
+                            // Pins of collapsed node are gathered at the header edges,
+                            // so wires stay attached.
+                            // Viewer does not show pins of collapsed node,
+                            // they keep colors from the last frame the node was shown in.
+                            let header_rect = header_rect.unwrap_or(node_rect);
+                            let y = header_rect.center().y;
+                            let default_fill = style.styled_pin(PinInfo::default()).fill;
+                            let fill = |shown: Option<&Vec<(Vec2, Color32)>>, idx: usize| {
+                                shown
+                                    .and_then(|shown| shown.get(idx))
+                                    .map_or(default_fill, |&(_, fill)| fill)
+                            };
+
+                            // Single drawn input pin is flagged if any required input is missing.
+                            let mut missing_required = false;
+
+                            let inputs_count = viewer.inputs(&node.value.borrow());
+                            let input_pos = pos2(header_rect.left() - pin_size, y);
+                            for input_idx in 0..inputs_count {
+                                let in_pin = InPin::input(
+                                    self,
+                                    InPinId {
+                                        node: node_id,
+                                        input: input_idx,
+                                    },
+                                );
+                                if in_pin.remotes.is_empty() && viewer.input_required(&in_pin) {
+                                    missing_required = true;
+                                }
+                                input_positions.insert(in_pin.id, input_pos);
+                                input_colors.insert(
+                                    in_pin.id,
+                                    fill(geometry.as_ref().map(|g| &g.inputs), input_idx),
+                                );
+                            }
+
+                            let outputs_count = viewer.outputs(&node.value.borrow());
+                            let output_pos = pos2(header_rect.right() + pin_size, y);
+                            for output_idx in 0..outputs_count {
+                                let id = OutPinId {
+                                    node: node_id,
+                                    output: output_idx,
+                                };
+                                output_positions.insert(id, output_pos);
+                                output_colors.insert(
+                                    id,
+                                    fill(geometry.as_ref().map(|g| &g.outputs), output_idx),
+                                );
+                            }
+
+                            // First pin of each side stands for the stack,
+                            // wires are dragged from it and dropped onto it.
+                            let first_input = (inputs_count > 0).then(|| {
+                                let id = InPinId {
+                                    node: node_id,
+                                    input: 0,
+                                };
+                                let fill = if missing_required {
+                                    required_fill
+                                } else {
+                                    input_colors[&id]
+                                };
+                                (AnyPinId::In(id), input_pos, fill)
+                            });
+                            let first_output = (outputs_count > 0).then(|| {
+                                let id = OutPinId {
+                                    node: node_id,
+                                    output: 0,
+                                };
+                                (AnyPinId::Out(id), output_pos, output_colors[&id])
+                            });
+
+                            for (pin, pos, fill) in first_input.into_iter().chain(first_output) {
+                                let r = ui.interact(
+                                    Rect::from_center_size(pos, Vec2::splat(pin_hit_size)),
+                                    ui.id().with(pin),
+                                    Sense::click_and_drag(),
+                                );
+                                let pin_hit = r.hovered()
+                                    && !matches!(closest_pin, Some(closest) if closest != pin);
+
+                                let mut pin_info = style.styled_pin(PinInfo::default());
+                                pin_info.fill = drag_tint(pin, fill);
+                                if highlighted_pins.contains(&pin) {
+                                    pin_info.fill = highlight_color(pin_info.fill);
+                                }
+                                let size = if pin_hit { pin_size * 1.2 } else { pin_size };
+                                draw_pin(ui.painter(), &pin_info, pos, size);
+
+                                if style.editable && pin_hit && r.drag_started_by(PointerButton::Primary) {
+                                    set_part_wire(ui, snarl_id, pin);
+                                    viewer.on_wire_drag_start(&AnyPin::new(self, pin));
+                                }
+                                if r.drag_released_by(PointerButton::Primary) {
+                                    part_wire_drag_released = true;
+                                }
+                                if pin_hit {
+                                    pin_hovered = Some(pin);
+                                }
+                            }
+
+                            return header;
+                        }
+
+                        // Resized node fills its size even if content is smaller.
+                        if node.size.is_some() {
+                            ui.set_min_size(ui.available_size());
+                        }
+
+                        let inputs_count = viewer.inputs(&node.value.borrow());
+                        let outputs_count = viewer.outputs(&node.value.borrow());
+                        let body_max_height = viewer.body_max_height(&node.value.borrow());
 
-                                        let r = ui.allocate_rect(
-                                            Rect::from_center_size(
-                                                pos2(x, y),
-                                                vec2(pin_size, pin_size),
-                                            ),
-                                            Sense::click_and_drag(),
+                        let mut body = |ui: &mut Ui| {
+                            let inputs = (0..inputs_count)
+                                .map(|idx| {
+                                    InPin::input(
+                                        self,
+                                        InPinId {
+                                            node: node_id,
+                                            input: idx,
+                                        },
+                                    )
+                                })
+                                .collect::<Vec<_>>();
+
+                            let outputs = (0..outputs_count)
+                                .map(|idx| {
+                                    OutPin::output(
+                                        self,
+                                        OutPinId {
+                                            node: node_id,
+                                            output: idx,
+                                        },
+                                    )
+                                })
+                                .collect::<Vec<_>>();
+
+                            let node_ctx = NodeContext {
+                                id: node_id,
+                                snarl_id,
+                                cx: ui.ctx().clone(),
+                            };
+                            viewer.show_content(
+                                &node_ctx,
+                                &node.value,
+                                &inputs,
+                                &outputs,
+                                ui,
+                                effects,
+                            );
+
+                            // let r = ui.interact(r.response.rect, r.response.id, Sense::drag());
+
+                            ui.horizontal(|ui| {
+                                let columns = style.pin_column_layout;
+                                ui.with_layout(Layout::top_down(columns.input_align), |ui| {
+                                    if let Some(width) = columns.input_width {
+                                        ui.set_width(width * scale);
+                                    }
+                                    if let Some(spacing) = style.row_spacing {
+                                        ui.spacing_mut().item_spacing.y = spacing * scale;
+                                    }
+                                    for input_idx in 0..inputs_count {
+                                        let in_pin = InPin::input(
+                                            self,
+                                            InPinId {
+                                                node: node_id,
+                                                input: input_idx,
+                                            },
                                         );
 
-                                        let mut pin_size = pin_size;
-                                        if r.hovered() {
-                                            pin_size *= 1.2;
-                                        }
+                                        ui.horizontal(|ui| {
+                                            ui.allocate_space(vec2(pin_size, pin_size));
+
+                                            let name = viewer.input_name(&in_pin).map(|name| ui.label(name));
+                                            let r = viewer.show_input(&in_pin, ui, effects);
+                                            let pin_info = style.styled_pin(r.inner);
+
+                                            // Pin is placed next to the first widget of the row.
+                                            let row_rect = match &name {
+                                                Some(name) => name.rect.union(r.response.rect),
+                                                None => r.response.rect,
+                                            };
+
+                                            let x = row_rect.left()
+                                                - pin_size / 2.0
+                                                - ui.style().spacing.item_spacing.x;
+
+                                            let y = (row_rect.top() + row_rect.bottom()) / 2.0;
+
+                                            // Larger pins reserve more row height.
+                                            let size = pin_size * pin_info.size;
+                                            let r = ui.allocate_rect(
+                                                Rect::from_center_size(pos2(x, y), vec2(size, size)),
+                                                Sense::hover(),
+                                            );
+                                            let r = ui.interact(
+                                                Rect::from_center_size(
+                                                    pos2(x, y),
+                                                    Vec2::splat(pin_hit_size.max(size)),
+                                                ),
+                                                r.id,
+                                                Sense::click_and_drag(),
+                                            );
+
+                                            // Overlapping hitboxes resolve to the closest pin.
+                                            // Pins of read-only Snarl are only hovered.
+                                            let pin_edit = style.editable;
+                                            let pin_hit = r.hovered()
+                                                && !matches!(closest_pin, Some(pin) if pin != AnyPinId::In(in_pin.id));
+
+                                            if pin_stub_length > 0.0 && in_pin.remotes.is_empty() {
+                                                let center = r.rect.center();
+                                                ui.painter().line_segment(
+                                                    [center, center - vec2(pin_stub_length, 0.0)],
+                                                    Stroke::new(wire_width, pin_info.fill),
+                                                );
+                                            }
+
+                                            let mut pin_size = pin_size;
+                                            if pin_hit {
+                                                pin_size *= 1.2;
+                                            }
+
+                                            let mut draw_info = pin_info.clone();
+                                            if in_pin.remotes.is_empty() && viewer.input_required(&in_pin) {
+                                                draw_info.fill = required_fill;
+                                            }
+                                            draw_info.fill = drag_tint(AnyPinId::In(in_pin.id), draw_info.fill);
+                                            if highlighted_pins.contains(&AnyPinId::In(in_pin.id)) {
+                                                draw_info.fill = highlight_color(draw_info.fill);
+                                            }
+                                            draw_pin(ui.painter(), &draw_info, r.rect.center(), pin_size);
+
+                                            if pin_edit && pin_hit && r.clicked_by(PointerButton::Secondary) {
+                                                let _ = viewer.drop_inputs(&in_pin, effects);
+                                            }
+                                            if pin_edit && pin_hit && r.clicked_by(PointerButton::Primary) {
+                                                match selected_pin {
+                                                    Some(AnyPinId::Out(out_pin)) if shift_held => {
+                                                        let result = viewer.connect(
+                                                            &OutPin::output(self, out_pin),
+                                                            &in_pin,
+                                                            effects,
+                                                        );
+                                                        if let Err(err) = result {
+                                                            set_rejected_connection(
+                                                                ui,
+                                                                snarl_id,
+                                                                AnyPinId::In(in_pin.id),
+                                                                err,
+                                                            );
+                                                        }
+                                                    }
+                                                    _ => {
+                                                        set_selected_pin(
+                                                            ui,
+                                                            snarl_id,
+                                                            AnyPinId::In(in_pin.id),
+                                                        );
+                                                    }
+                                                }
+                                            }
+                                            if selected_pin == Some(AnyPinId::In(in_pin.id)) {
+                                                ui.painter().circle_stroke(
+                                                    r.rect.center(),
+                                                    pin_size * pin_info.size * 0.75,
+                                                    ui.visuals().selection.stroke,
+                                                );
+                                            }
+                                            if pin_edit && pin_hit && r.drag_started_by(PointerButton::Primary) {
+                                                set_part_wire(ui, snarl_id, AnyPinId::In(in_pin.id));
+                                                viewer.on_wire_drag_start(&AnyPin::new(self, AnyPinId::In(in_pin.id)));
+                                            }
+                                            if r.drag_released_by(PointerButton::Primary) {
+                                                part_wire_drag_released = true;
+                                            }
+                                            if pin_hit {
+                                                pin_hovered = Some(AnyPinId::In(in_pin.id));
+                                                if let Some(text) = viewer.input_tooltip(&in_pin) {
+                                                    r.clone().on_hover_text(text);
+                                                }
+                                            }
+
+                                            input_positions.insert(in_pin.id, r.rect.center());
+                                            input_colors.insert(in_pin.id, pin_info.fill);
+                                        });
+                                    }
+                                });
+
+                                ui.with_layout(Layout::top_down(columns.output_align), |ui| {
+                                    if let Some(width) = columns.output_width {
+                                        ui.set_width(width * scale);
+                                    }
+                                    if let Some(spacing) = style.row_spacing {
+                                        ui.spacing_mut().item_spacing.y = spacing * scale;
+                                    }
+                                    for output_idx in 0..outputs_count {
+                                        let out_pin = OutPin::output(
+                                            self,
+                                            OutPinId {
+                                                node: node_id,
+                                                output: output_idx,
+                                            },
+                                        );
 
-                                        draw_pin(ui.painter(), pin_info, r.rect.center(), pin_size);
+                                        ui.horizontal(|ui| {
+                                            let r = viewer.show_output(&out_pin, ui, effects);
+                                            let pin_info = style.styled_pin(r.inner);
+                                            let name = viewer.output_name(&out_pin).map(|name| ui.label(name));
+
+                                            ui.allocate_space(vec2(pin_size, pin_size));
+
+                                            // Pin is placed next to the last widget of the row.
+                                            let row_rect = match &name {
+                                                Some(name) => name.rect.union(r.response.rect),
+                                                None => r.response.rect,
+                                            };
+
+                                            let x = row_rect.right()
+                                                + pin_size / 2.0
+                                                + ui.style().spacing.item_spacing.x;
+
+                                            let y = (row_rect.top() + row_rect.bottom()) / 2.0;
+
+                                            // Larger pins reserve more row height.
+                                            let size = pin_size * pin_info.size;
+                                            let r = ui.allocate_rect(
+                                                Rect::from_center_size(pos2(x, y), vec2(size, size)),
+                                                Sense::hover(),
+                                            );
+                                            let r = ui.interact(
+                                                Rect::from_center_size(
+                                                    pos2(x, y),
+                                                    Vec2::splat(pin_hit_size.max(size)),
+                                                ),
+                                                r.id,
+                                                Sense::click_and_drag(),
+                                            );
+
+                                            // Overlapping hitboxes resolve to the closest pin.
+                                            // Pins of read-only Snarl are only hovered.
+                                            let pin_edit = style.editable;
+                                            let pin_hit = r.hovered()
+                                                && !matches!(closest_pin, Some(pin) if pin != AnyPinId::Out(out_pin.id));
+
+                                            if pin_stub_length > 0.0 && out_pin.remotes.is_empty() {
+                                                let center = r.rect.center();
+                                                ui.painter().line_segment(
+                                                    [center, center + vec2(pin_stub_length, 0.0)],
+                                                    Stroke::new(wire_width, pin_info.fill),
+                                                );
+                                            }
+
+                                            let mut pin_size = pin_size;
+                                            if pin_hit {
+                                                pin_size *= 1.2;
+                                            }
+
+                                            let mut draw_info = pin_info.clone();
+                                            draw_info.fill = drag_tint(AnyPinId::Out(out_pin.id), draw_info.fill);
+                                            if highlighted_pins.contains(&AnyPinId::Out(out_pin.id)) {
+                                                draw_info.fill = highlight_color(draw_info.fill);
+                                            }
+                                            draw_pin(ui.painter(), &draw_info, r.rect.center(), pin_size);
+
+                                            if pin_edit && pin_hit && r.clicked_by(PointerButton::Secondary) {
+                                                let _ = viewer.drop_outputs(&out_pin, effects);
+                                            }
+                                            if pin_edit && pin_hit && r.clicked_by(PointerButton::Primary) {
+                                                match selected_pin {
+                                                    Some(AnyPinId::In(in_pin)) if shift_held => {
+                                                        let result = viewer.connect(
+                                                            &out_pin,
+                                                            &InPin::input(self, in_pin),
+                                                            effects,
+                                                        );
+                                                        if let Err(err) = result {
+                                                            set_rejected_connection(
+                                                                ui,
+                                                                snarl_id,
+                                                                AnyPinId::Out(out_pin.id),
+                                                                err,
+                                                            );
+                                                        }
+                                                    }
+                                                    _ => {
+                                                        set_selected_pin(
+                                                            ui,
+                                                            snarl_id,
+                                                            AnyPinId::Out(out_pin.id),
+                                                        );
+                                                    }
+                                                }
+                                            }
+                                            if selected_pin == Some(AnyPinId::Out(out_pin.id)) {
+                                                ui.painter().circle_stroke(
+                                                    r.rect.center(),
+                                                    pin_size * pin_info.size * 0.75,
+                                                    ui.visuals().selection.stroke,
+                                                );
+                                            }
+                                            if pin_edit && pin_hit && r.drag_started_by(PointerButton::Primary) {
+                                                set_part_wire(ui, snarl_id, AnyPinId::Out(out_pin.id));
+                                                viewer.on_wire_drag_start(&AnyPin::new(self, AnyPinId::Out(out_pin.id)));
+                                            }
+                                            if r.drag_released_by(PointerButton::Primary) {
+                                                part_wire_drag_released = true;
+                                            }
+                                            if pin_hit {
+                                                pin_hovered = Some(AnyPinId::Out(out_pin.id));
+                                                if let Some(text) = viewer.output_tooltip(&out_pin) {
+                                                    r.clone().on_hover_text(text);
+                                                }
+                                            }
+
+                                            output_positions.insert(out_pin.id, r.rect.center());
+                                            output_colors.insert(out_pin.id, pin_info.fill);
+                                        });
+                                    }
+                                });
+                            });
+                        };
+
+                        match body_max_height {
+                            Some(height) => {
+                                let body_rect = ScrollArea::vertical()
+                                    .max_height(height * scale)
+                                    .show(ui, |ui| body(ui))
+                                    .inner_rect;
+
+                                // Wires of pins scrolled out of view stick to the body edge.
+                                for input in 0..inputs_count {
+                                    let pin = InPinId { node: node_id, input };
+                                    if let Some(pos) = input_positions.get_mut(&pin) {
+                                        pos.y = pos.y.clamp(body_rect.top(), body_rect.bottom());
+                                    }
+                                }
+                                for output in 0..outputs_count {
+                                    let pin = OutPinId { node: node_id, output };
+                                    if let Some(pos) = output_positions.get_mut(&pin) {
+                                        pos.y = pos.y.clamp(body_rect.top(), body_rect.bottom());
+                                    }
+                                }
+                            }
+                            None => body(ui),
+                        }
 
-                                        if r.clicked_by(PointerButton::Secondary) {
-                                            let _ = viewer.drop_outputs(&out_pin, effects);
-                                        }
-                                        if r.drag_started_by(PointerButton::Primary) {
-                                            set_part_wire(ui, snarl_id, AnyPin::Out(out_pin.id));
-                                        }
-                                        if r.drag_released_by(PointerButton::Primary) {
-                                            part_wire_drag_released = true;
-                                        }
-                                        if r.hovered() {
-                                            pin_hovered = Some(AnyPin::Out(out_pin.id));
-                                        }
+                        header
+                    });
 
-                                        output_positions.insert(out_pin.id, r.rect.center());
-                                        output_colors.insert(out_pin.id, pin_info.fill);
-                                    });
+                    // Without header the node is dragged by its background.
+                    // Interacting after the content lets content widgets take precedence.
+                    let drag = match r.inner {
+                        Some(header) => header,
+                        None => ui.interact(
+                            r.response.rect,
+                            r.response.id.with("drag"),
+                            Sense::click_and_drag(),
+                        ),
+                    };
+                    let movable = style.editable && viewer.movable(&node.value.borrow());
+                    if movable && drag.dragged_by(PointerButton::Primary) {
+                        nodes_moved.push((node_id, drag.drag_delta() / scale));
+                    }
+                    if movable && drag.drag_released_by(PointerButton::Primary) {
+                        *node_drag_released = Some(node_id);
+                    }
+                    if style.editable && drag.clicked_by(PointerButton::Secondary) {
+                        if let Some(pos) = drag.interact_pointer_pos() {
+                            node_menu_request = Some((node_id, pos));
+                        }
+                    }
+                    if drag.double_clicked_by(PointerButton::Primary) {
+                        viewer.on_node_double_click(node_id, effects);
+                    }
+                    if drag.clicked_by(PointerButton::Primary)
+                        || drag.dragged_by(PointerButton::Primary)
+                    {
+                        *node_order_to_top = Some(order);
+                    }
+
+                    // Interacting after the drag lets the handle take precedence.
+                    if style.editable && !collapsed && viewer.resizable(&node.value.borrow()) {
+                        let corner = r.response.rect.right_bottom();
+                        let handle_rect = Rect::from_min_max(
+                            corner - Vec2::splat(RESIZE_HANDLE_SIZE * scale),
+                            corner,
+                        );
+                        let handle = ui.interact(
+                            handle_rect,
+                            r.response.id.with("resize"),
+                            Sense::drag(),
+                        );
+                        if handle.dragged_by(PointerButton::Primary) {
+                            let size = node
+                                .size
+                                .unwrap_or_else(|| viewer.size_hint(&node.value.borrow()));
+                            let size = (size + handle.drag_delta() / scale).max(style.node_min_size);
+                            effects.set_node_size(node_id, Some(size));
+                        }
+                        if handle.hovered() || handle.dragged() {
+                            ui.ctx().set_cursor_icon(CursorIcon::ResizeNwSe);
+                        }
+                        let stroke = ui.style().interact(&handle).fg_stroke;
+                        paint_resize_handle(ui.painter(), handle_rect, stroke);
+                    }
+
+                    if self.is_selected(node_id) {
+                        let stroke = match style.selection_stroke {
+                            Some(mut stroke) => {
+                                stroke.zoom(scale);
+                                stroke
+                            }
+                            None => ui.visuals().selection.stroke,
+                        };
+                        ui.painter().rect_stroke(
+                            r.response.rect,
+                            ui.visuals().window_rounding,
+                            stroke,
+                        );
+                    }
+
+                    // Drawn outside of the selection border, so both are visible.
+                    if self.is_highlighted(node_id) {
+                        let stroke = match style.search_highlight_stroke {
+                            Some(mut stroke) => {
+                                stroke.zoom(scale);
+                                stroke
+                            }
+                            None => Stroke::new(
+                                ui.visuals().selection.stroke.width,
+                                ui.visuals().warn_fg_color,
+                            ),
+                        };
+                        ui.painter().rect_stroke(
+                            r.response.rect.expand(stroke.width * 2.0),
+                            ui.visuals().window_rounding,
+                            stroke,
+                        );
+                    }
+
+                    if style.title_as_tooltip {
+                        let title = viewer.title(&node.value.borrow()).to_owned();
+                        r.response.clone().on_hover_text(title);
+                    }
+
+                    if ui.rect_contains_pointer(r.response.rect) {
+                        node_hovered = Some(node_id);
+                    }
+
+                    layout.nodes.push((node_id, r.response.rect));
+
+                    // Remembered to place pins while the node is culled.
+                    let inputs = (0..viewer.inputs(&node.value.borrow())).map(|input| InPinId {
+                        node: node_id,
+                        input,
+                    });
+                    let outputs = (0..viewer.outputs(&node.value.borrow())).map(|output| {
+                        OutPinId {
+                            node: node_id,
+                            output,
+                        }
+                    });
+                    let geometry = NodeGeometry::capture(
+                        r.response.rect.size(),
+                        node_rect.min,
+                        scale,
+                        shown_pins(inputs, &input_positions, &input_colors),
+                        shown_pins(outputs, &output_positions, &output_colors),
+                    );
+                    set_node_geometry(ui, geometry_id, geometry);
+                }
+
+                if let Some(frozen) = frozen {
+                    // Wires must attach exactly where they were when layout was frozen.
+                    input_positions.extend(frozen.inputs.iter().copied());
+                    output_positions.extend(frozen.outputs.iter().copied());
+                }
+
+                layout
+                    .inputs
+                    .extend(input_positions.iter().map(|(&id, &pos)| (id, pos)));
+                layout
+                    .outputs
+                    .extend(output_positions.iter().map(|(&id, &pos)| (id, pos)));
+                layout.inputs.sort_by_key(|(id, _)| (id.node, id.input));
+                layout.outputs.sort_by_key(|(id, _)| (id.node, id.output));
+                layout.hovered_pin = pin_hovered;
+
+                // Canvas response covers nodes too, so its interactions must be filtered.
+                let canvas_hovered = node_hovered.is_none() && pin_hovered.is_none();
+
+                if style.editable && r.clicked_by(PointerButton::Primary) && canvas_hovered {
+                    take_selected_pin(ui, snarl_id);
+                    if !shift_held {
+                        effects.select_nodes(Vec::new(), false);
+                    }
+                }
+
+                // Dragging on empty canvas selects nodes with a rectangle.
+                // Start is kept in graph space so that it stays in place while zooming.
+                if style.editable
+                    && r.drag_started_by(PointerButton::Primary)
+                    && canvas_hovered
+                    && !space_held
+                {
+                    if let Some(pos) = r.interact_pointer_pos() {
+                        set_select_rect(ui, snarl_id, transform.screen_pos_to_graph(pos, max_rect));
+                    }
+                }
+
+                if let Some(start) = get_select_rect(ui, snarl_id) {
+                    let start = transform.graph_pos_to_screen(start, max_rect);
+                    let end = r.interact_pointer_pos().or(r.hover_pos()).unwrap_or(start);
+                    let select_rect = Rect::from_two_pos(start, end);
+
+                    let fill = style
+                        .select_rect_fill
+                        .unwrap_or_else(|| ui.visuals().selection.bg_fill.gamma_multiply(0.3));
+                    let stroke = style
+                        .select_rect_stroke
+                        .unwrap_or(ui.visuals().selection.stroke);
+                    ui.painter().rect(select_rect, 0.0, fill, stroke);
+
+                    if !r.dragged_by(PointerButton::Primary) {
+                        take_select_rect(ui, snarl_id);
+                        effects.select_nodes(layout.nodes_in_rect(select_rect).collect(), shift_held);
+                    }
+                }
+
+                // Body of the node with header is covered only by the canvas.
+                if style.editable && r.clicked_by(PointerButton::Secondary) && pin_hovered.is_none() {
+                    if let (Some(node), Some(pos)) = (node_hovered, r.interact_pointer_pos()) {
+                        node_menu_request = Some((node, pos));
+                    }
+                }
+
+                let mut menu_opened = false;
+                if let Some((node, pos)) = node_menu_request {
+                    let pos = transform.screen_pos_to_graph(pos, max_rect);
+                    set_context_menu(ui, snarl_id, ContextMenu::Node(node, pos));
+                    menu_opened = true;
+                } else if style.editable && r.clicked_by(PointerButton::Secondary) && canvas_hovered {
+                    if let Some(pos) = r.interact_pointer_pos() {
+                        let pos = transform.screen_pos_to_graph(pos, max_rect);
+                        set_context_menu(ui, snarl_id, ContextMenu::Graph(pos));
+                        menu_opened = true;
+                    }
+                }
+
+                if let Some(menu) = get_context_menu(ui, snarl_id) {
+                    let pos = match menu {
+                        ContextMenu::Graph(pos)
+                        | ContextMenu::Node(_, pos)
+                        | ContextMenu::DroppedWire(_, pos) => pos,
+                    };
+                    let effects_count = effects.effects.len();
+
+                    let area = Area::new(snarl_id.with("context-menu"))
+                        .order(Order::Foreground)
+                        .fixed_pos(transform.graph_pos_to_screen(pos, max_rect))
+                        .constrain(true)
+                        .show(ui.ctx(), |ui| {
+                            Frame::menu(ui.style()).show(ui, |ui| match menu {
+                                ContextMenu::Graph(pos) => viewer.graph_menu(pos, ui, effects),
+                                ContextMenu::Node(node, _) => {
+                                    if self.contains_node(node) {
+                                        viewer.node_menu(node, ui, effects);
+                                    }
+                                }
+                                ContextMenu::DroppedWire(pin, pos) => {
+                                    if self.contains_node(pin.node()) {
+                                        let src = AnyPin::new(self, pin);
+                                        viewer.dropped_wire_menu(pos, ui, src, effects);
+                                    }
                                 }
                             });
                         });
-                    });
+
+                    let node_removed = match menu {
+                        ContextMenu::Graph(_) => false,
+                        ContextMenu::Node(node, _) => !self.contains_node(node),
+                        ContextMenu::DroppedWire(pin, _) => !self.contains_node(pin.node()),
+                    };
+
+                    if node_removed
+                        || effects.effects.len() > effects_count
+                        || (!menu_opened && area.response.clicked_elsewhere())
+                        || ui.input(|i| i.key_pressed(Key::Escape))
+                    {
+                        take_context_menu(ui, snarl_id);
+                    }
                 }
 
                 let part_wire = get_part_wire(ui, snarl_id);
                 let hover_pos = r.hover_pos();
+
+                // Pin the dragged wire is drawn to and connected to when released.
+                // Pin under the pointer is the target even if it is incompatible,
+                // otherwise the nearest compatible pin within the snap radius.
+                let snap_target = part_wire.and_then(|from| {
+                    let pos = ui.input(|i| i.pointer.latest_pos())?;
+                    layout
+                        .snap_pin(from, pos, pin_hit_size * 0.5)
+                        .or(pin_hovered)
+                        .or_else(|| {
+                            layout.snap_compatible_pin(
+                                from,
+                                pos,
+                                style.pin_snap_radius * scale,
+                                |out_pin, in_pin| {
+                                    viewer.can_connect(
+                                        &OutPin::output(self, out_pin),
+                                        &InPin::input(self, in_pin),
+                                    )
+                                },
+                            )
+                        })
+                });
                 let mut hovered_wire = None;
 
+                let wire_hit_distance = style
+                    .wire_hit_distance
+                    .map_or(wire_width * 1.5, |d| d * scale);
+
+                let mut selected_wire =
+                    get_selected_wire(ui, snarl_id).filter(|wire| self.wires.contains(wire));
+
+                // Inputs driven by each output, ordered by pin id
+                // so that fan-out offsets are stable between frames.
+                let mut fan_out: HashMap<OutPinId, Vec<InPinId>> = HashMap::default();
+                if style.fan_out_wires {
+                    for wire in self.wires.iter() {
+                        fan_out.entry(wire.out_pin).or_default().push(wire.in_pin);
+                    }
+                    for inputs in fan_out.values_mut() {
+                        inputs.sort_unstable();
+                    }
+                }
+                let wire_params_for = |wire: Wire| {
+                    let fan = match fan_out.get(&wire.out_pin) {
+                        Some(inputs) if inputs.len() > 1 => {
+                            let idx = inputs.iter().position(|&pin| pin == wire.in_pin);
+                            let idx = idx.unwrap_or(0) as f32;
+                            let spread = idx - (inputs.len() - 1) as f32 * 0.5;
+                            vec2(pin_size, spread * pin_size * 0.5)
+                        }
+                        _ => Vec2::ZERO,
+                    };
+                    WireParams { fan, ..wire_params }
+                };
+
+                // Geometry is reused from earlier frames unless the wire moved or changed shape.
+                wire_cache.retain(|wire| self.wires.contains(wire));
                 for wire in self.wires.iter() {
                     let from = output_positions[&wire.out_pin];
                     let to = input_positions[&wire.in_pin];
 
+                    wire_cache.update(wire, &wire_params_for(wire), from, to, wire_width);
+                }
+
+                for wire in self.wires.iter() {
                     if part_wire.is_none() {
                         // Do not select wire if we are dragging a new wire.
                         if let Some(hover_pos) = hover_pos {
-                            let hit = hit_wire(
-                                hover_pos,
-                                wire_frame_size,
-                                style.upscale_wire,
-                                style.downscale_wire,
-                                from,
-                                to,
-                                wire_width * 1.5,
-                            );
-
-                            if hit {
+                            if wire_cache.hit(&wire, hover_pos, wire_hit_distance) {
                                 hovered_wire = Some(wire);
                             }
                         }
                     }
                 }
 
-                if let Some(wire) = hovered_wire {
+                *hovered = match (pin_hovered, node_hovered, hovered_wire) {
+                    (Some(AnyPinId::In(pin)), _, _) => Some(SnarlHover::InPin(pin)),
+                    (Some(AnyPinId::Out(pin)), _, _) => Some(SnarlHover::OutPin(pin)),
+                    (None, Some(node), _) => Some(SnarlHover::Node(node)),
+                    (None, None, Some(wire)) => Some(SnarlHover::Wire(wire.out_pin, wire.in_pin)),
+                    (None, None, None) if ui.rect_contains_pointer(max_rect) => {
+                        Some(SnarlHover::Background)
+                    }
+                    _ => None,
+                };
+
+                if let Some(wire) = hovered_wire.filter(|_| style.editable) {
                     if r.clicked_by(PointerButton::Secondary) {
-                        let out_pin = OutPin::output(&self, wire.out_pin);
-                        let in_pin = InPin::input(&self, wire.in_pin);
+                        let out_pin = OutPin::output(self, wire.out_pin);
+                        let in_pin = InPin::input(self, wire.in_pin);
+
+                        let _ = viewer.disconnect(&out_pin, &in_pin, effects);
+                    }
+                }
+
+                // Button that disconnects selected wire is placed in the middle of the wire.
+                let disconnect_button_rect = selected_wire.map(|wire| {
+                    let midpoint = path_midpoint(wire_cache.path(&wire));
+                    Rect::from_center_size(midpoint, Vec2::splat(16.0 * scale))
+                });
+
+                // Clicking a wire selects it, clicking anything else deselects it.
+                if !style.editable {
+                    selected_wire = None;
+                } else if r.clicked_by(PointerButton::Primary) {
+                    selected_wire = hovered_wire.filter(|_| canvas_hovered);
+                } else if ui.input(|i| i.pointer.primary_clicked()) {
+                    let pointer = ui.input(|i| i.pointer.interact_pos());
+                    let on_button = match (disconnect_button_rect, pointer) {
+                        (Some(rect), Some(pointer)) => rect.contains(pointer),
+                        _ => false,
+                    };
+                    if !on_button && ui.rect_contains_pointer(max_rect) {
+                        selected_wire = None;
+                    }
+                }
+
+                let painter = ui.painter();
+                let mut wire_labels = Vec::new();
+                for wire in self.wires.iter() {
+                    let from = output_positions[&wire.out_pin];
+                    let to = input_positions[&wire.in_pin];
+
+                    let out_pin = OutPin::output(self, wire.out_pin);
+                    let in_pin = InPin::input(self, wire.in_pin);
+                    let path = wire_cache.path(&wire);
+
+                    let color = viewer.wire_color(
+                        &out_pin,
+                        &in_pin,
+                        style.wire_color.unwrap_or_else(|| {
+                            mix_colors(output_colors[&wire.out_pin], input_colors[&wire.in_pin])
+                        }),
+                    );
+
+                    let highlighted = highlighted_pins.first().is_some_and(|&pin| {
+                        pin == AnyPinId::Out(wire.out_pin) || pin == AnyPinId::In(wire.in_pin)
+                    });
+
+                    let mut draw_width = wire_width;
+                    let mut draw_color = color;
+                    if hovered_wire == Some(wire) || highlighted {
+                        draw_width *= 1.5;
+                    }
+                    if highlighted {
+                        draw_color = highlight_color(color);
+                    }
+
+                    if selected_wire == Some(wire) {
+                        let selection = ui.visuals().selection.stroke;
+                        draw_path(
+                            painter,
+                            &wire_params,
+                            path,
+                            Stroke::new(draw_width + selection.width * 2.0, selection.color),
+                        );
+                    }
+
+                    draw_path(
+                        painter,
+                        &wire_params,
+                        path,
+                        Stroke::new(draw_width, draw_color),
+                    );
+
+                    layout.wires.push((wire.out_pin, wire.in_pin, from, to));
+
+                    let label = viewer.wire_label(&out_pin, &in_pin);
+                    if let Some(label) = label {
+                        wire_labels.push((label, path_midpoint(path)));
+                    }
+                }
+
+                draw_wire_labels(ui, wire_labels, scale);
+
+                let pending_dash = style
+                    .pending_wire_dash
+                    .as_ref()
+                    .map(|pattern| pattern.iter().map(|len| len * scale).collect::<Vec<_>>());
+                let pending_params = WireParams {
+                    dash: pending_dash.as_deref().or(wire_params.dash),
+                    ..wire_params
+                };
+                // Dry run tells if releasing the wire would be rejected.
+                let forbidden = match (part_wire, snap_target) {
+                    (Some(AnyPinId::In(in_pin)), Some(AnyPinId::Out(out_pin)))
+                    | (Some(AnyPinId::Out(out_pin)), Some(AnyPinId::In(in_pin))) => !viewer
+                        .can_connect(&OutPin::output(self, out_pin), &InPin::input(self, in_pin)),
+                    _ => false,
+                };
+                let pending_stroke = |color| {
+                    let custom = match forbidden {
+                        true => style.forbidden_wire_stroke,
+                        false => style.pending_wire_stroke,
+                    };
+                    match custom {
+                        Some(stroke) => Stroke::new(stroke.width * scale, stroke.color),
+                        None if forbidden => Stroke::new(wire_width, ui.visuals().error_fg_color),
+                        None => Stroke::new(wire_width, color),
+                    }
+                };
+                let pointer = ui.input(|i| i.pointer.latest_pos().unwrap_or(Pos2::ZERO));
+
+                match part_wire {
+                    None => {}
+                    Some(AnyPinId::In(pin)) => {
+                        let from = match snap_target {
+                            Some(AnyPinId::Out(target)) => output_positions[&target],
+                            _ => pointer,
+                        };
+                        let to = input_positions[&pin];
+
+                        let color = style.wire_color.unwrap_or(input_colors[&pin]);
+
+                        draw_wire(painter, &pending_params, from, to, pending_stroke(color));
+                    }
+                    Some(AnyPinId::Out(pin)) => {
+                        let from: Pos2 = output_positions[&pin];
+                        let to = match snap_target {
+                            Some(AnyPinId::In(target)) => input_positions[&target],
+                            _ => pointer,
+                        };
+
+                        let color = style.wire_color.unwrap_or(output_colors[&pin]);
+
+                        draw_wire(painter, &pending_params, from, to, pending_stroke(color));
+                    }
+                }
+
+                if let Some(rejected) = get_rejected_connection(ui, snarl_id) {
+                    let pos = match rejected.pin {
+                        AnyPinId::In(pin) => input_positions.get(&pin),
+                        AnyPinId::Out(pin) => output_positions.get(&pin),
+                    };
+                    let time = ui.input(|i| i.time);
+                    match pos {
+                        Some(&pos) if time < rejected.until => {
+                            show_tooltip_at(
+                                ui.ctx(),
+                                snarl_id.with("rejected-connection"),
+                                Some(pos + vec2(pin_size, pin_size)),
+                                |ui| ui.label(rejected.reason.to_string()),
+                            );
+                            ui.ctx().request_repaint();
+                        }
+                        _ => take_rejected_connection(ui, snarl_id),
+                    }
+                }
+
+                if let (Some(wire), Some(rect)) = (selected_wire, disconnect_button_rect) {
+                    let button = Button::new(RichText::new("x").size(12.0 * scale)).small();
+                    if ui.put(rect, button).on_hover_text("Disconnect").clicked() {
+                        let out_pin = OutPin::output(self, wire.out_pin);
+                        let in_pin = InPin::input(self, wire.in_pin);
+                        if viewer.disconnect(&out_pin, &in_pin, effects).is_ok() {
+                            selected_wire = None;
+                        }
+                    }
+                }
+
+                if style.show_minimap {
+                    let panel = style.minimap_rect(max_rect);
+                    let viewport = Rect::from_min_max(
+                        transform.screen_pos_to_graph(max_rect.min, max_rect),
+                        transform.screen_pos_to_graph(max_rect.max, max_rect),
+                    );
+
+                    // Graph space rects of nodes rendered this frame.
+                    let node_rects = layout
+                        .nodes
+                        .iter()
+                        .map(|(_, rect)| {
+                            Rect::from_min_max(
+                                transform.screen_pos_to_graph(rect.min, max_rect),
+                                transform.screen_pos_to_graph(rect.max, max_rect),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+
+                    let bounds = node_rects.iter().fold(viewport, |acc, rect| acc.union(*rect));
+                    let minimap = MinimapTransform::new(bounds, panel);
+
+                    let minimap_r =
+                        ui.interact(panel, snarl_id.with("minimap"), Sense::click_and_drag());
+                    if minimap_r.clicked() || minimap_r.dragged() {
+                        if let Some(pos) = minimap_r.interact_pointer_pos() {
+                            // Centers the viewport on the pointed graph position.
+                            let pos = minimap.minimap_pos_to_graph(pos);
+                            transform.offset = max_rect.size() * 0.5 - pos.to_vec2() * transform.scale;
+                        }
+                    }
+
+                    let visuals = ui.visuals();
+                    let painter = ui.painter().with_clip_rect(panel);
+                    painter.rect(
+                        panel,
+                        visuals.window_rounding,
+                        visuals.extreme_bg_color.gamma_multiply(0.8),
+                        visuals.window_stroke,
+                    );
+                    for rect in &node_rects {
+                        painter.rect_filled(
+                            minimap.graph_rect_to_minimap(*rect),
+                            0.0,
+                            visuals.widgets.inactive.bg_fill,
+                        );
+                    }
+                    painter.rect_stroke(
+                        minimap.graph_rect_to_minimap(viewport),
+                        0.0,
+                        visuals.selection.stroke,
+                    );
+                }
+
+                let released_wire = part_wire_drag_released
+                    .then(|| take_part_wire(ui, snarl_id))
+                    .flatten();
+                if let Some(from) = released_wire {
+                    let result = match (from, snap_target) {
+                        (AnyPinId::In(in_pin), Some(AnyPinId::Out(out_pin)))
+                        | (AnyPinId::Out(out_pin), Some(AnyPinId::In(in_pin))) => {
+                            let result = viewer.connect(
+                                &OutPin::output(self, out_pin),
+                                &InPin::input(self, in_pin),
+                                effects,
+                            );
+                            match (result, snap_target) {
+                                (Ok(()), _) => WireDragResult::Connected {
+                                    from: out_pin,
+                                    to: in_pin,
+                                },
+                                (Err(err), Some(target)) => {
+                                    set_rejected_connection(ui, snarl_id, target, err);
+                                    WireDragResult::Cancelled
+                                }
+                                (Err(_), None) => WireDragResult::Cancelled,
+                            }
+                        }
+                        (pin, None) if node_hovered.is_none() => {
+                            // Released on empty canvas.
+                            let pos = ui.input(|i| i.pointer.latest_pos());
+                            match pos.filter(|pos| max_rect.contains(*pos)) {
+                                Some(pos) => {
+                                    let pos = transform.screen_pos_to_graph(pos, max_rect);
+                                    set_context_menu(
+                                        ui,
+                                        snarl_id,
+                                        ContextMenu::DroppedWire(pin, pos),
+                                    );
+                                    WireDragResult::DroppedOnEmpty { pos }
+                                }
+                                None => WireDragResult::Cancelled,
+                            }
+                        }
+                        _ => WireDragResult::Cancelled,
+                    };
+                    viewer.on_wire_drag_end(result);
+                }
+
+                if ui.rect_contains_pointer(max_rect) && ui.memory(|m| m.focus().is_none()) {
+                    let shortcut_ctx = ShortcutContext {
+                        hovered_node: node_hovered,
+                        pointer_pos: r
+                            .hover_pos()
+                            .map(|pos| transform.screen_pos_to_graph(pos, max_rect)),
+                    };
+
+                    let events = ui.input(|i| i.events.clone());
+                    for event in &events {
+                        if !matches!(event, Event::Key { pressed: true, .. }) {
+                            continue;
+                        }
+
+                        if style.editable && viewer.handle_shortcut(event, shortcut_ctx, effects) {
+                            continue;
+                        }
+
+                        if style.default_shortcuts
+                            && matches!(
+                                event,
+                                Event::Key {
+                                    key: Key::Delete,
+                                    ..
+                                }
+                            )
+                        {
+                            // Selected wire takes the key press, so selected nodes are kept.
+                            if let Some(wire) = selected_wire {
+                                let out_pin = OutPin::output(self, wire.out_pin);
+                                let in_pin = InPin::input(self, wire.in_pin);
+                                if viewer.disconnect(&out_pin, &in_pin, effects).is_ok() {
+                                    selected_wire = None;
+                                }
+                                continue;
+                            }
+                        }
+
+                        let delete = matches!(
+                            event,
+                            Event::Key {
+                                key: Key::Delete | Key::Backspace,
+                                ..
+                            }
+                        );
+                        if style.editable && style.enable_keyboard && delete {
+                            // Same path as removing nodes from the viewer.
+                            for &node_id in &self.selection {
+                                let node = &self.expect_node(node_id).value;
+                                if !viewer.deletable(&node.borrow()) {
+                                    continue;
+                                }
+                                let inputs = (0..viewer.inputs(&node.borrow()))
+                                    .map(|input| {
+                                        InPin::input(
+                                            self,
+                                            InPinId {
+                                                node: node_id,
+                                                input,
+                                            },
+                                        )
+                                    })
+                                    .collect::<Vec<_>>();
+                                let outputs = (0..viewer.outputs(&node.borrow()))
+                                    .map(|output| {
+                                        OutPin::output(
+                                            self,
+                                            OutPinId {
+                                                node: node_id,
+                                                output,
+                                            },
+                                        )
+                                    })
+                                    .collect::<Vec<_>>();
+                                let _ = viewer.remove_node(node_id, node, &inputs, &outputs, effects);
+                            }
+                        }
+
+                        if style.default_shortcuts {
+                            match event {
+                                Event::Key {
+                                    key: Key::Escape, ..
+                                } => {
+                                    if take_part_wire(ui, snarl_id).is_some() {
+                                        viewer.on_wire_drag_end(WireDragResult::Cancelled);
+                                    }
+                                    take_selected_pin(ui, snarl_id);
+                                    selected_wire = None;
+                                }
+                                Event::Key { key: Key::Home, .. } => {
+                                    *transform = SnarlTransform::default();
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+
+                match selected_wire {
+                    Some(wire) => set_selected_wire(ui, snarl_id, wire),
+                    None => take_selected_wire(ui, snarl_id),
+                }
+            });
+    }
+}
+
+/// Size of the node resize handle.
+const RESIZE_HANDLE_SIZE: f32 = 10.0;
+
+/// Distance between the minimap and edges of the Snarl.
+const MINIMAP_MARGIN: f32 = 8.0;
+
+/// Zoom factor applied per point of mouse wheel scroll.
+const WHEEL_ZOOM_SPEED: f32 = 0.005;
+
+/// Distance from the viewport within which nodes are still shown.
+/// Covers pins and outlines drawn outside of the node frame.
+const CULL_MARGIN: f32 = 32.0;
+
+/// Seconds the reason of a rejected connection is shown for.
+const REJECTED_CONNECTION_TIMEOUT: f64 = 2.0;
+
+#[derive(Clone)]
+struct RejectedConnection {
+    pin: AnyPinId,
+    reason: ConnectionError,
+    until: f64,
+}
+
+fn get_rejected_connection(ui: &Ui, id: Id) -> Option<RejectedConnection> {
+    ui.memory(|m| m.data.get_temp::<RejectedConnection>(id))
+}
+
+fn set_rejected_connection(ui: &Ui, id: Id, pin: AnyPinId, reason: ConnectionError) {
+    let until = ui.input(|i| i.time) + REJECTED_CONNECTION_TIMEOUT;
+    let rejected = RejectedConnection { pin, reason, until };
+    ui.memory_mut(|m| m.data.insert_temp(id, rejected));
+}
+
+fn take_rejected_connection(ui: &Ui, id: Id) {
+    ui.memory_mut(|m| m.data.remove::<RejectedConnection>(id));
+}
+
+/// Size of the shown node and offsets of its pins with their colors, in graph space.
+/// Used to place pins of the node while it is culled.
+#[derive(Clone)]
+struct NodeGeometry {
+    size: Vec2,
+    inputs: Vec<(Vec2, Color32)>,
+    outputs: Vec<(Vec2, Color32)>,
+}
+
+impl NodeGeometry {
+    /// Captures geometry of the node shown at `origin` with `size` on screen
+    /// from screen positions and colors of its pins.
+    fn capture(
+        size: Vec2,
+        origin: Pos2,
+        scale: f32,
+        inputs: impl Iterator<Item = (Pos2, Color32)>,
+        outputs: impl Iterator<Item = (Pos2, Color32)>,
+    ) -> Self {
+        let offset = |(pos, fill): (Pos2, Color32)| ((pos - origin) / scale, fill);
+        NodeGeometry {
+            size: size / scale,
+            inputs: inputs.map(offset).collect(),
+            outputs: outputs.map(offset).collect(),
+        }
+    }
+}
+
+/// Returns positions and colors of pins placed this frame,
+/// stopping at the first pin that was not placed.
+fn shown_pins<'a, K>(
+    ids: impl Iterator<Item = K> + 'a,
+    positions: &'a HashMap<K, Pos2>,
+    colors: &'a HashMap<K, Color32>,
+) -> impl Iterator<Item = (Pos2, Color32)> + 'a
+where
+    K: Hash + Eq,
+{
+    ids.map_while(|id| Some((*positions.get(&id)?, colors[&id])))
+}
+
+/// Places `count` pins of a culled node whose top left corner is at `origin` on screen.
+///
+/// Pins remembered in `shown` keep their position and color,
+/// others are spread evenly at `x` over `y_range` with `default_fill`.
+fn place_culled_pins(
+    count: usize,
+    shown: &[(Vec2, Color32)],
+    origin: Pos2,
+    scale: f32,
+    x: f32,
+    y_range: Rangef,
+    default_fill: Color32,
+) -> impl Iterator<Item = (Pos2, Color32)> + '_ {
+    (0..count).map(move |idx| match shown.get(idx) {
+        Some(&(offset, fill)) => (origin + offset * scale, fill),
+        None => {
+            let t = (idx + 1) as f32 / (count + 1) as f32;
+            (
+                pos2(x, y_range.min + (y_range.max - y_range.min) * t),
+                default_fill,
+            )
+        }
+    })
+}
+
+fn get_node_geometry(ui: &Ui, id: Id) -> Option<NodeGeometry> {
+    ui.memory(|m| m.data.get_temp::<NodeGeometry>(id))
+}
+
+fn set_node_geometry(ui: &Ui, id: Id, geometry: NodeGeometry) {
+    ui.memory_mut(|m| m.data.insert_temp(id, geometry));
+}
+
+/// Takes wire geometry cached for the Snarl shown with `id`, leaving an empty cache.
+fn take_wire_cache(ui: &Ui, id: Id) -> WireCache {
+    ui.memory_mut(|m| std::mem::take(m.data.get_temp_mut_or_default::<WireCache>(id)))
+}
+
+fn set_wire_cache(ui: &Ui, id: Id, cache: WireCache) {
+    ui.memory_mut(|m| m.data.insert_temp(id, cache));
+}
+
+#[derive(Clone, Copy)]
+struct PartWire(AnyPinId);
+
+fn get_part_wire(ui: &Ui, id: Id) -> Option<AnyPinId> {
+    ui.memory(|m| m.data.get_temp::<PartWire>(id))
+        .map(|PartWire(pin)| pin)
+}
+
+fn set_part_wire(ui: &Ui, id: Id, pin: AnyPinId) {
+    ui.memory_mut(|m| m.data.insert_temp(id, PartWire(pin)));
+}
+
+fn take_part_wire(ui: &Ui, id: Id) -> Option<AnyPinId> {
+    let part_wire = ui.memory_mut(|m| {
+        let value = m.data.get_temp::<PartWire>(id);
+        m.data.remove::<PartWire>(id);
+        value
+    });
+    part_wire.map(|PartWire(pin)| pin)
+}
+
+/// Pin selected by clicking on it.
+/// Shift-clicking pins of the opposite kind connects them to the selected pin.
+#[derive(Clone, Copy)]
+struct SelectedPin(AnyPinId);
+
+fn get_selected_pin(ui: &Ui, id: Id) -> Option<AnyPinId> {
+    ui.memory(|m| m.data.get_temp::<SelectedPin>(id))
+        .map(|SelectedPin(pin)| pin)
+}
 
-                        let _ = viewer.disconnect(&out_pin, &in_pin, effects);
-                    }
-                }
+fn set_selected_pin(ui: &Ui, id: Id, pin: AnyPinId) {
+    ui.memory_mut(|m| m.data.insert_temp(id, SelectedPin(pin)));
+}
 
-                let painter = ui.painter();
-                for wire in self.wires.iter() {
-                    let from = output_positions[&wire.out_pin];
-                    let to = input_positions[&wire.in_pin];
+fn take_selected_pin(ui: &Ui, id: Id) -> Option<AnyPinId> {
+    let selected_pin = ui.memory_mut(|m| {
+        let value = m.data.get_temp::<SelectedPin>(id);
+        m.data.remove::<SelectedPin>(id);
+        value
+    });
+    selected_pin.map(|SelectedPin(pin)| pin)
+}
 
-                    let color =
-                        mix_colors(output_colors[&wire.out_pin], input_colors[&wire.in_pin]);
+/// Open context menu with graph space position where it was opened.
+#[derive(Clone, Copy)]
+enum ContextMenu {
+    Graph(Pos2),
+    Node(NodeId, Pos2),
 
-                    let mut draw_width = wire_width;
-                    if hovered_wire == Some(wire) {
-                        draw_width *= 1.5;
-                    }
+    /// Opened by releasing a new wire dragged from the pin on empty canvas.
+    DroppedWire(AnyPinId, Pos2),
+}
 
-                    draw_wire(
-                        painter,
-                        wire_frame_size,
-                        style.upscale_wire,
-                        style.downscale_wire,
-                        from,
-                        to,
-                        Stroke::new(draw_width, color),
-                    );
-                }
+/// Wire selected by clicking on it.
+#[derive(Clone, Copy)]
+struct SelectedWire(Wire);
 
-                match part_wire {
-                    None => {}
-                    Some(AnyPin::In(pin)) => {
-                        let from = ui.input(|i| i.pointer.latest_pos().unwrap_or(Pos2::ZERO));
-                        let to = input_positions[&pin];
+fn get_selected_wire(ui: &Ui, id: Id) -> Option<Wire> {
+    ui.memory(|m| m.data.get_temp::<SelectedWire>(id))
+        .map(|SelectedWire(wire)| wire)
+}
 
-                        let color = input_colors[&pin];
+fn set_selected_wire(ui: &Ui, id: Id, wire: Wire) {
+    ui.memory_mut(|m| m.data.insert_temp(id, SelectedWire(wire)));
+}
 
-                        draw_wire(
-                            painter,
-                            wire_frame_size,
-                            style.upscale_wire,
-                            style.downscale_wire,
-                            from,
-                            to,
-                            Stroke::new(wire_width, color),
-                        );
-                    }
-                    Some(AnyPin::Out(pin)) => {
-                        let from: Pos2 = output_positions[&pin];
-                        let to = ui.input(|i| i.pointer.latest_pos().unwrap_or(Pos2::ZERO));
+fn take_selected_wire(ui: &Ui, id: Id) {
+    ui.memory_mut(|m| m.data.remove::<SelectedWire>(id));
+}
 
-                        let color = output_colors[&pin];
+fn get_context_menu(ui: &Ui, id: Id) -> Option<ContextMenu> {
+    ui.memory(|m| m.data.get_temp::<ContextMenu>(id))
+}
 
-                        draw_wire(
-                            painter,
-                            wire_frame_size,
-                            style.upscale_wire,
-                            style.downscale_wire,
-                            from,
-                            to,
-                            Stroke::new(wire_width, color),
-                        );
-                    }
-                }
+fn set_context_menu(ui: &Ui, id: Id, menu: ContextMenu) {
+    ui.memory_mut(|m| m.data.insert_temp(id, menu));
+}
 
-                if part_wire_drag_released {
-                    match (take_part_wire(ui, snarl_id), pin_hovered) {
-                        (Some(AnyPin::In(in_pin)), Some(AnyPin::Out(out_pin)))
-                        | (Some(AnyPin::Out(out_pin)), Some(AnyPin::In(in_pin))) => {
-                            let _ = viewer.connect(
-                                &OutPin::output(self, out_pin),
-                                &InPin::input(self, in_pin),
-                                effects,
-                            );
-                        }
-                        _ => {}
-                    }
-                }
-            });
-    }
+fn take_context_menu(ui: &Ui, id: Id) {
+    ui.memory_mut(|m| m.data.remove::<ContextMenu>(id));
 }
 
 #[derive(Clone, Copy)]
-struct PartWire(AnyPin);
+struct SelectRect(Pos2);
 
-fn get_part_wire(ui: &Ui, id: Id) -> Option<AnyPin> {
-    match ui.memory(|m| m.data.get_temp::<PartWire>(id)) {
-        Some(PartWire(pin)) => Some(pin),
-        None => None,
-    }
+fn get_select_rect(ui: &Ui, id: Id) -> Option<Pos2> {
+    ui.memory(|m| m.data.get_temp::<SelectRect>(id))
+        .map(|SelectRect(pos)| pos)
 }
 
-fn set_part_wire(ui: &Ui, id: Id, pin: AnyPin) {
-    ui.memory_mut(|m| m.data.insert_temp(id, PartWire(pin)));
+fn set_select_rect(ui: &Ui, id: Id, pos: Pos2) {
+    ui.memory_mut(|m| m.data.insert_temp(id, SelectRect(pos)));
 }
 
-fn take_part_wire(ui: &Ui, id: Id) -> Option<AnyPin> {
-    let part_wire = ui.memory_mut(|m| {
-        let value = m.data.get_temp::<PartWire>(id);
-        m.data.remove::<PartWire>(id);
-        value
-    });
-    match part_wire {
-        Some(PartWire(pin)) => Some(pin),
-        None => None,
-    }
+fn take_select_rect(ui: &Ui, id: Id) {
+    ui.memory_mut(|m| m.data.remove::<SelectRect>(id));
 }
 
 /// Returns 6th degree bezier curve for the wire
@@ -909,33 +4949,287 @@ fn wire_bezier(
     }
 }
 
-fn draw_wire(
-    painter: &Painter,
+/// Parameters that define wire shape.
+#[derive(Clone, Copy)]
+struct WireParams<'a> {
     frame_size: f32,
     upscale: bool,
     downscale: bool,
-    from: Pos2,
-    to: Pos2,
-    stroke: Stroke,
-) {
-    draw_bezier(
-        painter,
-        &wire_bezier(frame_size, upscale, downscale, from, to),
-        stroke,
-    );
+    style: WireStyle,
+
+    /// Alternating dash and gap lengths.
+    dash: Option<&'a [f32]>,
+
+    /// Offset of the dash pattern along the wire.
+    phase: f32,
+
+    /// Offset from the output pin to the point where the wire turns towards the input.
+    /// Spreads wires of the same output apart.
+    fan: Vec2,
 }
 
-fn hit_wire(
-    pos: Pos2,
-    frame_size: f32,
-    upscale: bool,
-    downscale: bool,
-    from: Pos2,
-    to: Pos2,
-    threshold: f32,
-) -> bool {
-    let points = wire_bezier(frame_size, upscale, downscale, from, to);
-    hit_bezier(pos, &points, threshold)
+impl WireParams<'_> {
+    fn bezier(&self, from: Pos2, to: Pos2) -> [Pos2; 6] {
+        wire_bezier(self.frame_size, self.upscale, self.downscale, from, to)
+    }
+
+    /// Returns polyline of the wire.
+    fn path(&self, from: Pos2, to: Pos2, tolerance: f32) -> Vec<Pos2> {
+        if self.fan != Vec2::ZERO {
+            let rest = WireParams {
+                fan: Vec2::ZERO,
+                ..*self
+            };
+            let mut path = vec![from];
+            path.extend(rest.path(from + self.fan, to, tolerance));
+            return path;
+        }
+
+        match self.style {
+            WireStyle::Bezier => sample_bezier_path(&self.bezier(from, to), tolerance),
+            style => style.path(from, to, self.frame_size, tolerance),
+        }
+    }
+}
+
+fn draw_wire(painter: &Painter, params: &WireParams, from: Pos2, to: Pos2, stroke: Stroke) {
+    match (params.style, params.dash) {
+        (WireStyle::Bezier, None) if params.fan == Vec2::ZERO => {
+            draw_bezier(painter, &params.bezier(from, to), stroke)
+        }
+        _ => draw_path(
+            painter,
+            params,
+            &params.path(from, to, stroke.width),
+            stroke,
+        ),
+    }
+}
+
+/// Draws already computed polyline of the wire, dashed if `params` say so.
+fn draw_path(painter: &Painter, params: &WireParams, path: &[Pos2], stroke: Stroke) {
+    match params.dash {
+        None => {
+            painter.add(Shape::line(path.to_vec(), stroke));
+        }
+        Some(pattern) => {
+            for dash in dash_path(path, pattern, params.phase) {
+                painter.add(Shape::line(dash, stroke));
+            }
+        }
+    }
+}
+
+/// Returns point in the middle of the path, measured along the path.
+fn path_midpoint(path: &[Pos2]) -> Pos2 {
+    path_point_at(path, path_length(path) * 0.5)
+}
+
+/// Returns corners of axis-aligned wire including its ends.
+fn axis_aligned_corners(from: Pos2, to: Pos2, frame_size: f32) -> Vec<Pos2> {
+    if to.x >= from.x {
+        let middle_x = (from.x + to.x) * 0.5;
+        vec![from, pos2(middle_x, from.y), pos2(middle_x, to.y), to]
+    } else {
+        // Wire goes back, so it leaves the output, turns between the pins
+        // and enters the input from the left.
+        let middle_y = (from.y + to.y) * 0.5;
+        let right = from.x + frame_size;
+        let left = to.x - frame_size;
+        vec![
+            from,
+            pos2(right, from.y),
+            pos2(right, middle_y),
+            pos2(left, middle_y),
+            pos2(left, to.y),
+            to,
+        ]
+    }
+}
+
+/// Replaces inner corners of the polyline with arcs of up to `radius`.
+/// Arcs are sampled with segments not longer than `tolerance`.
+fn round_corners(corners: &[Pos2], radius: f32, tolerance: f32) -> Vec<Pos2> {
+    if radius <= 0.0 || corners.len() < 3 {
+        return corners.to_vec();
+    }
+
+    let mut path = vec![corners[0]];
+    for w in corners.windows(3) {
+        let (prev, corner, next) = (w[0], w[1], w[2]);
+
+        // Corner may use at most half of each adjacent segment.
+        let r = radius
+            .min((corner - prev).length() * 0.5)
+            .min((next - corner).length() * 0.5);
+
+        if r <= 0.0 {
+            path.push(corner);
+            continue;
+        }
+
+        let start = corner + (prev - corner).normalized() * r;
+        let end = corner + (next - corner).normalized() * r;
+
+        // Quarter arc approximated with quadratic bezier curve.
+        let samples = ((r * std::f32::consts::FRAC_PI_2 / tolerance).ceil() as usize).max(2);
+        for i in 0..=samples {
+            let t = i as f32 / samples as f32;
+            let a = start.lerp(corner, t);
+            let b = corner.lerp(end, t);
+            path.push(a.lerp(b, t));
+        }
+    }
+    path.push(corners[corners.len() - 1]);
+    path
+}
+
+/// Returns true if `pos` is within `threshold` from the polyline.
+fn hit_path(pos: Pos2, path: &[Pos2], threshold: f32) -> bool {
+    path_distance(pos, path) <= threshold
+}
+
+/// Returns distance from `pos` to the closest point of the path.
+fn path_distance(pos: Pos2, path: &[Pos2]) -> f32 {
+    if let [point] = path {
+        return point.distance(pos);
+    }
+
+    path.windows(2)
+        .map(|w| {
+            let segment = w[1] - w[0];
+            let length_sq = segment.length_sq();
+            let t = if length_sq > 0.0 {
+                ((pos - w[0]).dot(segment) / length_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (w[0] + segment * t).distance(pos)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+fn path_length(path: &[Pos2]) -> f32 {
+    path.windows(2).map(|w| (w[1] - w[0]).length()).sum()
+}
+
+/// Returns point at `distance` along the path.
+fn path_point_at(path: &[Pos2], mut distance: f32) -> Pos2 {
+    for w in path.windows(2) {
+        let length = (w[1] - w[0]).length();
+        if distance <= length && length > 0.0 {
+            return w[0].lerp(w[1], distance / length);
+        }
+        distance -= length;
+    }
+    path.last().copied().unwrap_or(Pos2::ZERO)
+}
+
+/// Draws wire labels on pill-shaped backgrounds centered at given points.
+/// Overlapping labels are nudged apart vertically.
+fn draw_wire_labels(ui: &Ui, labels: Vec<(WidgetText, Pos2)>, scale: f32) {
+    let mut font = TextStyle::Small.resolve(ui.style());
+    font.size *= scale;
+
+    let padding = vec2(4.0, 1.0) * scale;
+    let spacing = 2.0 * scale;
+
+    let mut placed: Vec<Rect> = Vec::new();
+
+    for (label, center) in labels {
+        let galley = label.into_galley(ui, Some(false), f32::INFINITY, font.clone());
+        let mut rect = Rect::from_center_size(center, galley.size() + padding * 2.0);
+
+        // Each nudge moves the label below one overlapping label,
+        // so it takes at most as many steps as there are placed labels.
+        for _ in 0..=placed.len() {
+            match placed.iter().find(|other| other.intersects(rect)) {
+                None => break,
+                Some(other) => {
+                    rect = rect.translate(vec2(0.0, other.bottom() + spacing - rect.top()));
+                }
+            }
+        }
+
+        let painter = ui.painter();
+        painter.rect(
+            rect,
+            rect.height() * 0.5,
+            ui.visuals().extreme_bg_color,
+            ui.visuals().widgets.noninteractive.bg_stroke,
+        );
+        galley.paint_with_fallback_color(painter, rect.min + padding, ui.visuals().text_color());
+
+        placed.push(rect);
+    }
+}
+
+/// Splits path into dashes following the pattern of alternating dash and gap lengths.
+///
+/// Lengths are measured along the path, so dashes keep their length on curves.
+/// Pattern with odd number of entries alternates dashes and gaps between repetitions.
+/// Negative entries are treated as zero, zero-length dashes are skipped.
+/// `phase` shifts the pattern along the path.
+fn dash_path(path: &[Pos2], pattern: &[f32], phase: f32) -> Vec<Vec<Pos2>> {
+    let pattern: Vec<f32> = pattern.iter().map(|&len| len.max(0.0)).collect();
+    let total: f32 = pattern.iter().sum();
+    if path.len() < 2 || total <= 0.0 {
+        return vec![path.to_vec()];
+    }
+
+    // Doubled pattern always has even length.
+    let pattern: Vec<f32> = pattern.iter().chain(&pattern).copied().collect();
+    let total = total * 2.0;
+
+    // Find where the pattern starts.
+    let mut offset = phase.rem_euclid(total);
+    let mut idx = 0;
+    while offset >= pattern[idx] {
+        offset -= pattern[idx];
+        idx = (idx + 1) % pattern.len();
+    }
+    let mut remaining = pattern[idx] - offset;
+
+    let mut dashes = Vec::new();
+    let mut current = Vec::new();
+    if idx % 2 == 0 {
+        current.push(path[0]);
+    }
+
+    for segment in path.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let length = (b - a).length();
+        let mut t = 0.0;
+
+        while length - t > remaining {
+            t += remaining;
+            let p = a.lerp(b, t / length);
+
+            if idx % 2 == 0 {
+                current.push(p);
+                let dash = std::mem::take(&mut current);
+                if pattern[idx] > 0.0 {
+                    dashes.push(dash);
+                }
+            } else {
+                current.push(p);
+            }
+
+            idx = (idx + 1) % pattern.len();
+            remaining = pattern[idx];
+        }
+
+        remaining -= length - t;
+        if idx % 2 == 0 {
+            current.push(b);
+        }
+    }
+
+    if current.len() >= 2 {
+        dashes.push(current);
+    }
+
+    dashes
 }
 
 fn bezier_reference_size(points: &[Pos2; 6]) -> f32 {
@@ -953,17 +5247,19 @@ fn bezier_samples_number(points: &[Pos2; 6], threshold: f32) -> usize {
     (reference_size / threshold).ceil() as usize
 }
 
-fn draw_bezier(painter: &Painter, points: &[Pos2; 6], stroke: Stroke) {
-    assert!(points.len() > 0);
+/// Samples bezier curve into a path with segments not longer than `threshold`.
+fn sample_bezier_path(points: &[Pos2; 6], threshold: f32) -> Vec<Pos2> {
+    let samples = bezier_samples_number(points, threshold).max(2);
 
-    let samples = bezier_samples_number(points, stroke.width);
+    (0..samples)
+        .map(|i| sample_bezier(points, i as f32 / (samples - 1) as f32))
+        .collect()
+}
 
-    let mut path = Vec::new();
+fn draw_bezier(painter: &Painter, points: &[Pos2; 6], stroke: Stroke) {
+    assert!(!points.is_empty());
 
-    for i in 0..samples {
-        let t = i as f32 / (samples - 1) as f32;
-        path.push(sample_bezier(points, t));
-    }
+    let path = sample_bezier_path(points, stroke.width);
 
     painter.add(Shape::Path(epaint::PathShape {
         points: path,
@@ -1001,90 +5297,18 @@ fn sample_bezier(points: &[Pos2; 6], t: f32) -> Pos2 {
     let p0_4 = p0_3.lerp(p1_3, t);
     let p1_4 = p1_3.lerp(p2_3, t);
 
-    let p0_5 = p0_4.lerp(p1_4, t);
-
-    p0_5
-}
-
-fn split_bezier(points: &[Pos2; 6], t: f32) -> [[Pos2; 6]; 2] {
-    let [p0, p1, p2, p3, p4, p5] = *points;
-
-    let p0_0 = p0;
-    let p1_0 = p1;
-    let p2_0 = p2;
-    let p3_0 = p3;
-    let p4_0 = p4;
-    let p5_0 = p5;
-
-    let p0_1 = p0_0.lerp(p1_0, t);
-    let p1_1 = p1_0.lerp(p2_0, t);
-    let p2_1 = p2_0.lerp(p3_0, t);
-    let p3_1 = p3_0.lerp(p4_0, t);
-    let p4_1 = p4_0.lerp(p5_0, t);
-
-    let p0_2 = p0_1.lerp(p1_1, t);
-    let p1_2 = p1_1.lerp(p2_1, t);
-    let p2_2 = p2_1.lerp(p3_1, t);
-    let p3_2 = p3_1.lerp(p4_1, t);
-
-    let p0_3 = p0_2.lerp(p1_2, t);
-    let p1_3 = p1_2.lerp(p2_2, t);
-    let p2_3 = p2_2.lerp(p3_2, t);
-
-    let p0_4 = p0_3.lerp(p1_3, t);
-    let p1_4 = p1_3.lerp(p2_3, t);
-
-    let p0_5 = p0_4.lerp(p1_4, t);
-
-    [
-        [p0_0, p0_1, p0_2, p0_3, p0_4, p0_5],
-        [p0_5, p1_4, p2_3, p3_2, p4_1, p5_0],
-    ]
-}
-
-fn hit_bezier(pos: Pos2, points: &[Pos2; 6], threshold: f32) -> bool {
-    let aabb = Rect::from_points(points);
-
-    if pos.x + threshold < aabb.left() {
-        return false;
-    }
-    if pos.x - threshold > aabb.right() {
-        return false;
-    }
-    if pos.y + threshold < aabb.top() {
-        return false;
-    }
-    if pos.y - threshold > aabb.bottom() {
-        return false;
-    }
-
-    let samples = bezier_samples_number(points, threshold);
-    if samples > 16 {
-        let [points1, points2] = split_bezier(points, 0.5);
-
-        return hit_bezier(pos, &points1, threshold) || hit_bezier(pos, &points2, threshold);
-    }
-
-    for i in 0..samples {
-        let t = i as f32 / (samples - 1) as f32;
-        let p = sample_bezier(points, t);
-        if (p - pos).length() < threshold {
-            return true;
-        }
-    }
-
-    false
+    p0_4.lerp(p1_4, t)
 }
 
-fn draw_pin(painter: &Painter, pin: PinInfo, pos: Pos2, base_size: f32) {
+fn draw_pin(painter: &Painter, pin: &PinInfo, pos: Pos2, base_size: f32) {
     let size = base_size * pin.size;
-    match pin.shape {
+    match &pin.shape {
         PinShape::Cirle => {
             painter.circle(pos, size * 0.5, pin.fill, pin.stroke);
         }
         PinShape::Triangle => {
-            const A: Vec2 = vec2(-0.64951905283832895, 0.4875);
-            const B: Vec2 = vec2(0.64951905283832895, 0.4875);
+            const A: Vec2 = vec2(-0.649_519, 0.4875);
+            const B: Vec2 = vec2(0.649_519, 0.4875);
             const C: Vec2 = vec2(0.0, -0.6375);
 
             let points = vec![pos + A * size, pos + B * size, pos + C * size];
@@ -1111,10 +5335,32 @@ fn draw_pin(painter: &Painter, pin: PinInfo, pos: Pos2, base_size: f32) {
                 stroke: pin.stroke,
             }));
         }
+        PinShape::Custom(shape) => {
+            shape.draw(
+                painter,
+                Rect::from_center_size(pos, vec2(size, size)),
+                pin.fill,
+            );
+        }
     }
 }
 
-fn mix_colors(a: Color32, b: Color32) -> Color32 {
+/// Mixes two colors in equal proportions.
+///
+/// # Examples
+///
+/// ```
+/// # use egui::Color32;
+/// # use egui_snarl::ui::mix_colors;
+/// assert_eq!(
+///     mix_colors(
+///         Color32::from_rgba_premultiplied(200, 0, 100, 200),
+///         Color32::from_rgba_premultiplied(0, 100, 50, 100),
+///     ),
+///     Color32::from_rgba_premultiplied(100, 50, 75, 150)
+/// );
+/// ```
+pub fn mix_colors(a: Color32, b: Color32) -> Color32 {
     let [or, og, ob, oa] = a.to_array();
     let [ir, ig, ib, ia] = b.to_array();
 
@@ -1125,3 +5371,206 @@ fn mix_colors(a: Color32, b: Color32) -> Color32 {
         oa / 2 + ia / 2,
     )
 }
+
+/// Paints diagonal lines of the resize handle in the corner of `rect`.
+fn paint_resize_handle(painter: &Painter, rect: Rect, stroke: Stroke) {
+    let corner = rect.right_bottom();
+    let size = rect.width();
+    for offset in [size, size * 0.5] {
+        painter.line_segment(
+            [corner - vec2(offset, 0.0), corner - vec2(0.0, offset)],
+            stroke,
+        );
+    }
+}
+
+/// Brightens color of highlighted wires and pins.
+fn highlight_color(color: Color32) -> Color32 {
+    ecolor::tint_color_towards(color, Color32::WHITE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// S-shaped wire sampled the way `draw_path` receives it.
+    fn curve() -> Vec<Pos2> {
+        let points = [
+            pos2(0.0, 0.0),
+            pos2(60.0, 0.0),
+            pos2(120.0, 0.0),
+            pos2(180.0, 150.0),
+            pos2(240.0, 150.0),
+            pos2(300.0, 150.0),
+        ];
+        sample_bezier_path(&points, 1.0)
+    }
+
+    /// Asserts that all dashes but the last, which may be cut by the path end,
+    /// follow `lengths` along the path.
+    fn assert_dash_lengths(dashes: &[Vec<Pos2>], lengths: &[f32]) {
+        assert!(dashes.len() > lengths.len());
+        for (dash, &expected) in dashes[..dashes.len() - 1]
+            .iter()
+            .zip(lengths.iter().cycle())
+        {
+            let length = path_length(dash);
+            assert!(
+                (length - expected).abs() < 1e-2,
+                "dash is {length} long, expected {expected}"
+            );
+        }
+    }
+
+    fn assert_same_dashes(a: &[Vec<Pos2>], b: &[Vec<Pos2>]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert_eq!(a.len(), b.len());
+            for (a, b) in a.iter().zip(b) {
+                assert!((*a - *b).length() < 1e-2, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn dashes_keep_pattern_length_along_curve() {
+        let dashes = dash_path(&curve(), &[10.0, 5.0], 0.0);
+        assert_dash_lengths(&dashes, &[10.0]);
+    }
+
+    #[test]
+    fn odd_pattern_alternates_dashes_and_gaps() {
+        let dashes = dash_path(&curve(), &[10.0, 5.0, 3.0], 0.0);
+        assert_dash_lengths(&dashes, &[10.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn phase_wraps_around_pattern() {
+        let path = curve();
+        let pattern = [10.0, 5.0, 3.0];
+        let period = 36.0;
+
+        let dashes = dash_path(&path, &pattern, 4.0);
+        assert_same_dashes(&dashes, &dash_path(&path, &pattern, 4.0 + period));
+        assert_same_dashes(&dashes, &dash_path(&path, &pattern, 4.0 - 2.0 * period));
+
+        // Phase inside the first dash shortens it.
+        assert!((path_length(&dashes[0]) - 6.0).abs() < 1e-2);
+        assert_dash_lengths(&dashes[1..], &[3.0, 5.0, 10.0]);
+    }
+
+    #[test]
+    fn zero_length_entries() {
+        let path = curve();
+
+        // Zero gap joins dashes end to end.
+        let dashes = dash_path(&path, &[10.0, 0.0, 5.0, 5.0], 0.0);
+        assert_dash_lengths(&dashes, &[10.0, 5.0]);
+        assert_eq!(dashes[0].last(), dashes[1].first());
+
+        // Zero dash is skipped instead of producing a degenerate line.
+        let dashes = dash_path(&path, &[0.0, 5.0, 10.0, 5.0], 0.0);
+        assert_dash_lengths(&dashes, &[10.0]);
+    }
+
+    #[test]
+    fn negative_entries_count_as_zero() {
+        let path = curve();
+        assert_same_dashes(
+            &dash_path(&path, &[10.0, -5.0, 5.0, 5.0], 0.0),
+            &dash_path(&path, &[10.0, 0.0, 5.0, 5.0], 0.0),
+        );
+
+        // Pattern without positive entries leaves the path solid.
+        assert_same_dashes(
+            &dash_path(&path, &[-1.0, 0.0], 0.0),
+            std::slice::from_ref(&path),
+        );
+    }
+
+    /// One input and one output per node.
+    struct ChainViewer;
+
+    impl SnarlViewer<()> for ChainViewer {
+        fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<()>> {
+            unimplemented!()
+        }
+
+        fn size_hint(&self, _: &()) -> Vec2 {
+            vec2(50.0, 50.0)
+        }
+
+        fn title<'a>(&'a mut self, _: &'a ()) -> &'a str {
+            "Node"
+        }
+
+        fn inputs(&mut self, _: &()) -> usize {
+            1
+        }
+
+        fn outputs(&mut self, _: &()) -> usize {
+            1
+        }
+
+        fn show_input(
+            &mut self,
+            _: &InPin<()>,
+            ui: &mut Ui,
+            _: &mut Effects<()>,
+        ) -> InnerResponse<PinInfo> {
+            InnerResponse::new(PinInfo::default(), ui.label("in"))
+        }
+
+        fn show_output(
+            &mut self,
+            _: &OutPin<()>,
+            ui: &mut Ui,
+            _: &mut Effects<()>,
+        ) -> InnerResponse<PinInfo> {
+            InnerResponse::new(PinInfo::default(), ui.label("out"))
+        }
+    }
+
+    /// Shows the Snarl for one frame and returns number of wire paths recomputed in it.
+    fn rebuilt_wires(ctx: &Context, snarl: &mut Snarl<()>) -> usize {
+        let id = Id::new("snarl");
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0))),
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            CentralPanel::default().show(ctx, |ui| {
+                snarl.show(&mut ChainViewer, &SnarlStyle::default(), id, ui);
+            });
+        });
+        ctx.data_mut(|d| std::mem::take(&mut d.get_temp_mut_or_default::<WireCache>(id).rebuilt))
+    }
+
+    #[test]
+    fn only_moved_wires_are_rebuilt() {
+        let ctx = Context::default();
+
+        // Chain of nodes a -> b -> c -> d.
+        let mut snarl = Snarl::new();
+        let [a, b, c, d] = [0.0, 200.0, 400.0, 600.0].map(|x| snarl.add_node((), pos2(x, 100.0)));
+        for (from, to) in [(a, b), (b, c), (c, d)] {
+            snarl.connect(
+                OutPinId {
+                    node: from,
+                    output: 0,
+                },
+                InPinId { node: to, input: 0 },
+            );
+        }
+
+        // First frames compute all wires, until the layout settles.
+        assert_eq!(rebuilt_wires(&ctx, &mut snarl), 3);
+        rebuilt_wires(&ctx, &mut snarl);
+        assert_eq!(rebuilt_wires(&ctx, &mut snarl), 0);
+
+        // Moving a node invalidates only wires touching it.
+        snarl.set_node_pos(b, pos2(200.0, 150.0));
+        assert_eq!(rebuilt_wires(&ctx, &mut snarl), 2);
+        assert_eq!(rebuilt_wires(&ctx, &mut snarl), 0);
+    }
+}
@@ -0,0 +1,117 @@
+use egui::{Pos2, Ui};
+
+use crate::{NodeId, Snarl};
+
+/// Easing function for node animations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+
+    /// Starts slow and accelerates.
+    EaseIn,
+
+    /// Starts fast and decelerates.
+    EaseOut,
+
+    /// Starts slow, accelerates and decelerates at the end.
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps linear progress in `[0, 1]` to eased progress.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Animation of nodes moving to target positions.
+#[derive(Clone, Debug)]
+pub(crate) struct NodesAnimation {
+    /// Node identifier, start position and target position.
+    nodes: Vec<(NodeId, Pos2, Pos2)>,
+
+    /// Duration in seconds.
+    duration: f32,
+
+    easing: Easing,
+
+    /// Time when animation started.
+    /// Set on the first frame the animation is shown.
+    start: Option<f64>,
+}
+
+impl<T> Snarl<T> {
+    /// Smoothly moves nodes to target positions over `duration` seconds.
+    ///
+    /// Animation is advanced by `Snarl::show`, which requests repaints until it is finished.
+    /// Targets for nodes that do not exist are ignored.
+    /// Starting new animation replaces the previous one, starting from current positions.
+    pub fn animate_to_layout(
+        &mut self,
+        targets: impl IntoIterator<Item = (NodeId, Pos2)>,
+        duration: f32,
+        easing: Easing,
+    ) {
+        let nodes = targets
+            .into_iter()
+            .filter_map(|(id, target)| {
+                let node = self.node(id)?;
+                Some((id, node.pos, target))
+            })
+            .collect();
+
+        self.animation = Some(NodesAnimation {
+            nodes,
+            duration,
+            easing,
+            start: None,
+        });
+    }
+
+    /// Returns true if nodes are being animated.
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    /// Advances node animation.
+    pub(crate) fn advance_animation(&mut self, ui: &Ui) {
+        let Some(mut animation) = self.animation.take() else {
+            return;
+        };
+
+        let now = ui.input(|i| i.time);
+        let start = *animation.start.get_or_insert(now);
+
+        let t = if animation.duration > 0.0 {
+            ((now - start) as f32 / animation.duration).min(1.0)
+        } else {
+            1.0
+        };
+        let eased = animation.easing.apply(t);
+
+        for &(id, from, to) in &animation.nodes {
+            if let Some(node) = self.node_mut(id) {
+                node.pos = from.lerp(to, eased);
+            }
+        }
+
+        if t < 1.0 {
+            ui.ctx().request_repaint();
+            self.animation = Some(animation);
+        }
+    }
+}
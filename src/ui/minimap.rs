@@ -0,0 +1,76 @@
+use egui::{Pos2, Rect, Vec2};
+
+/// Maps graph space to the minimap panel and back.
+///
+/// Graph `bounds` are scaled uniformly to fit into the panel and centered in it.
+///
+/// # Examples
+///
+/// ```
+/// # use egui::{pos2, Rect};
+/// # use egui_snarl::ui::MinimapTransform;
+/// let bounds = Rect::from_min_max(pos2(-100.0, 0.0), pos2(300.0, 100.0));
+/// let panel = Rect::from_min_max(pos2(600.0, 400.0), pos2(800.0, 500.0));
+/// let minimap = MinimapTransform::new(bounds, panel);
+///
+/// // Bounds are twice as wide as the panel, so the scale is one half
+/// // and the bounds are centered vertically.
+/// assert_eq!(minimap.scale(), 0.5);
+/// assert_eq!(minimap.graph_pos_to_minimap(pos2(-100.0, 0.0)), pos2(600.0, 425.0));
+/// assert_eq!(minimap.graph_pos_to_minimap(pos2(300.0, 100.0)), pos2(800.0, 475.0));
+/// assert_eq!(minimap.graph_pos_to_minimap(pos2(100.0, 50.0)), panel.center());
+///
+/// assert_eq!(minimap.minimap_pos_to_graph(pos2(650.0, 450.0)), pos2(0.0, 50.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapTransform {
+    /// Offset of the graph origin in screen space.
+    offset: Vec2,
+
+    /// Size of a graph space point in the minimap.
+    scale: f32,
+}
+
+impl MinimapTransform {
+    /// Creates transform that fits graph `bounds` into minimap `panel`.
+    pub fn new(bounds: Rect, panel: Rect) -> Self {
+        let mut scale = f32::INFINITY;
+        if bounds.width() > 0.0 {
+            scale = scale.min(panel.width() / bounds.width());
+        }
+        if bounds.height() > 0.0 {
+            scale = scale.min(panel.height() / bounds.height());
+        }
+        if !scale.is_finite() {
+            scale = 1.0;
+        }
+
+        MinimapTransform {
+            offset: panel.center().to_vec2() - bounds.center().to_vec2() * scale,
+            scale,
+        }
+    }
+
+    /// Returns size of a graph space point in the minimap.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Converts graph space position to the minimap position in screen space.
+    pub fn graph_pos_to_minimap(&self, pos: Pos2) -> Pos2 {
+        pos * self.scale + self.offset
+    }
+
+    /// Converts graph space rect to the minimap rect in screen space.
+    pub fn graph_rect_to_minimap(&self, rect: Rect) -> Rect {
+        Rect::from_min_max(
+            self.graph_pos_to_minimap(rect.min),
+            self.graph_pos_to_minimap(rect.max),
+        )
+    }
+
+    /// Converts minimap position in screen space to graph space.
+    pub fn minimap_pos_to_graph(&self, pos: Pos2) -> Pos2 {
+        ((pos.to_vec2() - self.offset) / self.scale).to_pos2()
+    }
+}
@@ -0,0 +1,255 @@
+use egui::{util::id_type_map::SerializableAny, Context, Id, Pos2, Rect, Vec2};
+
+use crate::{NodeId, Snarl};
+
+/// Pan and zoom of the Snarl viewport.
+///
+/// Maps graph space to screen space relative to the viewport's top-left corner.
+///
+/// # Examples
+///
+/// ```
+/// # use egui::{pos2, vec2, Rect};
+/// # use egui_snarl::ui::SnarlTransform;
+/// let viewport = Rect::from_min_size(pos2(100.0, 100.0), vec2(800.0, 600.0));
+/// let node = pos2(10.0, 20.0);
+///
+/// let mut transform = SnarlTransform::default();
+/// assert_eq!(transform.graph_pos_to_screen(node, viewport), pos2(110.0, 120.0));
+///
+/// // Panning shifts nodes on screen by the same amount.
+/// transform.offset += vec2(30.0, -40.0);
+/// assert_eq!(transform.graph_pos_to_screen(node, viewport), pos2(140.0, 80.0));
+///
+/// // Zooming keeps the pivot in place and scales distances from it.
+/// let pivot = pos2(140.0, 80.0);
+/// transform.zoom_around(2.0, pivot, viewport);
+/// assert_eq!(transform.graph_pos_to_screen(node, viewport), pivot);
+/// assert_eq!(
+///     transform.graph_pos_to_screen(node + vec2(5.0, 5.0), viewport),
+///     pivot + vec2(10.0, 10.0)
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnarlTransform {
+    /// Offset of the graph origin from the viewport's top-left corner.
+    pub offset: Vec2,
+
+    /// Scale of the graph.
+    pub scale: f32,
+}
+
+impl Default for SnarlTransform {
+    fn default() -> Self {
+        SnarlTransform {
+            offset: Vec2::ZERO,
+            scale: 1.0,
+        }
+    }
+}
+
+impl SnarlTransform {
+    /// Loads transform from egui memory.
+    /// With `serde` feature it survives app restarts when egui persistence is enabled.
+    #[cfg(feature = "serde")]
+    pub(crate) fn load(cx: &Context, id: Id) -> Option<Self> {
+        cx.data_mut(|d| d.get_persisted::<SnarlTransform>(id))
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub(crate) fn load(cx: &Context, id: Id) -> Option<Self> {
+        cx.data(|d| d.get_temp::<SnarlTransform>(id))
+    }
+
+    /// Stores transform in egui memory.
+    #[cfg(feature = "serde")]
+    pub(crate) fn store(self, cx: &Context, id: Id) {
+        cx.data_mut(|d| d.insert_persisted(id, self));
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub(crate) fn store(self, cx: &Context, id: Id) {
+        cx.data_mut(|d| d.insert_temp(id, self));
+    }
+
+    /// Converts graph space position to screen space.
+    pub fn graph_pos_to_screen(&self, pos: Pos2, viewport: Rect) -> Pos2 {
+        viewport.min + self.offset + pos.to_vec2() * self.scale
+    }
+
+    /// Converts screen space position to graph space.
+    pub fn screen_pos_to_graph(&self, pos: Pos2, viewport: Rect) -> Pos2 {
+        ((pos - viewport.min - self.offset) / self.scale).to_pos2()
+    }
+
+    /// Scales the graph by `factor` keeping `pivot` (in screen space) in place.
+    pub fn zoom_around(&mut self, factor: f32, pivot: Pos2, viewport: Rect) {
+        let graph_pivot = self.screen_pos_to_graph(pivot, viewport);
+        self.scale *= factor;
+        self.offset = pivot - viewport.min - graph_pivot.to_vec2() * self.scale;
+    }
+}
+
+/// Margin around nodes framed by `Snarl::fit_to_view`, in screen space.
+const FIT_MARGIN: f32 = 16.0;
+
+impl<T> Snarl<T> {
+    /// Pans and zooms the viewport with given id so that all nodes are visible.
+    ///
+    /// Resets pan and zoom if there are no nodes.
+    /// See `Snarl::fit_to_nodes` for details.
+    pub fn fit_to_view(&mut self, id: Id, viewport: Rect) {
+        let nodes = self.node_ids().collect::<Vec<_>>();
+        self.fit_to_nodes(id, &nodes, viewport);
+    }
+
+    /// Pans and zooms the viewport with given id so that given nodes are visible.
+    ///
+    /// Node sizes are taken from the last frame the Snarl was shown.
+    /// Graph is never zoomed in beyond scale 1.0.
+    /// Resets pan and zoom if none of the nodes exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{pos2, vec2, Id, Rect};
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// snarl.add_node((), pos2(-500.0, 0.0));
+    /// snarl.add_node((), pos2(1500.0, 300.0));
+    /// snarl.add_node((), pos2(200.0, -700.0));
+    ///
+    /// let id = Id::new("snarl");
+    /// let viewport = Rect::from_min_size(pos2(0.0, 0.0), vec2(800.0, 600.0));
+    /// snarl.fit_to_view(id, viewport);
+    ///
+    /// let transform = snarl.transform(id).unwrap();
+    /// for (_, pos) in snarl.node_positions() {
+    ///     assert!(viewport.contains(transform.graph_pos_to_screen(pos, viewport)));
+    /// }
+    /// ```
+    pub fn fit_to_nodes(&mut self, id: Id, nodes: &[NodeId], viewport: Rect) {
+        let old_scale = self.viewports.get(&id).map_or(1.0, |t| t.scale);
+
+        let mut bounds = Rect::NOTHING;
+        for &node_id in nodes {
+            let Some(node) = self.node(node_id) else {
+                continue;
+            };
+            let size = self
+                .layout
+                .node_rect(node_id)
+                .map_or(Vec2::ZERO, |rect| rect.size() / old_scale);
+            bounds = bounds.union(Rect::from_min_size(node.pos, size));
+        }
+
+        let transform = if bounds.is_finite() {
+            let available = viewport.size() - Vec2::splat(FIT_MARGIN * 2.0);
+            let mut scale = 1.0f32;
+            if bounds.width() > 0.0 {
+                scale = scale.min(available.x / bounds.width());
+            }
+            if bounds.height() > 0.0 {
+                scale = scale.min(available.y / bounds.height());
+            }
+            let scale = scale.max(f32::EPSILON);
+
+            SnarlTransform {
+                offset: viewport.size() * 0.5 - bounds.center().to_vec2() * scale,
+                scale,
+            }
+        } else {
+            SnarlTransform::default()
+        };
+
+        self.viewports.insert(id, transform);
+    }
+
+    /// Pans the viewport with given id so that the node is in its center.
+    ///
+    /// Zoom is kept. Node size is taken from the last frame the Snarl was shown.
+    /// Does nothing if the node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::{pos2, vec2, Id, Rect};
+    /// # use egui_snarl::{ui::SnarlTransform, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let node = snarl.add_node((), pos2(1500.0, -300.0));
+    ///
+    /// let id = Id::new("snarl");
+    /// let viewport = Rect::from_min_size(pos2(100.0, 50.0), vec2(800.0, 600.0));
+    /// snarl.set_transform(id, SnarlTransform { offset: vec2(20.0, 30.0), scale: 2.0 });
+    /// snarl.center_on_node(id, node, viewport);
+    ///
+    /// let transform = snarl.transform(id).unwrap();
+    /// assert_eq!(transform.scale, 2.0);
+    /// assert_eq!(transform.graph_pos_to_screen(pos2(1500.0, -300.0), viewport), viewport.center());
+    /// ```
+    pub fn center_on_node(&mut self, id: Id, node: NodeId, viewport: Rect) {
+        let Some(pos) = self.node_pos(node) else {
+            return;
+        };
+
+        let mut transform = self.viewports.get(&id).copied().unwrap_or_default();
+        let size = self
+            .layout
+            .node_rect(node)
+            .map_or(Vec2::ZERO, |rect| rect.size() / transform.scale);
+        let center = pos + size * 0.5;
+
+        transform.offset = viewport.size() * 0.5 - center.to_vec2() * transform.scale;
+        self.viewports.insert(id, transform);
+    }
+}
+
+/// Returns id under which UI state of the node is stored in egui memory.
+fn node_state_id(snarl_id: Id, node: NodeId) -> Id {
+    snarl_id.with(("node-state", node))
+}
+
+/// Returns a copy of the UI state of the node.
+///
+/// `snarl_id` is the id passed to `Snarl::show`.
+pub fn node_state<S>(cx: &Context, snarl_id: Id, node: NodeId) -> Option<S>
+where
+    S: SerializableAny,
+{
+    cx.data_mut(|d| d.get_persisted::<S>(node_state_id(snarl_id, node)))
+}
+
+/// Gives mutable access to the UI state of the node.
+///
+/// Node UI state is transient data, like open tab or scroll position,
+/// that is kept separately from the node value and so is not serialized with the `Snarl`.
+/// It is stored in egui memory and is persisted with it when egui persistence is enabled.
+///
+/// `snarl_id` is the id passed to `Snarl::show`.
+/// Missing state is initialized with `S::default()`.
+/// `f` is called while egui memory is locked, so it must not access the `Context`.
+///
+/// State is keyed by `NodeId`, so node added in place of a removed one starts fresh.
+/// Call `remove_node_state` to free state of removed nodes.
+///
+/// `SnarlViewer::show_content` reaches it through `NodeContext::node_state_mut`.
+pub fn node_state_mut<S, R>(
+    cx: &Context,
+    snarl_id: Id,
+    node: NodeId,
+    f: impl FnOnce(&mut S) -> R,
+) -> R
+where
+    S: SerializableAny + Default,
+{
+    cx.data_mut(|d| f(d.get_persisted_mut_or_default::<S>(node_state_id(snarl_id, node))))
+}
+
+/// Removes the UI state of the node.
+pub fn remove_node_state<S>(cx: &Context, snarl_id: Id, node: NodeId)
+where
+    S: 'static,
+{
+    cx.data_mut(|d| d.remove::<S>(node_state_id(snarl_id, node)));
+}
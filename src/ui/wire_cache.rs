@@ -0,0 +1,102 @@
+use egui::{ahash::HashMap, Pos2, Rect, Vec2};
+
+use crate::Wire;
+
+use super::{hit_path, WireParams, WireStyle};
+
+/// Everything the wire geometry depends on.
+/// Pan and zoom change endpoints and frame size, so they invalidate the geometry too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct WireKey {
+    from: Pos2,
+    to: Pos2,
+    frame_size: f32,
+    upscale: bool,
+    downscale: bool,
+    style: WireStyle,
+    fan: Vec2,
+    tolerance: f32,
+}
+
+/// Screen-space polylines of wires kept between frames.
+///
+/// Path of a wire is recomputed only when its key changes,
+/// so wires between nodes that did not move are not sampled again.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WireCache {
+    paths: HashMap<Wire, CachedWire>,
+
+    /// Number of paths recomputed so far.
+    #[cfg(test)]
+    pub(crate) rebuilt: usize,
+}
+
+#[derive(Clone, Debug)]
+struct CachedWire {
+    key: WireKey,
+    path: Vec<Pos2>,
+
+    /// Bounding rect of the path, to skip distance checks for far away wires.
+    bounds: Rect,
+}
+
+impl WireCache {
+    /// Drops paths of wires that no longer exist.
+    pub(super) fn retain(&mut self, mut f: impl FnMut(&Wire) -> bool) {
+        self.paths.retain(|wire, _| f(wire));
+    }
+
+    /// Makes sure cached path of the wire matches given endpoints and parameters.
+    pub(super) fn update(
+        &mut self,
+        wire: Wire,
+        params: &WireParams,
+        from: Pos2,
+        to: Pos2,
+        tolerance: f32,
+    ) {
+        let key = WireKey {
+            from,
+            to,
+            frame_size: params.frame_size,
+            upscale: params.upscale,
+            downscale: params.downscale,
+            style: params.style,
+            fan: params.fan,
+            tolerance,
+        };
+
+        if self
+            .paths
+            .get(&wire)
+            .is_some_and(|cached| cached.key == key)
+        {
+            return;
+        }
+
+        let path = params.path(from, to, tolerance);
+        let bounds = Rect::from_points(&path);
+        self.paths.insert(wire, CachedWire { key, path, bounds });
+
+        #[cfg(test)]
+        {
+            self.rebuilt += 1;
+        }
+    }
+
+    /// Returns cached path of the wire.
+    /// Empty if the wire was not updated.
+    pub(super) fn path(&self, wire: &Wire) -> &[Pos2] {
+        self.paths.get(wire).map_or(&[], |cached| &cached.path)
+    }
+
+    /// Returns true if `pos` is within `threshold` from the cached path of the wire.
+    pub(super) fn hit(&self, wire: &Wire, pos: Pos2, threshold: f32) -> bool {
+        match self.paths.get(wire) {
+            Some(cached) if cached.bounds.expand(threshold).contains(pos) => {
+                hit_path(pos, &cached.path, threshold)
+            }
+            _ => false,
+        }
+    }
+}
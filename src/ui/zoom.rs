@@ -0,0 +1,141 @@
+use egui::{
+    epaint::Shadow,
+    style::{Spacing, WidgetVisuals, Widgets},
+    FontId, Frame, Margin, Rounding, Stroke, Style, Vec2, Visuals,
+};
+
+/// Scales UI metrics by the zoom factor.
+pub(crate) trait Zoom {
+    fn zoom(&mut self, zoom: f32);
+}
+
+impl Zoom for f32 {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        *self *= zoom;
+    }
+}
+
+impl Zoom for Vec2 {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        *self *= zoom;
+    }
+}
+
+impl Zoom for Rounding {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        self.nw.zoom(zoom);
+        self.ne.zoom(zoom);
+        self.se.zoom(zoom);
+        self.sw.zoom(zoom);
+    }
+}
+
+impl Zoom for Margin {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        self.left.zoom(zoom);
+        self.right.zoom(zoom);
+        self.top.zoom(zoom);
+        self.bottom.zoom(zoom);
+    }
+}
+
+impl Zoom for Stroke {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        self.width.zoom(zoom);
+    }
+}
+
+impl Zoom for Shadow {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        self.extrusion.zoom(zoom);
+    }
+}
+
+impl Zoom for Frame {
+    fn zoom(&mut self, zoom: f32) {
+        self.inner_margin.zoom(zoom);
+        self.outer_margin.zoom(zoom);
+        self.rounding.zoom(zoom);
+        self.shadow.zoom(zoom);
+        self.stroke.zoom(zoom);
+    }
+}
+
+impl Zoom for FontId {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        self.size.zoom(zoom);
+    }
+}
+
+impl Zoom for WidgetVisuals {
+    fn zoom(&mut self, zoom: f32) {
+        self.bg_stroke.zoom(zoom);
+        self.rounding.zoom(zoom);
+        self.fg_stroke.zoom(zoom);
+        self.expansion.zoom(zoom);
+    }
+}
+
+impl Zoom for Widgets {
+    fn zoom(&mut self, zoom: f32) {
+        self.noninteractive.zoom(zoom);
+        self.inactive.zoom(zoom);
+        self.hovered.zoom(zoom);
+        self.active.zoom(zoom);
+        self.open.zoom(zoom);
+    }
+}
+
+impl Zoom for Visuals {
+    fn zoom(&mut self, zoom: f32) {
+        self.widgets.zoom(zoom);
+        self.selection.stroke.zoom(zoom);
+        self.window_rounding.zoom(zoom);
+        self.window_shadow.zoom(zoom);
+        self.window_stroke.zoom(zoom);
+        self.menu_rounding.zoom(zoom);
+        self.popup_shadow.zoom(zoom);
+        self.resize_corner_size.zoom(zoom);
+        self.text_cursor.zoom(zoom);
+        self.clip_rect_margin.zoom(zoom);
+    }
+}
+
+impl Zoom for Spacing {
+    fn zoom(&mut self, zoom: f32) {
+        self.item_spacing.zoom(zoom);
+        self.window_margin.zoom(zoom);
+        self.button_padding.zoom(zoom);
+        self.menu_margin.zoom(zoom);
+        self.indent.zoom(zoom);
+        self.interact_size.zoom(zoom);
+        self.slider_width.zoom(zoom);
+        self.combo_width.zoom(zoom);
+        self.text_edit_width.zoom(zoom);
+        self.icon_width.zoom(zoom);
+        self.icon_width_inner.zoom(zoom);
+        self.icon_spacing.zoom(zoom);
+        self.tooltip_width.zoom(zoom);
+        self.combo_height.zoom(zoom);
+    }
+}
+
+impl Zoom for Style {
+    fn zoom(&mut self, zoom: f32) {
+        if let Some(font_id) = &mut self.override_font_id {
+            font_id.zoom(zoom);
+        }
+        for font_id in self.text_styles.values_mut() {
+            font_id.zoom(zoom);
+        }
+        self.spacing.zoom(zoom);
+        self.visuals.zoom(zoom);
+    }
+}
@@ -0,0 +1,223 @@
+//! Harness for tests that show a `Snarl` and drive it with synthetic input.
+
+#![allow(dead_code)]
+
+use std::cell::Cell;
+
+use egui::{
+    pos2, vec2, CentralPanel, Context, Event, FullOutput, Id, Key, Modifiers, PointerButton, Pos2,
+    RawInput, Rect, Ui,
+};
+use egui_snarl::{
+    ui::{SnarlResponse, SnarlStyle, SnarlViewer},
+    Snarl,
+};
+
+/// Implements required `SnarlViewer` methods with fixed 50x50 nodes
+/// and given number of pins, labelled "in" and "out".
+macro_rules! viewer_stubs {
+    ($node:ty) => {
+        viewer_stubs!($node, inputs: 0, outputs: 0);
+    };
+    ($node:ty, inputs: $inputs:expr, outputs: $outputs:expr) => {
+        fn node_picker(&mut self, _: &mut egui::Ui) -> egui::InnerResponse<Option<$node>> {
+            unimplemented!()
+        }
+
+        fn size_hint(&self, _: &$node) -> egui::Vec2 {
+            egui::vec2(50.0, 50.0)
+        }
+
+        fn title<'a>(&'a mut self, _: &'a $node) -> &'a str {
+            "Node"
+        }
+
+        fn inputs(&mut self, _: &$node) -> usize {
+            $inputs
+        }
+
+        fn outputs(&mut self, _: &$node) -> usize {
+            $outputs
+        }
+
+        fn show_input(
+            &mut self,
+            _: &egui_snarl::ui::InPin<$node>,
+            ui: &mut egui::Ui,
+            _: &mut egui_snarl::ui::Effects<$node>,
+        ) -> egui::InnerResponse<egui_snarl::ui::PinInfo> {
+            egui::InnerResponse::new(Default::default(), ui.label("in"))
+        }
+
+        fn show_output(
+            &mut self,
+            _: &egui_snarl::ui::OutPin<$node>,
+            ui: &mut egui::Ui,
+            _: &mut egui_snarl::ui::Effects<$node>,
+        ) -> egui::InnerResponse<egui_snarl::ui::PinInfo> {
+            egui::InnerResponse::new(Default::default(), ui.label("out"))
+        }
+    };
+}
+
+/// Screen the Snarl is shown on.
+pub const SCREEN: Rect = Rect {
+    min: pos2(0.0, 0.0),
+    max: pos2(800.0, 600.0),
+};
+
+/// Id the Snarl is shown with.
+pub fn snarl_id() -> Id {
+    Id::new("snarl")
+}
+
+/// Shows a Snarl in a central panel of a persistent egui context, one frame per call.
+///
+/// Each frame advances the clock by `FRAME_TIME`.
+pub struct Harness {
+    pub ctx: Context,
+    pub style: SnarlStyle,
+    time: Cell<f64>,
+    modifiers: Cell<Modifiers>,
+}
+
+/// Seconds between frames.
+pub const FRAME_TIME: f64 = 1.0 / 60.0;
+
+impl Harness {
+    pub fn new() -> Self {
+        Harness::with_style(SnarlStyle::default())
+    }
+
+    pub fn with_style(style: SnarlStyle) -> Self {
+        Harness {
+            ctx: Context::default(),
+            style,
+            time: Cell::new(0.0),
+            modifiers: Cell::new(Modifiers::NONE),
+        }
+    }
+
+    /// Advances the clock without running frames.
+    pub fn wait(&self, seconds: f64) {
+        self.time.set(self.time.get() + seconds);
+    }
+
+    /// Runs one frame showing the Snarl with `events` as input.
+    pub fn frame<T, V>(
+        &self,
+        snarl: &mut Snarl<T>,
+        viewer: &mut V,
+        events: Vec<Event>,
+    ) -> (SnarlResponse, FullOutput)
+    where
+        V: SnarlViewer<T>,
+    {
+        self.frame_with(events, |ui| snarl.show(viewer, &self.style, snarl_id(), ui))
+    }
+
+    /// Runs one frame calling `f` inside the central panel.
+    pub fn frame_with<R>(
+        &self,
+        events: Vec<Event>,
+        f: impl FnOnce(&mut Ui) -> R,
+    ) -> (R, FullOutput) {
+        let input = RawInput {
+            screen_rect: Some(SCREEN),
+            time: Some(self.time.get()),
+            modifiers: self.modifiers.get(),
+            events,
+            ..Default::default()
+        };
+        self.wait(FRAME_TIME);
+
+        let mut f = Some(f);
+        let mut result = None;
+        let output = self.ctx.run(input, |ctx| {
+            CentralPanel::default().show(ctx, |ui| {
+                if let Some(f) = f.take() {
+                    result = Some(f(ui));
+                }
+            });
+        });
+        (result.unwrap(), output)
+    }
+
+    /// Clicks at `pos`, taking two frames.
+    pub fn click<T, V>(&self, snarl: &mut Snarl<T>, viewer: &mut V, pos: Pos2)
+    where
+        V: SnarlViewer<T>,
+    {
+        self.frame(
+            snarl,
+            viewer,
+            vec![Event::PointerMoved(pos), button(pos, true)],
+        );
+        self.frame(snarl, viewer, vec![button(pos, false)]);
+    }
+
+    /// Clicks at `pos` with `modifiers` held, taking two frames.
+    pub fn click_with<T, V>(
+        &self,
+        snarl: &mut Snarl<T>,
+        viewer: &mut V,
+        pos: Pos2,
+        modifiers: Modifiers,
+    ) where
+        V: SnarlViewer<T>,
+    {
+        self.modifiers.set(modifiers);
+        self.click(snarl, viewer, pos);
+        self.modifiers.set(Modifiers::NONE);
+    }
+
+    /// Drags with primary button from `from` to `to` in a few steps.
+    pub fn drag<T, V>(&self, snarl: &mut Snarl<T>, viewer: &mut V, from: Pos2, to: Pos2)
+    where
+        V: SnarlViewer<T>,
+    {
+        self.frame(
+            snarl,
+            viewer,
+            vec![Event::PointerMoved(from), button(from, true)],
+        );
+        for t in [0.5, 1.0] {
+            self.frame(snarl, viewer, vec![Event::PointerMoved(from.lerp(to, t))]);
+        }
+        self.frame(snarl, viewer, vec![button(to, false)]);
+    }
+
+    /// Moves the pointer to `pos` and keeps it there for a frame.
+    pub fn hover<T, V>(&self, snarl: &mut Snarl<T>, viewer: &mut V, pos: Pos2)
+    where
+        V: SnarlViewer<T>,
+    {
+        self.frame(snarl, viewer, vec![Event::PointerMoved(pos)]);
+        self.frame(snarl, viewer, vec![]);
+    }
+}
+
+/// Primary button press or release at `pos`.
+pub fn button(pos: Pos2, pressed: bool) -> Event {
+    Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+/// Press of `key` without modifiers.
+pub fn key(key: Key) -> Event {
+    Event::Key {
+        key,
+        pressed: true,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+/// Point inside the header of the node shown in the last frame.
+pub fn header_pos<T>(snarl: &Snarl<T>, node: egui_snarl::NodeId) -> Pos2 {
+    snarl.debug_layout().node_rect(node).unwrap().center_top() + vec2(0.0, 8.0)
+}
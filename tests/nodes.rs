@@ -0,0 +1,423 @@
+#[macro_use]
+mod common;
+
+use std::cell::RefCell;
+
+use egui::{pos2, vec2, Color32, Event, Frame, Key, Pos2, Rect, Response, Shape, Ui};
+use egui_snarl::{
+    ui::{Effects, InPin, NodeContext, OutPin, SnarlStyle, SnarlViewer},
+    NodeId, Snarl, SnarlComment,
+};
+
+use common::{header_pos, key, snarl_id, Harness};
+
+/// Node value tells if the node is locked.
+struct LockViewer;
+
+impl SnarlViewer<bool> for LockViewer {
+    fn movable(&self, locked: &bool) -> bool {
+        !*locked
+    }
+
+    fn deletable(&self, locked: &bool) -> bool {
+        !*locked
+    }
+
+    viewer_stubs!(bool);
+}
+
+#[test]
+fn locked_node_is_not_dragged() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let locked = snarl.add_node(true, pos2(100.0, 100.0));
+    let free = snarl.add_node(false, pos2(300.0, 100.0));
+
+    harness.frame(&mut snarl, &mut LockViewer, vec![]);
+    let from = header_pos(&snarl, locked);
+    harness.drag(&mut snarl, &mut LockViewer, from, from + vec2(40.0, 40.0));
+    assert_eq!(snarl.node_pos(locked), Some(pos2(100.0, 100.0)));
+
+    // Dragging selection moves only nodes that are not locked.
+    snarl.select_node(locked);
+    snarl.select_node(free);
+    let from = header_pos(&snarl, free);
+    harness.drag(&mut snarl, &mut LockViewer, from, from + vec2(40.0, 40.0));
+    assert_eq!(snarl.node_pos(locked), Some(pos2(100.0, 100.0)));
+    assert_ne!(snarl.node_pos(free), Some(pos2(300.0, 100.0)));
+}
+
+#[test]
+fn locked_node_is_not_deleted() {
+    let harness = Harness::with_style(SnarlStyle::default().enable_keyboard(true));
+    let mut snarl = Snarl::new();
+    let locked = snarl.add_node(true, pos2(0.0, 0.0));
+    let free = snarl.add_node(false, pos2(100.0, 0.0));
+    snarl.select_node(locked);
+    snarl.select_node(free);
+
+    let events = vec![Event::PointerMoved(pos2(400.0, 300.0)), key(Key::Delete)];
+    harness.frame(&mut snarl, &mut LockViewer, events);
+    assert_eq!(
+        snarl.nodes().map(|(id, _)| id).collect::<Vec<_>>(),
+        [locked]
+    );
+}
+
+/// Shows close button in the header and remembers where it was shown.
+#[derive(Default)]
+struct CloseButtonViewer {
+    close_button: Option<Rect>,
+}
+
+impl SnarlViewer<()> for CloseButtonViewer {
+    fn show_header(&mut self, id: NodeId, _: &RefCell<()>, ui: &mut Ui, effects: &mut Effects<()>) {
+        ui.label("Node");
+        let r = ui.small_button("x");
+        if r.clicked() {
+            effects.remove_node(id);
+        }
+        self.close_button = Some(r.rect);
+    }
+
+    viewer_stubs!(());
+}
+
+#[test]
+fn header_button_takes_precedence_over_drag() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    snarl.add_node((), pos2(100.0, 100.0));
+    let mut viewer = CloseButtonViewer::default();
+
+    // Header rect is known from the first frame.
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    let close = viewer.close_button.unwrap().center();
+    harness.click(&mut snarl, &mut viewer, close);
+    assert!(snarl.is_empty());
+}
+
+#[derive(Default)]
+struct MoveViewer {
+    moved: Vec<(NodeId, Pos2)>,
+}
+
+impl SnarlViewer<()> for MoveViewer {
+    fn on_node_moved(&mut self, node: NodeId, new_pos: Pos2) {
+        self.moved.push((node, new_pos));
+    }
+
+    viewer_stubs!(());
+}
+
+#[test]
+fn drag_reports_moves_once_per_frame() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let mut viewer = MoveViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    let from = header_pos(&snarl, a);
+    harness.drag(&mut snarl, &mut viewer, from, from + vec2(60.0, 30.0));
+
+    // One call per frame in which the node moved, the last one with the final position.
+    let pos = snarl.node_pos(a).unwrap();
+    assert_ne!(pos, pos2(100.0, 100.0));
+    assert!(!viewer.moved.is_empty() && viewer.moved.len() <= 2);
+    assert_eq!(viewer.moved.last(), Some(&(a, pos)));
+}
+
+#[derive(Default)]
+struct DoubleClickViewer {
+    double_clicked: Vec<NodeId>,
+}
+
+impl SnarlViewer<()> for DoubleClickViewer {
+    fn on_node_double_click(&mut self, node: NodeId, _: &mut Effects<()>) {
+        self.double_clicked.push(node);
+    }
+
+    viewer_stubs!(());
+}
+
+#[test]
+fn double_click_on_header() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let mut viewer = DoubleClickViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    let header = header_pos(&snarl, a);
+
+    // Clicks too far apart are not a double click.
+    harness.click(&mut snarl, &mut viewer, header);
+    harness.wait(1.0);
+    harness.click(&mut snarl, &mut viewer, header);
+    assert!(viewer.double_clicked.is_empty());
+
+    harness.click(&mut snarl, &mut viewer, header);
+    assert_eq!(viewer.double_clicked, [a]);
+}
+
+const ERROR: Color32 = Color32::from_rgb(120, 20, 20);
+
+/// Node value tells if the node is in error state.
+struct ErrorViewer;
+
+impl SnarlViewer<bool> for ErrorViewer {
+    fn node_frame(&mut self, error: &bool, default: Frame) -> Frame {
+        if *error {
+            default.fill(ERROR)
+        } else {
+            default
+        }
+    }
+
+    viewer_stubs!(bool);
+}
+
+#[test]
+fn node_frame_is_used_per_node() {
+    let harness = Harness::new();
+
+    // Count shapes filled with the error color.
+    // Frame with shadow is painted as a group of shapes.
+    let error_frames = |snarl: &mut Snarl<bool>| {
+        let (_, output) = harness.frame(snarl, &mut ErrorViewer, vec![]);
+        let is_error = |shape: &Shape| matches!(shape, Shape::Rect(rect) if rect.fill == ERROR);
+        output
+            .shapes
+            .iter()
+            .filter(|clipped| match &clipped.shape {
+                Shape::Vec(shapes) => shapes.iter().any(is_error),
+                shape => is_error(shape),
+            })
+            .count()
+    };
+
+    let mut snarl = Snarl::new();
+    snarl.add_node(false, pos2(100.0, 100.0));
+    assert_eq!(error_frames(&mut snarl), 0);
+
+    snarl.add_node(true, pos2(300.0, 100.0));
+    assert_eq!(error_frames(&mut snarl), 1);
+}
+
+/// Node value tells if the node is always updated.
+#[derive(Default)]
+struct UpdateViewer {
+    shown: Vec<NodeId>,
+}
+
+impl SnarlViewer<bool> for UpdateViewer {
+    fn always_update(&self, node: &bool) -> bool {
+        *node
+    }
+
+    fn show_content(
+        &mut self,
+        ctx: &NodeContext,
+        _: &RefCell<bool>,
+        _: &[InPin<bool>],
+        _: &[OutPin<bool>],
+        ui: &mut Ui,
+        _: &mut Effects<bool>,
+    ) -> Response {
+        self.shown.push(ctx.id);
+        ui.label("content")
+    }
+
+    viewer_stubs!(bool);
+}
+
+#[test]
+fn offscreen_nodes_are_culled_unless_always_updated() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let visible = snarl.add_node(false, pos2(100.0, 100.0));
+    let culled = snarl.add_node(false, pos2(5000.0, 100.0));
+    let updated = snarl.add_node(true, pos2(5000.0, 300.0));
+    let mut viewer = UpdateViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    assert!(viewer.shown.contains(&visible));
+    assert!(viewer.shown.contains(&updated));
+    assert!(!viewer.shown.contains(&culled));
+}
+
+#[test]
+fn set_node_pos_moves_shown_node() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let mut viewer = MoveViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    let before = snarl.node_rect(a).unwrap();
+
+    snarl.set_node_pos(a, pos2(50.0, 30.0));
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    let after = snarl.node_rect(a).unwrap();
+    assert_eq!(after.min - before.min, vec2(50.0, 30.0));
+
+    // Moving node programmatically is not reported as user move.
+    assert!(viewer.moved.is_empty());
+}
+
+struct PlainViewer;
+
+impl SnarlViewer<()> for PlainViewer {
+    viewer_stubs!(());
+}
+
+#[test]
+fn delete_is_suppressed_by_focused_text_edit() {
+    let harness = Harness::with_style(SnarlStyle::default().enable_keyboard(true));
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(100.0, 0.0));
+    let c = snarl.add_node((), pos2(200.0, 0.0));
+    snarl.select_node(a);
+    snarl.select_node(c);
+
+    // Shows the Snarl with the pointer over it and Delete pressed,
+    // optionally with a focused text edit.
+    let press_delete = |snarl: &mut Snarl<()>, focus_text: bool| {
+        let events = vec![Event::PointerMoved(pos2(400.0, 300.0)), key(Key::Delete)];
+        harness.frame_with(events, |ui| {
+            if focus_text {
+                ui.text_edit_singleline(&mut String::new()).request_focus();
+            }
+            snarl.show(&mut PlainViewer, &harness.style, snarl_id(), ui);
+        });
+    };
+
+    press_delete(&mut snarl, true);
+    assert_eq!(snarl.nodes().count(), 3);
+
+    // Focus is released once the text edit is not shown.
+    harness.frame_with(vec![], |_| {});
+
+    press_delete(&mut snarl, false);
+    assert_eq!(snarl.nodes().map(|(id, _)| id).collect::<Vec<_>>(), [b]);
+}
+
+#[derive(Default)]
+struct SelectionViewer {
+    changes: Vec<Vec<NodeId>>,
+}
+
+impl SnarlViewer<()> for SelectionViewer {
+    fn on_selection_changed(&mut self, selected: &[NodeId]) {
+        self.changes.push(selected.to_vec());
+    }
+
+    viewer_stubs!(());
+}
+
+#[test]
+fn selection_changes_are_reported_once() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(100.0, 0.0));
+    let mut viewer = SelectionViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    assert!(viewer.changes.is_empty());
+
+    snarl.select_node(b);
+    snarl.select_node(a);
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    assert_eq!(viewer.changes, [vec![b, a]]);
+
+    snarl.clear_selection();
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    assert_eq!(viewer.changes, [vec![b, a], vec![]]);
+}
+
+/// Shows content taller than the size hint.
+#[derive(Default)]
+struct TallViewer {
+    shown: Vec<NodeId>,
+}
+
+impl SnarlViewer<()> for TallViewer {
+    fn show_content(
+        &mut self,
+        ctx: &NodeContext,
+        _: &RefCell<()>,
+        _: &[InPin<()>],
+        _: &[OutPin<()>],
+        ui: &mut Ui,
+        _: &mut Effects<()>,
+    ) -> Response {
+        self.shown.push(ctx.id);
+        ui.add_space(300.0);
+        ui.label("bottom")
+    }
+
+    viewer_stubs!(());
+}
+
+#[test]
+fn partly_visible_node_larger_than_size_hint_is_shown() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let mut viewer = TallViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    let rect = snarl.node_rect(a).unwrap();
+    assert!(rect.height() > 300.0);
+
+    // Size hint part of the node is above the screen, but its bottom is visible.
+    snarl.set_node_pos(a, pos2(100.0, 100.0 - rect.top() - 200.0));
+    for _ in 0..2 {
+        viewer.shown.clear();
+        harness.frame(&mut snarl, &mut viewer, vec![]);
+        assert_eq!(viewer.shown, [a]);
+        assert_eq!(snarl.node_rect(a).unwrap().height(), rect.height());
+    }
+}
+
+#[test]
+fn resize_starts_from_size_hint_and_keeps_min_size() {
+    let mut snarl = Snarl::new();
+    let node = snarl.add_node((), pos2(0.0, 0.0));
+    assert_eq!(snarl.node_size(node), None);
+
+    // Size hint of the plain viewer is 50x50.
+    let min_size = vec2(30.0, 30.0);
+    snarl.resize_node(node, vec2(20.0, -10.0), min_size, &PlainViewer);
+    assert_eq!(snarl.node_size(node), Some(vec2(70.0, 40.0)));
+
+    snarl.resize_node(node, vec2(-100.0, 5.0), min_size, &PlainViewer);
+    assert_eq!(snarl.node_size(node), Some(vec2(30.0, 45.0)));
+}
+
+#[test]
+fn comment_contains_nodes_it_intersects() {
+    let mut snarl = Snarl::new();
+    let inside = snarl.add_node((), pos2(20.0, 20.0));
+    let overlapping = snarl.add_node((), pos2(180.0, 80.0));
+    snarl.add_node((), pos2(300.0, 0.0));
+
+    let comment = snarl.add_comment(SnarlComment {
+        pos: pos2(0.0, 0.0),
+        size: vec2(200.0, 100.0),
+        color: Color32::DARK_BLUE,
+        text: "Inputs".to_owned(),
+    });
+    assert_eq!(
+        snarl.comment_nodes(comment, &PlainViewer),
+        [inside, overlapping]
+    );
+
+    snarl.remove_comment(comment);
+    assert!(snarl.comment_nodes(comment, &PlainViewer).is_empty());
+}
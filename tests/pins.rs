@@ -0,0 +1,420 @@
+#[macro_use]
+mod common;
+
+use egui::{
+    pos2, vec2, Color32, FullOutput, InnerResponse, Pos2, Rect, Shape, Ui, Vec2, WidgetText,
+};
+use egui_snarl::{
+    ui::{
+        Effects, InPin, LayoutSnapshot, OutPin, PinColumnLayout, PinInfo, SnarlStyle, SnarlViewer,
+    },
+    InPinId, NodeId, OutPinId, Snarl,
+};
+
+use common::Harness;
+
+/// Fill of the pin circle centered at `pos`.
+fn fill_at(output: &FullOutput, pos: Pos2) -> Option<Color32> {
+    output
+        .shapes
+        .iter()
+        .find_map(|clipped| match &clipped.shape {
+            Shape::Circle(circle) if circle.center == pos => Some(circle.fill),
+            _ => None,
+        })
+}
+
+/// Rects on screen of texts equal to `s`.
+fn texts(output: &FullOutput, s: &str) -> Vec<Rect> {
+    output
+        .shapes
+        .iter()
+        .filter_map(|clipped| match &clipped.shape {
+            Shape::Text(text) if text.galley.text() == s => {
+                Some(text.galley.rect.translate(text.pos.to_vec2()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// First two inputs are required.
+struct RequiredViewer;
+
+impl SnarlViewer<()> for RequiredViewer {
+    fn input_required(&self, pin: &InPin<()>) -> bool {
+        pin.id.input < 2
+    }
+
+    viewer_stubs!((), inputs: 3, outputs: 1);
+}
+
+#[test]
+fn unconnected_required_input_is_filled() {
+    const MISSING: Color32 = Color32::from_rgb(255, 0, 255);
+    let harness =
+        Harness::with_style(SnarlStyle::default().unconnected_required_fill(Some(MISSING)));
+
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(300.0, 0.0));
+    snarl.connect(
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 1 },
+    );
+
+    let (_, output) = harness.frame(&mut snarl, &mut RequiredViewer, vec![]);
+    let fill = |input| {
+        let pos = snarl.debug_layout().input_pos(InPinId { node: b, input });
+        fill_at(&output, pos.unwrap())
+    };
+
+    // Required and unconnected.
+    assert_eq!(fill(0), Some(MISSING));
+    // Required, but connected.
+    assert_eq!(fill(1), Some(PinInfo::default().fill));
+    // Optional.
+    assert_eq!(fill(2), Some(PinInfo::default().fill));
+}
+
+/// Names only the second input, every input shows "in".
+struct NameViewer;
+
+impl SnarlViewer<()> for NameViewer {
+    fn input_name(&mut self, pin: &InPin<()>) -> Option<WidgetText> {
+        (pin.id.input == 1).then(|| "rhs".into())
+    }
+
+    viewer_stubs!((), inputs: 2, outputs: 0);
+}
+
+#[test]
+fn input_name_is_between_pin_and_widgets() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let node = snarl.add_node((), pos2(100.0, 100.0));
+
+    let (_, output) = harness.frame(&mut snarl, &mut NameViewer, vec![]);
+
+    // Only the second input is named.
+    let names = texts(&output, "rhs");
+    let widgets = texts(&output, "in");
+    assert_eq!(names.len(), 1);
+    assert_eq!(widgets.len(), 2);
+
+    // Name is in the row of its pin, between the pin and the pin widgets.
+    let pin = snarl
+        .debug_layout()
+        .input_pos(InPinId { node, input: 1 })
+        .unwrap();
+    assert!(names[0].y_range().contains(pin.y));
+    assert!(pin.x < names[0].left() && names[0].right() < widgets[1].left());
+}
+
+/// Node value is the number of inputs, body is limited to 60 points.
+struct ScrollViewer;
+
+impl SnarlViewer<usize> for ScrollViewer {
+    fn body_max_height(&self, _: &usize) -> Option<f32> {
+        Some(60.0)
+    }
+
+    fn inputs(&mut self, inputs: &usize) -> usize {
+        *inputs
+    }
+
+    fn outputs(&mut self, _: &usize) -> usize {
+        1
+    }
+
+    fn show_input(
+        &mut self,
+        _: &InPin<usize>,
+        ui: &mut Ui,
+        _: &mut Effects<usize>,
+    ) -> InnerResponse<PinInfo> {
+        InnerResponse::new(PinInfo::default(), ui.label("in"))
+    }
+
+    fn show_output(
+        &mut self,
+        _: &OutPin<usize>,
+        ui: &mut Ui,
+        _: &mut Effects<usize>,
+    ) -> InnerResponse<PinInfo> {
+        InnerResponse::new(PinInfo::default(), ui.label("out"))
+    }
+
+    fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<usize>> {
+        unimplemented!()
+    }
+
+    fn size_hint(&self, _: &usize) -> Vec2 {
+        vec2(80.0, 50.0)
+    }
+
+    fn title<'a>(&'a mut self, _: &'a usize) -> &'a str {
+        "Node"
+    }
+}
+
+#[test]
+fn scrolled_out_pin_is_attached_to_body_edge() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let source = snarl.add_node(0, pos2(0.0, 0.0));
+    let node = snarl.add_node(20, pos2(200.0, 0.0));
+    snarl.connect(
+        OutPinId {
+            node: source,
+            output: 0,
+        },
+        InPinId { node, input: 19 },
+    );
+
+    // Scroll area settles its size on the second frame.
+    harness.frame(&mut snarl, &mut ScrollViewer, vec![]);
+    harness.frame(&mut snarl, &mut ScrollViewer, vec![]);
+
+    let layout = snarl.debug_layout();
+    let rect = layout.node_rect(node).unwrap();
+    let input_pos = |input| layout.input_pos(InPinId { node, input }).unwrap();
+
+    // Last pin is scrolled out of view, its wire is attached to the body bottom.
+    assert!(rect.height() < 120.0);
+    assert!(input_pos(19).y > input_pos(0).y);
+    assert!(input_pos(19).y <= rect.bottom());
+    assert_eq!(input_pos(19), input_pos(18));
+}
+
+/// Two inputs and one output per node.
+struct CollapseViewer;
+
+impl SnarlViewer<()> for CollapseViewer {
+    viewer_stubs!((), inputs: 2, outputs: 1);
+}
+
+#[test]
+fn collapsed_node_pins_are_at_header_edge() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(200.0, 0.0));
+    let to = InPinId { node: b, input: 1 };
+    snarl.connect(OutPinId { node: a, output: 0 }, to);
+    snarl.set_collapsed(b, true);
+
+    harness.frame(&mut snarl, &mut CollapseViewer, vec![]);
+
+    // Input pins of the collapsed node are at the left edge of its header.
+    let layout = snarl.debug_layout();
+    let header = layout.node_rect(b).unwrap();
+    let pos = layout.input_pos(to).unwrap();
+    assert!(pos.x < header.left());
+    assert!(header.y_range().contains(pos.y));
+    assert_eq!(layout.wires.len(), 1);
+}
+
+/// Records nodes whose pins were shown.
+#[derive(Default)]
+struct ShownPinsViewer {
+    shown: Vec<NodeId>,
+}
+
+impl SnarlViewer<()> for ShownPinsViewer {
+    fn show_input(
+        &mut self,
+        pin: &InPin<()>,
+        ui: &mut Ui,
+        _: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        self.shown.push(pin.id.node);
+        InnerResponse::new(PinInfo::default(), ui.label("in"))
+    }
+
+    fn show_output(
+        &mut self,
+        pin: &OutPin<()>,
+        ui: &mut Ui,
+        _: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        self.shown.push(pin.id.node);
+        InnerResponse::new(PinInfo::default(), ui.label("out"))
+    }
+
+    fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<()>> {
+        unimplemented!()
+    }
+
+    fn size_hint(&self, _: &()) -> Vec2 {
+        vec2(50.0, 50.0)
+    }
+
+    fn title<'a>(&'a mut self, _: &'a ()) -> &'a str {
+        "Node"
+    }
+
+    fn inputs(&mut self, _: &()) -> usize {
+        2
+    }
+
+    fn outputs(&mut self, _: &()) -> usize {
+        1
+    }
+}
+
+#[test]
+fn collapsed_node_pins_are_not_shown() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(200.0, 0.0));
+    snarl.set_collapsed(b, true);
+    let mut viewer = ShownPinsViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+
+    assert!(viewer.shown.contains(&a));
+    assert!(!viewer.shown.contains(&b));
+    let layout = snarl.debug_layout();
+    assert!(layout.input_pos(InPinId { node: b, input: 1 }).is_some());
+}
+
+#[test]
+fn wires_are_dragged_from_and_onto_collapsed_node() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 100.0));
+    let b = snarl.add_node((), pos2(200.0, 100.0));
+    let c = snarl.add_node((), pos2(400.0, 100.0));
+    snarl.set_collapsed(b, true);
+    let into_b = InPinId { node: b, input: 0 };
+    let out_of_b = OutPinId { node: b, output: 0 };
+    let out_of_a = OutPinId { node: a, output: 0 };
+    let into_c = InPinId { node: c, input: 0 };
+
+    harness.frame(&mut snarl, &mut CollapseViewer, vec![]);
+    let layout = snarl.debug_layout().clone();
+
+    // Stacked input accepts a wire dropped onto it.
+    let (from, to) = (layout.output_pos(out_of_a), layout.input_pos(into_b));
+    harness.drag(&mut snarl, &mut CollapseViewer, from.unwrap(), to.unwrap());
+    assert!(snarl.connected(out_of_a, into_b));
+
+    // Stacked output starts a wire.
+    let (from, to) = (layout.output_pos(out_of_b), layout.input_pos(into_c));
+    harness.drag(&mut snarl, &mut CollapseViewer, from.unwrap(), to.unwrap());
+    assert!(snarl.connected(out_of_b, into_c));
+}
+
+/// Node value is the size of its input pin.
+struct SizeViewer;
+
+impl SnarlViewer<f32> for SizeViewer {
+    fn show_input(
+        &mut self,
+        pin: &InPin<f32>,
+        ui: &mut Ui,
+        _: &mut Effects<f32>,
+    ) -> InnerResponse<PinInfo> {
+        let size = *pin.node.borrow();
+        InnerResponse::new(PinInfo::circle().with_size(size), ui.label("in"))
+    }
+
+    fn show_output(
+        &mut self,
+        _: &OutPin<f32>,
+        _: &mut Ui,
+        _: &mut Effects<f32>,
+    ) -> InnerResponse<PinInfo> {
+        unimplemented!()
+    }
+
+    fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<f32>> {
+        unimplemented!()
+    }
+
+    fn size_hint(&self, _: &f32) -> Vec2 {
+        vec2(50.0, 50.0)
+    }
+
+    fn title<'a>(&'a mut self, _: &'a f32) -> &'a str {
+        "Node"
+    }
+
+    fn inputs(&mut self, _: &f32) -> usize {
+        1
+    }
+
+    fn outputs(&mut self, _: &f32) -> usize {
+        0
+    }
+}
+
+#[test]
+fn pin_row_fits_pin_size() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let small = snarl.add_node(1.0, pos2(0.0, 0.0));
+    let large = snarl.add_node(4.0, pos2(200.0, 0.0));
+
+    harness.frame(&mut snarl, &mut SizeViewer, vec![]);
+
+    let layout = snarl.debug_layout();
+    let height = |node| layout.node_rect(node).unwrap().height();
+    assert!(height(large) > height(small));
+}
+
+/// Three inputs and one output per node.
+struct RowsViewer;
+
+impl SnarlViewer<()> for RowsViewer {
+    viewer_stubs!((), inputs: 3, outputs: 1);
+}
+
+/// Shows a single node at the origin with `style` and returns its layout.
+fn rows_layout(style: SnarlStyle) -> (InPinId, OutPinId, LayoutSnapshot) {
+    let harness = Harness::with_style(style);
+    let mut snarl = Snarl::new();
+    let node = snarl.add_node((), pos2(0.0, 0.0));
+
+    harness.frame(&mut snarl, &mut RowsViewer, vec![]);
+    harness.frame(&mut snarl, &mut RowsViewer, vec![]);
+
+    let input = InPinId { node, input: 0 };
+    let output = OutPinId { node, output: 0 };
+    (input, output, snarl.debug_layout().clone())
+}
+
+#[test]
+fn input_column_width_pushes_outputs_right() {
+    let output_x = |style| {
+        let (input, output, layout) = rows_layout(style);
+        layout.output_pos(output).unwrap().x - layout.input_pos(input).unwrap().x
+    };
+
+    let narrow = output_x(SnarlStyle::default());
+    let wide = output_x(SnarlStyle::default().pin_column_layout(PinColumnLayout {
+        input_width: Some(100.0),
+        ..Default::default()
+    }));
+
+    assert!(wide > narrow + 50.0);
+}
+
+#[test]
+fn row_spacing_moves_following_rows_down() {
+    let (first, _, dense) = rows_layout(SnarlStyle::default().row_spacing(Some(2.0)));
+    let (_, _, spacious) = rows_layout(SnarlStyle::default().row_spacing(Some(12.0)));
+
+    // Two gaps between three rows grow by 10 points each.
+    let height = |layout: &LayoutSnapshot| layout.node_rect(first.node).unwrap().height();
+    assert!((height(&spacious) - height(&dense) - 20.0).abs() < 0.5);
+
+    // First row stays in place, following rows move down.
+    for (input, shift) in [(0, 0.0), (1, 10.0), (2, 20.0)] {
+        let pin = InPinId { input, ..first };
+        let dy = spacious.input_pos(pin).unwrap().y - dense.input_pos(pin).unwrap().y;
+        assert!((dy - shift).abs() < 0.5);
+    }
+}
@@ -0,0 +1,68 @@
+#[macro_use]
+mod common;
+
+use egui::{pos2, Event, Pos2};
+use egui_snarl::{
+    ui::{SnarlHover, SnarlResponse, SnarlViewer},
+    InPinId, OutPinId, Snarl,
+};
+
+use common::{button, Harness};
+
+/// One input and one output per node.
+struct PlainViewer;
+
+impl SnarlViewer<()> for PlainViewer {
+    viewer_stubs!((), inputs: 1, outputs: 1);
+}
+
+/// Shows the Snarl with the pointer at `pos`.
+fn hover(harness: &Harness, snarl: &mut Snarl<()>, pos: Pos2) -> SnarlResponse {
+    let (response, _) = harness.frame(snarl, &mut PlainViewer, vec![Event::PointerMoved(pos)]);
+    response
+}
+
+#[test]
+fn hovered_element_is_reported() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+
+    let response = hover(&harness, &mut snarl, pos2(700.0, 500.0));
+    assert_eq!(response.hovered, Some(SnarlHover::Background));
+
+    let layout = snarl.debug_layout().clone();
+    let node = layout.node_rect(a).unwrap().center();
+    let response = hover(&harness, &mut snarl, node);
+    assert_eq!(response.hovered, Some(SnarlHover::Node(a)));
+
+    let pin = InPinId { node: a, input: 0 };
+    let response = hover(&harness, &mut snarl, layout.input_pos(pin).unwrap());
+    assert_eq!(response.hovered, Some(SnarlHover::InPin(pin)));
+}
+
+#[test]
+fn dragged_wire_is_reported_on_release() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let b = snarl.add_node((), pos2(400.0, 100.0));
+
+    harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    let start = snarl.debug_layout().output_pos(from).unwrap();
+    let end = snarl.debug_layout().input_pos(to).unwrap();
+
+    // Drag a wire from the output to the input.
+    let events = vec![Event::PointerMoved(start), button(start, true)];
+    harness.frame(&mut snarl, &mut PlainViewer, events);
+    hover(&harness, &mut snarl, start.lerp(end, 0.5));
+    let response = hover(&harness, &mut snarl, end);
+    assert!(response.connected.is_empty());
+
+    let (response, _) = harness.frame(&mut snarl, &mut PlainViewer, vec![button(end, false)]);
+    assert_eq!(response.connected, [(from, to)]);
+    assert!(response.disconnected.is_empty());
+    assert!(response.moved.is_empty());
+}
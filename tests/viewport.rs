@@ -0,0 +1,145 @@
+#[macro_use]
+mod common;
+
+use egui::{pos2, vec2, Color32, Id, Painter, Rect, Shape};
+use egui_snarl::{
+    ui::{SnarlStyle, SnarlTransform, SnarlViewer},
+    InPinId, OutPinId, Snarl,
+};
+
+use common::{snarl_id, Harness};
+
+const REGION: Color32 = Color32::from_rgb(1, 2, 3);
+
+/// Shades 100x100 region of the graph at the origin.
+#[derive(Default)]
+struct BackgroundViewer {
+    seen: Option<SnarlTransform>,
+}
+
+impl SnarlViewer<()> for BackgroundViewer {
+    fn draw_background(&mut self, painter: &Painter, transform: &SnarlTransform, viewport: Rect) {
+        self.seen = Some(*transform);
+        let region = Rect::from_min_max(
+            transform.graph_pos_to_screen(pos2(0.0, 0.0), viewport),
+            transform.graph_pos_to_screen(pos2(100.0, 100.0), viewport),
+        );
+        painter.rect_filled(region, 0.0, REGION);
+    }
+
+    viewer_stubs!(());
+}
+
+#[test]
+fn background_is_painted_in_graph_space() {
+    let harness = Harness::new();
+    let transform = SnarlTransform {
+        offset: vec2(30.0, 40.0),
+        scale: 1.5,
+    };
+    let mut snarl = Snarl::new();
+    snarl.set_transform(snarl_id(), transform);
+    let mut viewer = BackgroundViewer::default();
+
+    let (_, output) = harness.frame(&mut snarl, &mut viewer, vec![]);
+    assert_eq!(viewer.seen, Some(transform));
+
+    // Region is scaled with the graph.
+    let region = output
+        .shapes
+        .iter()
+        .find_map(|clipped| match &clipped.shape {
+            Shape::Rect(rect) if rect.fill == REGION => Some(rect.rect),
+            _ => None,
+        });
+    assert_eq!(region.unwrap().size(), vec2(150.0, 150.0));
+}
+
+struct PlainViewer;
+
+impl SnarlViewer<()> for PlainViewer {
+    viewer_stubs!(());
+}
+
+#[test]
+fn transform_is_kept_in_egui_memory_per_id() {
+    let harness = Harness::new();
+    let show = |snarl: &mut Snarl<()>, id: Id| {
+        harness.frame_with(vec![], |ui| {
+            snarl.show(&mut PlainViewer, &SnarlStyle::default(), id, ui);
+        });
+    };
+
+    let id = Id::new("document-1");
+    let transform = SnarlTransform {
+        offset: vec2(-40.0, 25.0),
+        scale: 1.5,
+    };
+
+    let mut snarl = Snarl::new();
+    snarl.set_transform(id, transform);
+    show(&mut snarl, id);
+    assert_eq!(snarl.transform(id), Some(transform));
+
+    // Rebuilt Snarl restores the transform from egui memory.
+    let mut snarl = Snarl::new();
+    show(&mut snarl, id);
+    assert_eq!(snarl.transform(id), Some(transform));
+
+    // Other id starts fresh.
+    let other = Id::new("document-2");
+    show(&mut snarl, other);
+    assert_eq!(snarl.transform(other), Some(SnarlTransform::default()));
+}
+
+#[test]
+fn node_rect_follows_transform() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let node = snarl.add_node((), pos2(100.0, 40.0));
+    let transform = SnarlTransform {
+        offset: vec2(30.0, 20.0),
+        scale: 2.0,
+    };
+    snarl.set_transform(snarl_id(), transform);
+
+    let (viewport, _) = harness.frame_with(vec![], |ui| {
+        let viewport = ui.max_rect();
+        snarl.show(&mut PlainViewer, &harness.style, snarl_id(), ui);
+        viewport
+    });
+
+    let rect = snarl.node_rect(node).unwrap();
+    let expected = transform.graph_pos_to_screen(pos2(100.0, 40.0), viewport);
+    assert!(rect.min.distance(expected) < 1.0);
+}
+
+/// Two inputs and one output per node.
+struct PinsViewer;
+
+impl SnarlViewer<()> for PinsViewer {
+    viewer_stubs!((), inputs: 2, outputs: 1);
+}
+
+#[test]
+fn layout_places_pins_on_node_edges_without_ui() {
+    let mut snarl = Snarl::new();
+    let node = snarl.add_node((), pos2(100.0, 50.0));
+
+    let viewport = Rect::from_min_size(pos2(0.0, 0.0), vec2(800.0, 600.0));
+    let layout = snarl.layout(&mut PinsViewer, &SnarlStyle::default(), viewport);
+
+    let rect = layout.node_rect(node).unwrap();
+    assert_eq!(
+        rect,
+        Rect::from_min_size(pos2(100.0, 50.0), vec2(50.0, 50.0))
+    );
+
+    // Inputs are on the left edge of the node and outputs are on the right edge.
+    for input in 0..2 {
+        let pos = layout.input_pos(InPinId { node, input }).unwrap();
+        assert!(pos.x == rect.left() && rect.y_range().contains(pos.y));
+    }
+    let pos = layout.output_pos(OutPinId { node, output: 0 }).unwrap();
+    assert!(pos.x == rect.right() && rect.y_range().contains(pos.y));
+}
@@ -0,0 +1,732 @@
+#[macro_use]
+mod common;
+
+use egui::{pos2, vec2, Event, InnerResponse, Key, Modifiers, Shape, Ui, Vec2, WidgetText};
+use egui_snarl::{
+    ui::{
+        AnyPin, ConnectionError, Effects, Forbidden, InPin, OutPin, PinInfo, SnarlStyle,
+        SnarlViewer, WireDragResult,
+    },
+    AnyPinId, InPinId, NodeId, OutPinId, Snarl,
+};
+
+use common::{key, Harness};
+
+#[derive(Default)]
+struct DragViewer {
+    started: Vec<AnyPinId>,
+    ended: Vec<WireDragResult>,
+}
+
+impl SnarlViewer<()> for DragViewer {
+    fn on_wire_drag_start(&mut self, from: &AnyPin<()>) {
+        self.started.push(match from {
+            AnyPin::Out(pin) => AnyPinId::Out(pin.id),
+            AnyPin::In(pin) => AnyPinId::In(pin.id),
+        });
+    }
+
+    fn on_wire_drag_end(&mut self, result: WireDragResult) {
+        self.ended.push(result);
+    }
+
+    viewer_stubs!((), inputs: 0, outputs: 1);
+}
+
+#[test]
+fn wire_drag_start_is_followed_by_end() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let mut viewer = DragViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    let from = OutPinId { node: a, output: 0 };
+    let start = snarl.debug_layout().output_pos(from).unwrap();
+
+    // Released over the node itself, not on a pin.
+    let node_center = snarl.debug_layout().node_rect(a).unwrap().center();
+    harness.drag(&mut snarl, &mut viewer, start, node_center);
+    assert_eq!(viewer.started, [AnyPinId::Out(from)]);
+    assert_eq!(viewer.ended, [WireDragResult::Cancelled]);
+
+    // Released on empty canvas.
+    harness.drag(&mut snarl, &mut viewer, start, pos2(500.0, 400.0));
+    assert_eq!(viewer.started.len(), 2);
+    assert!(matches!(
+        viewer.ended[1],
+        WireDragResult::DroppedOnEmpty { .. }
+    ));
+}
+
+#[derive(Default)]
+struct TooltipViewer {
+    asked: Vec<InPinId>,
+}
+
+impl SnarlViewer<()> for TooltipViewer {
+    fn input_tooltip(&mut self, pin: &InPin<()>) -> Option<WidgetText> {
+        self.asked.push(pin.id);
+        Some(format!("Input {}", pin.id.input).into())
+    }
+
+    viewer_stubs!((), inputs: 2, outputs: 0);
+}
+
+#[test]
+fn tooltip_is_asked_only_for_hovered_pin() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let mut viewer = TooltipViewer::default();
+
+    harness.frame(&mut snarl, &mut viewer, vec![]);
+    assert!(viewer.asked.is_empty());
+
+    let pin = InPinId { node: a, input: 1 };
+    let pos = snarl.debug_layout().input_pos(pin).unwrap();
+    harness.frame(&mut snarl, &mut viewer, vec![Event::PointerMoved(pos)]);
+    assert_eq!(viewer.asked, [pin]);
+}
+
+/// Connects first output of `link.0` to first input of `link.1` from `show_output`.
+struct ConnectViewer {
+    link: (NodeId, NodeId),
+    connected: Vec<(OutPinId, InPinId)>,
+}
+
+impl SnarlViewer<()> for ConnectViewer {
+    fn on_connect(&mut self, from: &OutPin<()>, to: &InPin<()>) {
+        self.connected.push((from.id, to.id));
+    }
+
+    fn show_output(
+        &mut self,
+        pin: &OutPin<()>,
+        ui: &mut Ui,
+        effects: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        let (from, to) = self.link;
+        if pin.id.node == from {
+            effects.connect(pin.id, InPinId { node: to, input: 0 });
+        }
+        InnerResponse::new(PinInfo::default(), ui.label("out"))
+    }
+
+    fn show_input(
+        &mut self,
+        _: &InPin<()>,
+        ui: &mut Ui,
+        _: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        InnerResponse::new(PinInfo::default(), ui.label("in"))
+    }
+
+    fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<()>> {
+        unimplemented!()
+    }
+
+    fn size_hint(&self, _: &()) -> Vec2 {
+        vec2(50.0, 50.0)
+    }
+
+    fn title<'a>(&'a mut self, _: &'a ()) -> &'a str {
+        "Node"
+    }
+
+    fn inputs(&mut self, _: &()) -> usize {
+        1
+    }
+
+    fn outputs(&mut self, _: &()) -> usize {
+        1
+    }
+}
+
+#[test]
+fn on_connect_is_called_once_per_new_wire() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(200.0, 0.0));
+    let mut viewer = ConnectViewer {
+        link: (a, b),
+        connected: Vec::new(),
+    };
+
+    // Connection is queued every frame, but the wire is created only once.
+    for _ in 0..3 {
+        harness.frame(&mut snarl, &mut viewer, vec![]);
+    }
+    assert_eq!(
+        viewer.connected,
+        [(
+            OutPinId { node: a, output: 0 },
+            InPinId { node: b, input: 0 }
+        )]
+    );
+}
+
+/// One input and one output per node.
+struct PlainViewer;
+
+impl SnarlViewer<()> for PlainViewer {
+    viewer_stubs!((), inputs: 1, outputs: 1);
+}
+
+#[test]
+fn wire_to_culled_node_is_drawn() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let b = snarl.add_node((), pos2(300.0, 100.0));
+    let never_shown = snarl.add_node((), pos2(5000.0, 300.0));
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+    snarl.connect(
+        from,
+        InPinId {
+            node: never_shown,
+            input: 0,
+        },
+    );
+
+    harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+    let shown = snarl.debug_layout().input_pos(to).unwrap();
+
+    // Pin of culled node keeps its place relative to the node.
+    snarl.set_node_pos(b, pos2(5300.0, 100.0));
+    harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+
+    let layout = snarl.debug_layout();
+    assert_eq!(layout.wires.len(), 2);
+    let culled = layout.input_pos(to).unwrap();
+    assert!((culled - shown - vec2(5000.0, 0.0)).length() < 1.0);
+
+    // Pins of node that was never shown are spread at its left edge.
+    let pos = layout
+        .input_pos(InPinId {
+            node: never_shown,
+            input: 0,
+        })
+        .unwrap();
+    assert!(pos.x > 4000.0);
+}
+
+#[test]
+fn delete_removes_selected_wire_and_keeps_nodes() {
+    let harness = Harness::with_style(SnarlStyle::default().enable_keyboard(true));
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let b = snarl.add_node((), pos2(400.0, 100.0));
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+
+    // Select the wire by clicking its middle.
+    harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+    let (_, _, start, end) = snarl.debug_layout().wires[0];
+    harness.click(&mut snarl, &mut PlainViewer, start.lerp(end, 0.5));
+
+    snarl.select_node(a);
+    snarl.select_node(b);
+    harness.frame(&mut snarl, &mut PlainViewer, vec![key(Key::Delete)]);
+    assert_eq!(snarl.wires().count(), 0);
+    assert_eq!(snarl.nodes().count(), 2);
+
+    // Next press deletes selected nodes.
+    harness.frame(&mut snarl, &mut PlainViewer, vec![key(Key::Delete)]);
+    assert!(snarl.is_empty());
+}
+
+/// Records wires reported by `on_disconnect`.
+#[derive(Default)]
+struct DisconnectViewer {
+    disconnected: Vec<(OutPinId, InPinId)>,
+}
+
+impl SnarlViewer<()> for DisconnectViewer {
+    fn on_disconnect(&mut self, from: &OutPin<()>, to: &InPin<()>) {
+        self.disconnected.push((from.id, to.id));
+    }
+
+    viewer_stubs!((), inputs: 1, outputs: 1);
+}
+
+#[test]
+fn on_disconnect_is_called_for_wires_of_removed_node() {
+    let harness = Harness::with_style(SnarlStyle::default().enable_keyboard(true));
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 100.0));
+    let b = snarl.add_node((), pos2(200.0, 100.0));
+    let c = snarl.add_node((), pos2(400.0, 100.0));
+    let wires = [
+        (
+            OutPinId { node: a, output: 0 },
+            InPinId { node: b, input: 0 },
+        ),
+        (
+            OutPinId { node: b, output: 0 },
+            InPinId { node: c, input: 0 },
+        ),
+    ];
+    for (from, to) in wires {
+        snarl.connect(from, to);
+    }
+    let mut viewer = DisconnectViewer::default();
+
+    harness.hover(&mut snarl, &mut viewer, pos2(300.0, 500.0));
+    snarl.select_node(b);
+    let (response, _) = harness.frame(&mut snarl, &mut viewer, vec![key(Key::Delete)]);
+    assert!(!snarl.contains_node(b));
+    assert_eq!(response.disconnected, wires);
+
+    viewer.disconnected.sort_unstable();
+    assert_eq!(viewer.disconnected, wires);
+}
+
+/// Picks a node right away.
+struct PickerViewer;
+
+impl SnarlViewer<()> for PickerViewer {
+    fn node_picker(&mut self, ui: &mut Ui) -> InnerResponse<Option<()>> {
+        InnerResponse::new(Some(()), ui.label("Node"))
+    }
+
+    fn size_hint(&self, _: &()) -> Vec2 {
+        vec2(50.0, 50.0)
+    }
+
+    fn title<'a>(&'a mut self, _: &'a ()) -> &'a str {
+        "Node"
+    }
+
+    fn inputs(&mut self, _: &()) -> usize {
+        1
+    }
+
+    fn outputs(&mut self, _: &()) -> usize {
+        1
+    }
+
+    fn show_input(
+        &mut self,
+        _: &InPin<()>,
+        _: &mut Ui,
+        _: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        unimplemented!()
+    }
+
+    fn show_output(
+        &mut self,
+        _: &OutPin<()>,
+        _: &mut Ui,
+        _: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn dropped_wire_menu_connects_picked_node() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let from = OutPinId { node: a, output: 0 };
+
+    let mut effects = snarl.effects();
+    harness.frame_with(vec![], |ui| {
+        let src = AnyPin::new(&snarl, AnyPinId::Out(from));
+        PickerViewer.dropped_wire_menu(pos2(200.0, 0.0), ui, src, &mut effects);
+    });
+    snarl.apply_effects(effects);
+
+    let b = snarl.nodes().map(|(id, _)| id).find(|&id| id != a).unwrap();
+    assert_eq!(snarl.node_pos(b), Some(pos2(200.0, 0.0)));
+    assert_eq!(snarl.out_pin_remotes(from), [InPinId { node: b, input: 0 }]);
+}
+
+#[test]
+fn wire_is_not_dragged_when_not_editable() {
+    let harness = Harness::with_style(SnarlStyle::default().editable(false));
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let b = snarl.add_node((), pos2(400.0, 100.0));
+    let from = OutPinId { node: a, output: 0 };
+
+    harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+    let start = snarl.debug_layout().output_pos(from).unwrap();
+    let end = snarl
+        .debug_layout()
+        .input_pos(InPinId { node: b, input: 0 })
+        .unwrap();
+
+    // Dragging a wire between the pins does not connect them.
+    harness.drag(&mut snarl, &mut PlainViewer, start, end);
+    assert_eq!(snarl.wires().count(), 0);
+    assert_eq!(snarl.debug_layout().output_pos(from), Some(start));
+}
+
+/// Connects each output to a missing input of its own node.
+struct OutOfRangeViewer;
+
+impl SnarlViewer<()> for OutOfRangeViewer {
+    fn show_output(
+        &mut self,
+        pin: &OutPin<()>,
+        ui: &mut Ui,
+        effects: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        effects.connect(
+            pin.id,
+            InPinId {
+                node: pin.id.node,
+                input: 5,
+            },
+        );
+        InnerResponse::new(PinInfo::default(), ui.label("out"))
+    }
+
+    fn show_input(
+        &mut self,
+        _: &InPin<()>,
+        ui: &mut Ui,
+        _: &mut Effects<()>,
+    ) -> InnerResponse<PinInfo> {
+        InnerResponse::new(PinInfo::default(), ui.label("in"))
+    }
+
+    fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<()>> {
+        unimplemented!()
+    }
+
+    fn size_hint(&self, _: &()) -> Vec2 {
+        vec2(50.0, 50.0)
+    }
+
+    fn title<'a>(&'a mut self, _: &'a ()) -> &'a str {
+        "Node"
+    }
+
+    fn inputs(&mut self, _: &()) -> usize {
+        2
+    }
+
+    fn outputs(&mut self, _: &()) -> usize {
+        1
+    }
+}
+
+#[test]
+fn wire_to_missing_pin_is_not_created() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    snarl.add_node((), pos2(0.0, 0.0));
+    snarl.add_node((), pos2(200.0, 0.0));
+
+    harness.frame(&mut snarl, &mut OutOfRangeViewer, vec![]);
+    assert_eq!(snarl.wires().count(), 0);
+}
+
+#[test]
+fn fanned_out_wires_leave_output_in_different_directions() {
+    let harness = Harness::with_style(SnarlStyle::default().fan_out_wires(true));
+    let mut snarl = Snarl::new();
+    let hub = snarl.add_node((), pos2(0.0, 150.0));
+    let from = OutPinId {
+        node: hub,
+        output: 0,
+    };
+    for y in [0.0, 150.0, 300.0] {
+        let node = snarl.add_node((), pos2(300.0, y));
+        snarl.connect(from, InPinId { node, input: 0 });
+    }
+
+    // Directions in which wires leave the hub output.
+    let mut tangents = || {
+        let (_, output) = harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+        let start = snarl.debug_layout().output_pos(from).unwrap();
+        output
+            .shapes
+            .into_iter()
+            .filter_map(|clipped| match clipped.shape {
+                Shape::Path(path) if path.points.len() > 1 && path.points[0] == start => {
+                    Some((path.points[1] - path.points[0]).normalized())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let first = tangents();
+    assert_eq!(first.len(), 3);
+    for (i, a) in first.iter().enumerate() {
+        for b in &first[i + 1..] {
+            assert!((*a - *b).length() > 0.1);
+        }
+    }
+
+    // Offsets do not change between frames.
+    assert_eq!(tangents(), first);
+}
+
+#[test]
+fn shift_click_ignores_pin_of_removed_node() {
+    let harness = Harness::new();
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(100.0, 100.0));
+    let b = snarl.add_node((), pos2(400.0, 100.0));
+    let c = snarl.add_node((), pos2(100.0, 300.0));
+
+    harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+    let layout = snarl.debug_layout().clone();
+    let out_a = layout.output_pos(OutPinId { node: a, output: 0 }).unwrap();
+    let to = InPinId { node: b, input: 0 };
+    let in_b = layout.input_pos(to).unwrap();
+    let out_c = layout.output_pos(OutPinId { node: c, output: 0 }).unwrap();
+
+    // Selected pin goes away with its node.
+    harness.click(&mut snarl, &mut PlainViewer, out_a);
+    snarl.remove_node(a);
+    harness.frame(&mut snarl, &mut PlainViewer, vec![]);
+    harness.click_with(&mut snarl, &mut PlainViewer, in_b, Modifiers::SHIFT);
+    assert_eq!(snarl.wires().count(), 0);
+
+    // Input clicked instead became the selected pin.
+    harness.click_with(&mut snarl, &mut PlainViewer, out_c, Modifiers::SHIFT);
+    assert_eq!(
+        snarl.wires().collect::<Vec<_>>(),
+        [(OutPinId { node: c, output: 0 }, to)]
+    );
+}
+
+/// Rejects wires within a node and to inputs that are already driven.
+struct ReasonViewer;
+
+impl SnarlViewer<()> for ReasonViewer {
+    fn connect(
+        &mut self,
+        from: &OutPin<()>,
+        to: &InPin<()>,
+        effects: &mut Effects<()>,
+    ) -> Result<(), ConnectionError> {
+        if from.id.node == to.id.node {
+            return Err(ConnectionError::WouldCycle);
+        }
+        if !to.remotes.is_empty() {
+            return Err(ConnectionError::Custom(
+                "Input is already driven".to_owned(),
+            ));
+        }
+        effects.connect(from.id, to.id);
+        Ok(())
+    }
+
+    viewer_stubs!((), inputs: 1, outputs: 1);
+}
+
+#[test]
+fn connection_error_carries_reason() {
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(100.0, 0.0));
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(OutPinId { node: a, output: 0 }, to);
+
+    let mut effects = snarl.effects();
+    let mut connect = |from| {
+        ReasonViewer.connect(
+            &snarl.out_pin(from).unwrap(),
+            &snarl.in_pin(to).unwrap(),
+            &mut effects,
+        )
+    };
+    assert_eq!(
+        connect(OutPinId { node: b, output: 0 }),
+        Err(ConnectionError::WouldCycle)
+    );
+    let err = connect(OutPinId { node: a, output: 0 }).unwrap_err();
+    assert_eq!(err.to_string(), "Input is already driven");
+
+    // Viewers that do not have a reason may keep returning `Forbidden`.
+    assert_eq!(ConnectionError::from(Forbidden), ConnectionError::Forbidden);
+}
+
+/// Node value is the kind of all its pins, only pins of the same kind connect.
+struct KindViewer;
+
+impl SnarlViewer<u8> for KindViewer {
+    fn can_connect(&self, from: &OutPin<u8>, to: &InPin<u8>) -> bool {
+        *from.node.borrow() == *to.node.borrow()
+    }
+
+    viewer_stubs!(u8, inputs: 2, outputs: 1);
+}
+
+#[test]
+fn default_connect_rejects_pins_that_cannot_connect() {
+    let mut snarl = Snarl::new();
+    let number = snarl.add_node(0, pos2(0.0, 0.0));
+    let text = snarl.add_node(1, pos2(0.0, 100.0));
+    let sum = snarl.add_node(0, pos2(100.0, 0.0));
+
+    let to = snarl
+        .in_pin(InPinId {
+            node: sum,
+            input: 0,
+        })
+        .unwrap();
+    let from = |node| snarl.out_pin(OutPinId { node, output: 0 }).unwrap();
+    assert!(KindViewer.can_connect(&from(number), &to));
+    assert!(!KindViewer.can_connect(&from(text), &to));
+
+    // Pair is rejected without queuing effects.
+    let mut effects = snarl.effects();
+    assert!(KindViewer.connect(&from(text), &to, &mut effects).is_err());
+    snarl.apply_effects(effects);
+    assert_eq!(snarl.wires().count(), 0);
+}
+
+#[test]
+fn compatible_pins_follow_can_connect() {
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node(0, pos2(0.0, 0.0));
+    let b = snarl.add_node(1, pos2(100.0, 0.0));
+    let c = snarl.add_node(0, pos2(200.0, 0.0));
+
+    let from = AnyPinId::Out(OutPinId { node: a, output: 0 });
+    assert_eq!(
+        snarl.compatible_pins(from, &mut KindViewer),
+        [
+            AnyPinId::In(InPinId { node: a, input: 0 }),
+            AnyPinId::In(InPinId { node: a, input: 1 }),
+            AnyPinId::In(InPinId { node: c, input: 0 }),
+            AnyPinId::In(InPinId { node: c, input: 1 }),
+        ]
+    );
+
+    let from = AnyPinId::In(InPinId { node: b, input: 1 });
+    assert_eq!(
+        snarl.compatible_pins(from, &mut KindViewer),
+        [AnyPinId::Out(OutPinId { node: b, output: 0 })]
+    );
+}
+
+#[test]
+fn default_connect_replaces_wire_of_single_wire_input() {
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(0.0, 100.0));
+    let c = snarl.add_node((), pos2(100.0, 0.0));
+
+    let to = InPinId { node: c, input: 0 };
+    for node in [a, b] {
+        let from = OutPinId { node, output: 0 };
+        let mut effects = snarl.effects();
+        let result = PlainViewer.connect(
+            &snarl.out_pin(from).unwrap(),
+            &snarl.in_pin(to).unwrap(),
+            &mut effects,
+        );
+        assert!(result.is_ok());
+        snarl.apply_effects(effects);
+    }
+
+    assert_eq!(snarl.in_pin_remotes(to), [OutPinId { node: b, output: 0 }]);
+}
+
+/// One output and two inputs per node.
+struct TwoInputsViewer;
+
+impl SnarlViewer<()> for TwoInputsViewer {
+    viewer_stubs!((), inputs: 2, outputs: 1);
+}
+
+#[test]
+fn connect_many_skips_missing_pins() {
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node((), pos2(0.0, 0.0));
+    let b = snarl.add_node((), pos2(100.0, 0.0));
+    let c = snarl.add_node((), pos2(200.0, 0.0));
+
+    let out = |node| OutPinId { node, output: 0 };
+    let wires = [
+        (out(a), InPinId { node: b, input: 0 }),
+        (out(a), InPinId { node: b, input: 1 }),
+        (out(a), InPinId { node: c, input: 0 }),
+        (out(b), InPinId { node: c, input: 5 }),
+        (out(b), InPinId { node: c, input: 1 }),
+    ];
+
+    let results = snarl.connect_many(wires, &mut TwoInputsViewer);
+    assert_eq!(results, [true, true, true, false, true]);
+    assert_eq!(snarl.wires().count(), 4);
+
+    let results = snarl.disconnect_many(wires);
+    assert_eq!(results.iter().filter(|&&removed| removed).count(), 4);
+    assert_eq!(snarl.wires().count(), 0);
+}
+
+/// Node value is the number of its inputs.
+struct InputsViewer;
+
+impl SnarlViewer<usize> for InputsViewer {
+    fn inputs(&mut self, inputs: &usize) -> usize {
+        *inputs
+    }
+
+    fn outputs(&mut self, _: &usize) -> usize {
+        1
+    }
+
+    fn show_input(
+        &mut self,
+        _: &InPin<usize>,
+        ui: &mut Ui,
+        _: &mut Effects<usize>,
+    ) -> InnerResponse<PinInfo> {
+        InnerResponse::new(PinInfo::default(), ui.label("in"))
+    }
+
+    fn show_output(
+        &mut self,
+        _: &OutPin<usize>,
+        ui: &mut Ui,
+        _: &mut Effects<usize>,
+    ) -> InnerResponse<PinInfo> {
+        InnerResponse::new(PinInfo::default(), ui.label("out"))
+    }
+
+    fn node_picker(&mut self, _: &mut Ui) -> InnerResponse<Option<usize>> {
+        unimplemented!()
+    }
+
+    fn size_hint(&self, _: &usize) -> Vec2 {
+        vec2(50.0, 50.0)
+    }
+
+    fn title<'a>(&'a mut self, _: &'a usize) -> &'a str {
+        "Node"
+    }
+}
+
+#[test]
+fn prune_removes_wires_to_missing_pins() {
+    let mut snarl = Snarl::new();
+    let a = snarl.add_node(0, pos2(0.0, 0.0));
+    let b = snarl.add_node(3, pos2(100.0, 0.0));
+
+    let from = OutPinId { node: a, output: 0 };
+    snarl.connect(from, InPinId { node: b, input: 0 });
+    snarl.connect(from, InPinId { node: b, input: 2 });
+    assert_eq!(snarl.prune_invalid_wires(&mut InputsViewer), 0);
+
+    *snarl.get_node(b).unwrap().borrow_mut() = 2;
+    assert_eq!(snarl.prune_invalid_wires(&mut InputsViewer), 1);
+    assert_eq!(
+        snarl.wires().collect::<Vec<_>>(),
+        [(from, InPinId { node: b, input: 0 })]
+    );
+}